@@ -0,0 +1,367 @@
+use std::{fmt, sync::Arc};
+
+use serde_json::Value;
+
+use crate::{ChatMessage, ChatRequest, Ollama, OllamaError, Options, Tool, ToolCall};
+
+type Hook = Arc<dyn Fn(&AgentStep) + Send + Sync>;
+
+// Something the model can call: how it's described to the model and what runs when it does.
+// Closures work too, see Agent::tool.
+pub trait ToolHandler: Send + Sync {
+    fn definition(&self) -> Tool;
+
+    // The result goes back to the model as text. An Err is shown to the model as well, so it
+    // can fix its arguments or try something else.
+    fn call(&self, arguments: &Value) -> Result<String, String>;
+}
+
+struct FnTool<F> {
+    definition: Tool,
+    call: F,
+}
+
+impl<F: Fn(&Value) -> Result<String, String> + Send + Sync> ToolHandler for FnTool<F> {
+    fn definition(&self) -> Tool {
+        self.definition.clone()
+    }
+
+    fn call(&self, arguments: &Value) -> Result<String, String> {
+        (self.call)(arguments)
+    }
+}
+
+// What an agent did, passed to its on_step hooks as it happens
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentStep {
+    // The model answered in step `step`, either with tool calls or with its final answer
+    Reply {
+        step: usize,
+        message: ChatMessage,
+    },
+    // A tool the model asked for in step `step` has run
+    ToolResult {
+        step: usize,
+        call: ToolCall,
+        result: Result<String, String>,
+    },
+}
+
+// The outcome of Agent::run
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentRun {
+    // The model's last message, the one without tool calls
+    pub answer: ChatMessage,
+    // The whole conversation, including the tool calls and results and the answer
+    pub messages: Vec<ChatMessage>,
+    // How many chat requests it took
+    pub steps: usize,
+}
+
+// Runs the tool-calling loop: the model is offered the tools, the calls it asks for are run
+// and their results sent back, until it answers without calling anything. Needs a model with
+// tool support, see Version::supports_tools.
+#[derive(Clone)]
+pub struct Agent {
+    pub model: String,
+    pub options: Option<Options>,
+    // Chat requests before run gives up with StepLimit
+    pub max_steps: usize,
+    tools: Vec<Arc<dyn ToolHandler>>,
+    hooks: Vec<Hook>,
+}
+
+impl Agent {
+    pub fn new(model: impl Into<String>) -> Agent {
+        Agent {
+            model: model.into(),
+            options: None,
+            max_steps: 10,
+            tools: Vec::new(),
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn options(mut self, options: Options) -> Agent {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn max_steps(mut self, max_steps: usize) -> Agent {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    // A tool run by `call`, with the arguments the model passed
+    pub fn tool(
+        self,
+        definition: Tool,
+        call: impl Fn(&Value) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Agent {
+        self.handler(FnTool { definition, call })
+    }
+
+    // A tool taking the fields of T, see Tool::from_type. Arguments that don't deserialize
+    // are reported back to the model without calling `call`.
+    #[cfg(feature = "schemars")]
+    pub fn typed_tool<T: serde::de::DeserializeOwned + schemars::JsonSchema>(
+        self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        call: impl Fn(T) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Agent {
+        self.tool(Tool::from_type::<T>(name, description), move |arguments| {
+            let arguments = T::deserialize(arguments)
+                .map_err(|error| format!("invalid arguments: {}", error))?;
+            call(arguments)
+        })
+    }
+
+    pub fn handler(mut self, handler: impl ToolHandler + 'static) -> Agent {
+        self.tools.push(Arc::new(handler));
+        self
+    }
+
+    // Called with every step as it happens, e.g. to log or show progress
+    pub fn on_step(mut self, hook: impl Fn(&AgentStep) + Send + Sync + 'static) -> Agent {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    // run with a single user message, returning just the answer
+    pub fn ask(&self, ollama: &Ollama, prompt: impl Into<String>) -> Result<String, OllamaError> {
+        Ok(self
+            .run(ollama, vec![ChatMessage::user(prompt)])?
+            .answer
+            .content)
+    }
+
+    // Continues the conversation in `messages` until the model answers
+    pub fn run(
+        &self,
+        ollama: &Ollama,
+        mut messages: Vec<ChatMessage>,
+    ) -> Result<AgentRun, OllamaError> {
+        let definitions: Vec<Tool> = self.tools.iter().map(|tool| tool.definition()).collect();
+
+        for step in 1..=self.max_steps {
+            let mut request = ChatRequest::new(self.model.clone(), messages.clone());
+            request.options = self.options.clone();
+            if !definitions.is_empty() {
+                request.tools = Some(definitions.clone());
+            }
+            let message = ollama.chat(&request)?.message;
+            self.notify(&AgentStep::Reply {
+                step,
+                message: message.clone(),
+            });
+            let calls = message.tool_calls.clone().unwrap_or_default();
+            messages.push(message);
+
+            if calls.is_empty() {
+                return Ok(AgentRun {
+                    answer: messages.last().unwrap().clone(),
+                    messages,
+                    steps: step,
+                });
+            }
+            for call in calls {
+                let result = self.call(&call);
+                messages.push(ChatMessage::tool_result(
+                    &call,
+                    match &result {
+                        Ok(output) => output.clone(),
+                        Err(error) => format!("error: {}", error),
+                    },
+                ));
+                self.notify(&AgentStep::ToolResult { step, call, result });
+            }
+        }
+        Err(OllamaError::StepLimit(self.max_steps))
+    }
+
+    fn call(&self, call: &ToolCall) -> Result<String, String> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.definition().function.name == call.name())
+            .ok_or_else(|| format!("there is no tool called {:?}", call.name()))?;
+        tool.call(&call.function.arguments)
+    }
+
+    fn notify(&self, step: &AgentStep) {
+        for hook in &self.hooks {
+            hook(step);
+        }
+    }
+}
+
+impl fmt::Debug for Agent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tools: Vec<String> = self
+            .tools
+            .iter()
+            .map(|tool| tool.definition().function.name)
+            .collect();
+        f.debug_struct("Agent")
+            .field("model", &self.model)
+            .field("options", &self.options)
+            .field("max_steps", &self.max_steps)
+            .field("tools", &tools)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockOllama, Role};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn weather_tool() -> Tool {
+        Tool::function(
+            "get_weather",
+            "Get the current weather for a city",
+            json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"]
+            }),
+        )
+    }
+
+    fn calls(names: &[&str]) -> Value {
+        let calls: Vec<Value> = names
+            .iter()
+            .map(|name| json!({ "function": { "name": name, "arguments": { "city": "Paris" } } }))
+            .collect();
+        json!({
+            "model": "qwen3",
+            "message": { "role": "assistant", "content": "", "tool_calls": calls },
+            "done": true
+        })
+    }
+
+    #[test]
+    fn test_agent_loop() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                calls(&["get_weather", "get_time"]),
+            )
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({
+                    "model": "qwen3",
+                    "message": { "role": "assistant", "content": "Sunny, 22°C." },
+                    "done": true
+                }),
+            );
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let seen = steps.clone();
+        let agent = Agent::new("qwen3")
+            .tool(weather_tool(), |arguments| {
+                Ok(format!(
+                    "{}: sunny, 22°C",
+                    arguments["city"].as_str().unwrap()
+                ))
+            })
+            .on_step(move |step| seen.lock().unwrap().push(step.clone()));
+
+        let run = agent
+            .run(&mock.client(), vec![ChatMessage::user("Weather in Paris?")])
+            .unwrap();
+        assert_eq!(run.answer.content, "Sunny, 22°C.");
+        assert_eq!(run.steps, 2);
+        let roles: Vec<Role> = run.messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            [
+                Role::User,
+                Role::Assistant,
+                Role::Tool,
+                Role::Tool,
+                Role::Assistant
+            ]
+        );
+        assert_eq!(run.messages[2].content, "Paris: sunny, 22°C");
+        // An unknown tool is reported to the model rather than ending the run
+        assert!(
+            run.messages[3]
+                .content
+                .starts_with("error: there is no tool")
+        );
+
+        let steps = steps.lock().unwrap();
+        assert_eq!(steps.len(), 4);
+        assert!(matches!(
+            &steps[1],
+            AgentStep::ToolResult {
+                step: 1,
+                result: Ok(_),
+                ..
+            }
+        ));
+
+        let body = mock.request_json(2);
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 4);
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_typed_tool() {
+        #[derive(serde::Deserialize, schemars::JsonSchema)]
+        struct Weather {
+            city: String,
+        }
+
+        let mock = MockOllama::new()
+            .respond("POST", "/api/chat", 200, calls(&["get_weather"]))
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({
+                    "model": "qwen3",
+                    "message": { "role": "assistant", "content": "Sunny." },
+                    "done": true
+                }),
+            );
+        let agent = Agent::new("qwen3").typed_tool(
+            "get_weather",
+            "Get the current weather for a city",
+            |weather: Weather| Ok(format!("{}: sunny", weather.city)),
+        );
+        let run = agent
+            .run(&mock.client(), vec![ChatMessage::user("Weather in Paris?")])
+            .unwrap();
+        assert_eq!(run.messages[2].content, "Paris: sunny");
+
+        let tool = &agent.tools[0];
+        assert_eq!(tool.definition().function.parameters["required"][0], "city");
+        assert!(
+            tool.call(&json!({ "town": "Paris" }))
+                .unwrap_err()
+                .starts_with("invalid arguments")
+        );
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let mock = MockOllama::new().respond("POST", "/api/chat", 200, calls(&["get_weather"]));
+        let agent = Agent::new("qwen3")
+            .max_steps(3)
+            .tool(weather_tool(), |_| Ok("sunny".to_string()));
+        assert!(matches!(
+            agent.ask(&mock.client(), "Weather in Paris?"),
+            Err(OllamaError::StepLimit(3))
+        ));
+        assert_eq!(mock.requests().len(), 1 + 3);
+    }
+}
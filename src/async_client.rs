@@ -0,0 +1,343 @@
+use std::{
+    future::poll_fn,
+    io::{self, Write},
+    path::Path,
+    pin::Pin,
+};
+
+use futures_core::Stream;
+use futures_io::AsyncWrite;
+
+use crate::{
+    ChatEvent, ChatRequest, ChatResponse, Comparison, CreateRequest, EmbedRequest, EmbedResponse,
+    FewShot, GenerateRequest, GenerateResponse, GenerationStats, ModelDetails, ModelInfo, Ollama,
+    OllamaError, Progress, PullPolicy, RunningModel, Version,
+    stream::{ThreadFuture, ThreadStream, Workers},
+};
+
+// The client's API as futures and streams, usable from any async runtime:
+//
+//   let ollama = Ollama::connect("localhost", 11434)?.into_async();
+//   let reply = ollama.prompt_with("llama3".into(), "Hello".into()).await?;
+//
+// Every method runs the blocking method of the same name on a worker thread, so both clients
+// share one implementation and behave the same, retries, interceptors and metrics included.
+// Dropping a future or stream before it finishes aborts the call.
+//
+// This is not non-blocking I/O: each call holds a thread for as long as it runs, a stream
+// until it ends. The threads come from a pool shared by every AsyncOllama, 64 at most by
+// default, see set_max_threads. Calls beyond that wait for a free thread, so a server with
+// more concurrent streams than threads should raise the limit.
+#[derive(Debug, Clone)]
+pub struct AsyncOllama {
+    pub ollama: Ollama,
+}
+
+// Declares async methods that take owned copies of their arguments and run `$call` with
+// the blocking client
+macro_rules! blocking {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident($($arg:ident: $ty:ty),*) -> $output:ty = |$ollama:ident| $call:expr;
+    )*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(
+                &self,
+                $($arg: $ty),*
+            ) -> impl Future<Output = Result<$output, OllamaError>> + Send + Unpin + use<> {
+                $(let $arg = $arg.to_owned();)*
+                ThreadFuture::call(&self.ollama, move |$ollama| $call)
+            }
+        )*
+    };
+}
+
+impl Ollama {
+    pub fn into_async(self) -> AsyncOllama {
+        AsyncOllama { ollama: self }
+    }
+}
+
+impl From<Ollama> for AsyncOllama {
+    fn from(ollama: Ollama) -> AsyncOllama {
+        ollama.into_async()
+    }
+}
+
+impl AsyncOllama {
+    pub fn new(ollama: Ollama) -> AsyncOllama {
+        AsyncOllama { ollama }
+    }
+
+    // Caps the worker threads of every AsyncOllama in the process. When the cap is lowered,
+    // threads above it stop once their current call is done.
+    pub fn set_max_threads(threads: usize) {
+        Workers::global().set_max(threads);
+    }
+
+    // The blocking client behind this one
+    pub fn blocking(&self) -> &Ollama {
+        &self.ollama
+    }
+
+    blocking! {
+        fn version() -> Version = |ollama| ollama.version();
+        fn wait_until_ready(timeout: std::time::Duration) -> Version =
+            |ollama| ollama.wait_until_ready(timeout);
+
+        fn generate(request: &GenerateRequest) -> GenerateResponse =
+            |ollama| ollama.generate(&request);
+        fn prompt(prompt: String) -> String = |ollama| ollama.prompt(prompt);
+        fn prompt_with(model: String, prompt: String) -> String =
+            |ollama| ollama.prompt_with(model, prompt);
+        fn count_tokens(model: String, text: String) -> u64 =
+            |ollama| ollama.count_tokens(model, text);
+        // Fails only when the thread can't be started, each prompt has its own result
+        fn prompt_batch(
+            model: String,
+            prompts: Vec<String>,
+            concurrency: usize
+        ) -> Vec<Result<String, OllamaError>> =
+            |ollama| Ok(ollama.prompt_batch(model, prompts, concurrency));
+        fn complete_code(model: String, prefix: String, suffix: String) -> String =
+            |ollama| ollama.complete_code(model, prefix, suffix);
+        fn few_shot(model: String, few_shot: &FewShot, input: &str) -> String =
+            |ollama| ollama.few_shot(model, &few_shot, &input);
+        fn preload_model(name: String) -> () = |ollama| ollama.preload_model(name);
+        fn unload_model(name: String) -> () = |ollama| ollama.unload_model(name);
+
+        fn chat(request: &ChatRequest) -> ChatResponse = |ollama| ollama.chat(&request);
+
+        // Like prompt_batch, each model has its own result
+        fn compare_generate(
+            models: &[String],
+            request: &GenerateRequest
+        ) -> Vec<Comparison<GenerateResponse>> =
+            |ollama| Ok(ollama.compare_generate(&models, &request));
+        fn compare_chat(models: &[String], request: &ChatRequest) -> Vec<Comparison<ChatResponse>> =
+            |ollama| Ok(ollama.compare_chat(&models, &request));
+
+        fn embed(request: &EmbedRequest) -> EmbedResponse = |ollama| ollama.embed(&request);
+        fn embed_batch(request: &EmbedRequest, batch_size: usize) -> Vec<Vec<f32>> =
+            |ollama| ollama.embed_batch(&request, batch_size);
+
+        fn list_models() -> Vec<ModelInfo> = |ollama| ollama.list_models();
+        fn available_models() -> Vec<String> = |ollama| ollama.available_models();
+        fn running_models() -> Vec<RunningModel> = |ollama| ollama.running_models();
+        fn gpu_memory_in_use() -> u64 = |ollama| ollama.gpu_memory_in_use();
+        fn memory_in_use() -> u64 = |ollama| ollama.memory_in_use();
+        fn show_model(name: String) -> ModelDetails = |ollama| ollama.show_model(name);
+        fn has_model(name: String) -> bool = |ollama| ollama.has_model(name);
+
+        fn blob_exists(digest: &str) -> bool = |ollama| ollama.blob_exists(&digest);
+        fn upload_blob(digest: &str, path: &Path) -> () = |ollama| ollama.upload_blob(&digest, path);
+        fn create_blob(path: &Path) -> String = |ollama| ollama.create_blob(path);
+        fn import_gguf(path: &Path, model_name: String) -> () =
+            |ollama| ollama.import_gguf(path, model_name);
+
+        #[cfg(feature = "openai")]
+        fn chat_completion(
+            request: &crate::openai::ChatCompletionRequest
+        ) -> crate::openai::ChatCompletion = |ollama| ollama.chat_completion(&request);
+    }
+
+    // The progress callbacks of pull, push and create run on the call's thread
+    pub fn pull_model<F: FnMut(&Progress) + Send + 'static>(
+        &self,
+        name: String,
+        insecure: bool,
+        on_progress: F,
+    ) -> impl Future<Output = Result<(), OllamaError>> + Send + Unpin + use<F> {
+        ThreadFuture::call(&self.ollama, move |ollama| {
+            ollama.pull_model(name, insecure, on_progress)
+        })
+    }
+
+    pub fn push_model<F: FnMut(&Progress) + Send + 'static>(
+        &self,
+        name: String,
+        insecure: bool,
+        on_progress: F,
+    ) -> impl Future<Output = Result<(), OllamaError>> + Send + Unpin + use<F> {
+        ThreadFuture::call(&self.ollama, move |ollama| {
+            ollama.push_model(name, insecure, on_progress)
+        })
+    }
+
+    pub fn create_model<F: FnMut(&Progress) + Send + 'static>(
+        &self,
+        request: &CreateRequest,
+        on_progress: F,
+    ) -> impl Future<Output = Result<(), OllamaError>> + Send + Unpin + use<F> {
+        let request = request.clone();
+        ThreadFuture::call(&self.ollama, move |ollama| {
+            ollama.create_model(&request, on_progress)
+        })
+    }
+
+    pub fn ensure_model<F: FnMut(&Progress) + Send + 'static>(
+        &self,
+        name: String,
+        policy: PullPolicy,
+        on_progress: F,
+    ) -> impl Future<Output = Result<(), OllamaError>> + Send + Unpin + use<F> {
+        ThreadFuture::call(&self.ollama, move |ollama| {
+            ollama.ensure_model(name, policy, on_progress)
+        })
+    }
+
+    // The writing happens on the call's thread, `writer` is handed back with the stats when
+    // the reply is complete
+    pub fn prompt_to_writer<W: Write + Send + 'static>(
+        &self,
+        model: String,
+        prompt: String,
+        mut writer: W,
+    ) -> impl Future<Output = Result<(W, GenerationStats), OllamaError>> + Send + Unpin + use<W>
+    {
+        ThreadFuture::call(&self.ollama, move |ollama| {
+            let stats = ollama.prompt_to_writer(model, prompt, &mut writer)?;
+            Ok((writer, stats))
+        })
+    }
+
+    // prompt_to_writer for a writer of the caller's runtime, e.g. a socket. Each piece is
+    // written and flushed before the next is read.
+    pub async fn prompt_to_async_writer<W: AsyncWrite + Unpin>(
+        &self,
+        model: String,
+        prompt: String,
+        writer: &mut W,
+    ) -> Result<GenerationStats, OllamaError> {
+        self.generate_to_async_writer(&GenerateRequest::new(model, prompt), writer)
+            .await
+    }
+
+    pub async fn generate_to_async_writer<W: AsyncWrite + Unpin>(
+        &self,
+        request: &GenerateRequest,
+        writer: &mut W,
+    ) -> Result<GenerationStats, OllamaError> {
+        let mut stream = self.generate_stream(request);
+        let mut stats = GenerationStats::default();
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            let chunk = chunk?;
+            write_all(writer, chunk.response.as_bytes())
+                .await
+                .map_err(OllamaError::Io)?;
+            if chunk.done {
+                stats = chunk.stats;
+            }
+        }
+        Ok(stats)
+    }
+
+    pub fn generate_stream(
+        &self,
+        request: &GenerateRequest,
+    ) -> impl Stream<Item = Result<GenerateResponse, OllamaError>> + Send + Unpin + use<> {
+        self.ollama.generate_stream_async(request)
+    }
+
+    pub fn chat_stream(
+        &self,
+        request: &ChatRequest,
+    ) -> impl Stream<Item = Result<ChatResponse, OllamaError>> + Send + Unpin + use<> {
+        self.ollama.chat_stream_async(request)
+    }
+
+    pub fn chat_events(
+        &self,
+        request: &ChatRequest,
+    ) -> impl Stream<Item = Result<ChatEvent, OllamaError>> + Send + Unpin + use<> {
+        let request = request.clone();
+        ThreadStream::call(&self.ollama, move |ollama| ollama.chat_events(&request))
+    }
+
+    #[cfg(feature = "openai")]
+    pub fn chat_completion_stream(
+        &self,
+        request: &crate::openai::ChatCompletionRequest,
+    ) -> impl Stream<Item = Result<crate::openai::ChatCompletionChunk, OllamaError>> + Send + Unpin + use<>
+    {
+        let request = request.clone();
+        ThreadStream::call(&self.ollama, move |ollama| {
+            ollama.chat_completion_stream(&request)
+        })
+    }
+}
+
+async fn write_all<W: AsyncWrite + Unpin>(writer: &mut W, mut bytes: &[u8]) -> io::Result<()> {
+    while !bytes.is_empty() {
+        match poll_fn(|cx| Pin::new(&mut *writer).poll_write(cx, bytes)).await? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => bytes = &bytes[n..],
+        }
+    }
+    poll_fn(|cx| Pin::new(&mut *writer).poll_flush(cx)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ChatMessage, MockOllama,
+        stream::tests::{block_on, collect},
+    };
+    use serde_json::json;
+
+    fn mock() -> MockOllama {
+        MockOllama::new()
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "Hi", "done": true }),
+            )
+            .stream(
+                "POST",
+                "/api/chat",
+                vec![
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": "Hel" }, "done": false }),
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": "lo" }, "done": true }),
+                ],
+            )
+            .error("POST", "/api/show", 404, "model 'nope' not found")
+    }
+
+    #[test]
+    fn test_async_matches_blocking() {
+        let blocking = mock();
+        let ollama = blocking.client();
+        let expected_prompt = ollama.prompt_with("llama3", "Hello").unwrap();
+        let request = ChatRequest::new("llama3", vec![ChatMessage::user("Hi")]);
+        let expected_chat: Vec<String> = ollama
+            .chat_stream(&request)
+            .unwrap()
+            .map(|chunk| chunk.unwrap().message.content)
+            .collect();
+        let expected_error = ollama.show_model("nope").unwrap_err().to_string();
+
+        let asynchronous = mock();
+        let ollama = asynchronous.client().into_async();
+        let prompt = block_on(ollama.prompt_with("llama3".into(), "Hello".into())).unwrap();
+        assert_eq!(prompt, expected_prompt);
+        let chat: Vec<String> = collect(ollama.chat_stream(&request))
+            .into_iter()
+            .map(|chunk| chunk.unwrap().message.content)
+            .collect();
+        assert_eq!(chat, expected_chat);
+        let error = block_on(ollama.show_model("nope".into())).unwrap_err();
+        assert_eq!(error.to_string(), expected_error);
+
+        // Both clients sent the same requests
+        let sent = |mock: &MockOllama| {
+            mock.requests()
+                .into_iter()
+                .map(|r| (r.method, r.path, r.body.map(|b| format!("{:?}", b))))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sent(&asynchronous), sent(&blocking));
+    }
+}
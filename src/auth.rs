@@ -0,0 +1,73 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::base64;
+
+// Credentials sent in the Authorization header, e.g. for Ollama behind an authenticating proxy
+// Deserializes from {"bearer": token} or {"basic": {"username": .., "password": ..}}
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Auth {
+    Bearer(String),
+    Basic { username: String, password: String },
+}
+
+impl Auth {
+    pub fn bearer(token: impl Into<String>) -> Auth {
+        Auth::Bearer(token.into())
+    }
+
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Auth {
+        Auth::Basic {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    pub(crate) fn header_value(&self) -> String {
+        match self {
+            Auth::Bearer(token) => format!("Bearer {}", token),
+            Auth::Basic { username, password } => format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", username, password).as_bytes())
+            ),
+        }
+    }
+}
+
+// Keeps secrets out of logs
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Auth::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_value() {
+        assert_eq!(Auth::bearer("abc123").header_value(), "Bearer abc123");
+        assert_eq!(
+            Auth::basic("Aladdin", "open sesame").header_value(),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let debug = format!("{:?}", Auth::basic("admin", "hunter2"));
+        assert!(debug.contains("admin"));
+        assert!(!debug.contains("hunter2"));
+        assert!(!format!("{:?}", Auth::bearer("abc123")).contains("abc123"));
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    fmt,
+    io::{Error, Read},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{
+    Host, HttpTransport, Instant, OllamaError, Request, Response, Transport,
+    transport::was_delivered,
+};
+
+// How a HostPool picks the server for each request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Balance {
+    // Each server in turn
+    #[default]
+    RoundRobin,
+    // The server with the fewest unfinished requests, streamed replies count until they're
+    // read to the end or dropped
+    LeastInFlight,
+}
+
+// Spreads a client's requests over several Ollama servers, e.g. a few GPU boxes serving the
+// same models. It's a Transport, so set it up with OllamaBuilder::hosts or with_transport.
+//
+// A server that can't be reached is marked down and the request goes to the next one. Only
+// failures before the request was sent move it on, a timeout or a connection lost while
+// waiting for the reply is returned, as the server may already be running the request.
+//
+// There is no background probing: a down server is left alone for `probe_interval`, then the
+// next request tries it again. Call probe, e.g. from a timer, to bring servers back without
+// live traffic. Clones share the request counts and health.
+#[derive(Clone)]
+pub struct HostPool {
+    backends: Vec<Backend>,
+    pub balance: Balance,
+    pub probe_interval: Duration,
+    next: Arc<AtomicUsize>,
+    // The client's Authorization and custom headers, from the last request, for probes
+    headers: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+#[derive(Clone)]
+struct Backend {
+    name: String,
+    transport: Arc<dyn Transport>,
+    in_flight: Arc<AtomicUsize>,
+    // When the last attempt failed, None while the server is up
+    down_since: Arc<Mutex<Option<Instant>>>,
+}
+
+// A server of a pool as it is right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostStatus {
+    pub name: String,
+    pub in_flight: usize,
+    pub healthy: bool,
+}
+
+impl HostPool {
+    pub fn new(balance: Balance) -> HostPool {
+        HostPool {
+            backends: Vec::new(),
+            balance,
+            probe_interval: Duration::from_secs(10),
+            next: Arc::new(AtomicUsize::new(0)),
+            headers: Arc::default(),
+        }
+    }
+
+    pub fn probe_interval(mut self, interval: Duration) -> HostPool {
+        self.probe_interval = interval;
+        self
+    }
+
+    // A server reached with the built-in HTTP client and its defaults
+    pub fn host(self, host: Host) -> HostPool {
+        self.http(HttpTransport::new(host))
+    }
+
+    // A server reached with a configured HTTP client, e.g. with timeouts
+    pub fn http(self, transport: HttpTransport) -> HostPool {
+        let name = format!(
+            "{}://{}:{}{}",
+            transport.scheme, transport.host, transport.port, transport.base_path
+        );
+        self.transport(name, transport)
+    }
+
+    // Any transport as one of the servers, `name` identifies it in status
+    pub fn transport(
+        mut self,
+        name: impl Into<String>,
+        transport: impl Transport + 'static,
+    ) -> HostPool {
+        self.backends.push(Backend {
+            name: name.into(),
+            transport: Arc::new(transport),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            down_since: Arc::new(Mutex::new(None)),
+        });
+        self
+    }
+
+    pub fn status(&self) -> Vec<HostStatus> {
+        self.backends
+            .iter()
+            .map(|backend| HostStatus {
+                name: backend.name.clone(),
+                in_flight: backend.in_flight.load(Ordering::SeqCst),
+                healthy: backend.down_since.lock().unwrap().is_none(),
+            })
+            .collect()
+    }
+
+    // Asks every server that's down for its version right away and marks those that answer
+    // as up, e.g. from a timer instead of waiting for probe_interval. Sent with the client's
+    // headers, so an authenticating proxy lets it through.
+    pub fn probe(&self) {
+        let request = Request {
+            method: "GET".to_string(),
+            path: "/api/version".to_string(),
+            headers: self.headers.lock().unwrap().clone(),
+            body: None,
+            cancel: None,
+        };
+        for backend in &self.backends {
+            if backend.down_since.lock().unwrap().is_some() {
+                let up = backend
+                    .transport
+                    .send(&request)
+                    .is_ok_and(|response| response.status == 200);
+                backend.mark(up);
+            }
+        }
+    }
+
+    // The servers in the order to try them: by the balancing strategy, with the ones that are
+    // down and not due for another try last
+    fn candidates(&self) -> Vec<&Backend> {
+        let count = self.backends.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut order: Vec<&Backend> = (0..count)
+            .map(|i| &self.backends[(start + i) % count])
+            .collect();
+        // Sorts are stable, so ties keep the round-robin order and idle servers share the load
+        if self.balance == Balance::LeastInFlight {
+            order.sort_by_key(|backend| backend.in_flight.load(Ordering::SeqCst));
+        }
+        order.sort_by_key(|backend| !backend.available(self.probe_interval));
+        order
+    }
+}
+
+impl Backend {
+    fn available(&self, probe_interval: Duration) -> bool {
+        match *self.down_since.lock().unwrap() {
+            None => true,
+            Some(since) => since.elapsed() >= probe_interval,
+        }
+    }
+
+    fn mark(&self, up: bool) {
+        *self.down_since.lock().unwrap() = (!up).then(Instant::now);
+    }
+}
+
+impl Transport for HostPool {
+    // Every server gets at most one try per request, the last error is returned when none of
+    // them answered
+    fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+        request
+            .headers
+            .clone_into(&mut self.headers.lock().unwrap());
+        let mut last_error = OllamaError::Connection(Error::new(
+            std::io::ErrorKind::NotConnected,
+            "the host pool has no hosts",
+        ));
+        for backend in self.candidates() {
+            backend.in_flight.fetch_add(1, Ordering::SeqCst);
+            let counted = Counted(backend.in_flight.clone());
+            match backend.transport.send(request) {
+                Ok(response) => {
+                    backend.mark(true);
+                    return Ok(Response::new(
+                        response.status,
+                        Held {
+                            body: response.into_body(),
+                            _counted: counted,
+                        },
+                    ));
+                }
+                Err(OllamaError::Connection(error))
+                    if !request.cancel.as_ref().is_some_and(|c| c.is_cancelled()) =>
+                {
+                    backend.mark(false);
+                    if was_delivered(&error) {
+                        return Err(OllamaError::Connection(error));
+                    }
+                    last_error = OllamaError::Connection(error);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Err(last_error)
+    }
+}
+
+// Two pools are equal when they are clones of each other
+impl PartialEq for HostPool {
+    fn eq(&self, other: &HostPool) -> bool {
+        Arc::ptr_eq(&self.next, &other.next)
+    }
+}
+
+impl fmt::Debug for HostPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostPool")
+            .field("balance", &self.balance)
+            .field("hosts", &self.status())
+            .finish()
+    }
+}
+
+// One unfinished request to a server, until dropped
+struct Counted(Arc<AtomicUsize>);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Held {
+    body: Box<dyn Read + Send>,
+    _counted: Counted,
+}
+
+impl Read for Held {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.body.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockOllama, Ollama};
+
+    fn request() -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: "/api/version".to_string(),
+            headers: Vec::new(),
+            body: None,
+            cancel: None,
+        }
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let (a, b) = (MockOllama::new(), MockOllama::new());
+        let pool = HostPool::new(Balance::RoundRobin)
+            .transport("a", a.clone())
+            .transport("b", b.clone());
+        let ollama = Ollama::connect_transport(pool).unwrap();
+        for _ in 0..3 {
+            ollama.version().unwrap();
+        }
+        assert_eq!(a.requests().len(), 2);
+        assert_eq!(b.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_least_in_flight() {
+        let (a, b) = (MockOllama::new(), MockOllama::new());
+        let pool = HostPool::new(Balance::LeastInFlight)
+            .transport("a", a.clone())
+            .transport("b", b.clone());
+
+        // Held open like an unfinished stream, so every request after it avoids its server
+        let open = pool.send(&request()).unwrap();
+        let busy = pool
+            .status()
+            .into_iter()
+            .find(|host| host.in_flight == 1)
+            .unwrap()
+            .name;
+        for _ in 0..3 {
+            pool.send(&request()).unwrap();
+        }
+        let busy_mock = if busy == "a" { &a } else { &b };
+        assert_eq!(busy_mock.requests().len(), 1);
+
+        drop(open);
+        assert!(pool.status().iter().all(|host| host.in_flight == 0));
+    }
+
+    // Refuses connections until switched on
+    #[derive(Clone, Default)]
+    struct Flaky {
+        up: Arc<std::sync::atomic::AtomicBool>,
+        calls: Arc<AtomicUsize>,
+        // Sent with the last call
+        headers: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl Transport for Flaky {
+        fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            *self.headers.lock().unwrap() = request.headers.clone();
+            if !self.up.load(Ordering::SeqCst) {
+                return Err(OllamaError::Connection(Error::new(
+                    std::io::ErrorKind::ConnectionRefused,
+                    "refused",
+                )));
+            }
+            MockOllama::new().send(request)
+        }
+    }
+
+    #[test]
+    fn test_failover() {
+        let (flaky, mock) = (Flaky::default(), MockOllama::new());
+        let pool = HostPool::new(Balance::RoundRobin)
+            .probe_interval(Duration::from_secs(3600))
+            .transport("flaky", flaky.clone())
+            .transport("mock", mock.clone());
+
+        let mut authed = request();
+        authed.headers = vec![("Authorization".to_string(), "Bearer abc".to_string())];
+        for _ in 0..4 {
+            assert_eq!(pool.send(&authed).unwrap().status, 200);
+        }
+        // Tried once, then skipped while it's down
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(mock.requests().len(), 4);
+        assert!(!pool.status()[0].healthy);
+
+        // Probes carry the client's headers
+        pool.probe();
+        assert!(!pool.status()[0].healthy);
+        assert_eq!(*flaky.headers.lock().unwrap(), authed.headers);
+        flaky.up.store(true, Ordering::SeqCst);
+        pool.probe();
+        assert!(pool.status().iter().all(|host| host.healthy));
+
+        // With no wait between tries, a down server gets every other request again
+        flaky.up.store(false, Ordering::SeqCst);
+        let pool = pool.probe_interval(Duration::ZERO);
+        let before = flaky.calls.load(Ordering::SeqCst);
+        for _ in 0..4 {
+            pool.send(&request()).unwrap();
+        }
+        assert!(flaky.calls.load(Ordering::SeqCst) - before >= 2);
+
+        // Nothing answers
+        let pool = HostPool::new(Balance::LeastInFlight).transport("flaky", flaky);
+        assert!(matches!(
+            pool.send(&request()),
+            Err(OllamaError::Connection(_))
+        ));
+    }
+
+    // Times out every request
+    struct Slow;
+
+    impl Transport for Slow {
+        fn send(&self, _: &Request) -> Result<Response, OllamaError> {
+            Err(OllamaError::Timeout)
+        }
+    }
+
+    #[test]
+    fn test_no_failover_once_sent() {
+        let mock = MockOllama::new();
+        let pool = HostPool::new(Balance::RoundRobin)
+            .transport("slow", Slow)
+            .transport("mock", mock.clone());
+        assert!(matches!(pool.send(&request()), Err(OllamaError::Timeout)));
+        assert!(mock.requests().is_empty());
+
+        // The server read the request and hung up without a reply
+        let (ollama, server) = crate::tests::serve(vec![
+            crate::tests::json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            String::new(),
+        ]);
+        let pool = HostPool::new(Balance::RoundRobin)
+            .http(ollama.http_transport())
+            .transport("mock", mock.clone());
+        assert!(matches!(
+            pool.send(&request()),
+            Err(OllamaError::Connection(_))
+        ));
+        server.join().unwrap();
+        assert!(mock.requests().is_empty());
+        assert!(!pool.status()[0].healthy);
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        let pool = HostPool::new(Balance::RoundRobin);
+        assert!(matches!(
+            pool.send(&request()),
+            Err(OllamaError::Connection(_))
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use crate::{GenerateRequest, GenerationStats, Ollama, OllamaError, Options};
+
+// How Ollama::benchmark runs the prompts
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkConfig {
+    // Times each prompt is sent
+    pub runs: usize,
+    // Unloads the model first, so the report's load_duration is a load from disk
+    pub cold_start: bool,
+    // E.g. num_predict, to keep every run the same length
+    pub options: Option<Options>,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> BenchmarkConfig {
+        BenchmarkConfig {
+            runs: 3,
+            cold_start: true,
+            options: None,
+        }
+    }
+}
+
+impl BenchmarkConfig {
+    pub fn runs(mut self, runs: usize) -> BenchmarkConfig {
+        self.runs = runs.max(1);
+        self
+    }
+
+    pub fn cold_start(mut self, cold_start: bool) -> BenchmarkConfig {
+        self.cold_start = cold_start;
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> BenchmarkConfig {
+        self.options = Some(options);
+        self
+    }
+}
+
+// The spread of a speed over a benchmark's runs, in tokens per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Throughput {
+    fn of(rates: impl Iterator<Item = Option<f64>>) -> Option<Throughput> {
+        let rates: Vec<f64> = rates.flatten().collect();
+        if rates.is_empty() {
+            return None;
+        }
+        Some(Throughput {
+            mean: rates.iter().sum::<f64>() / rates.len() as f64,
+            min: rates.iter().copied().fold(f64::INFINITY, f64::min),
+            max: rates.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkReport {
+    pub model: String,
+    // What the first run spent loading the model, zero when it was already in memory
+    pub load_duration: Duration,
+    // The server's stats for every run, prompt by prompt
+    pub runs: Vec<GenerationStats>,
+}
+
+impl BenchmarkReport {
+    pub fn tokens_per_second(&self) -> Option<Throughput> {
+        Throughput::of(self.runs.iter().map(GenerationStats::tokens_per_second))
+    }
+
+    // The server reuses the cached prompt of the previous run, so repeats of a prompt only
+    // evaluate the part that changed. Use several different prompts for a fair number.
+    pub fn prompt_tokens_per_second(&self) -> Option<Throughput> {
+        Throughput::of(
+            self.runs
+                .iter()
+                .map(GenerationStats::prompt_tokens_per_second),
+        )
+    }
+
+    pub fn total_duration(&self) -> Duration {
+        self.runs.iter().map(|run| run.total_duration).sum()
+    }
+}
+
+impl Ollama {
+    // Sends each prompt `config.runs` times and collects the server's timings, e.g. to compare
+    // quantizations of a model on this machine. Stops at the first failed run.
+    pub fn benchmark(
+        &self,
+        model: impl Into<String>,
+        prompts: &[impl AsRef<str>],
+        config: &BenchmarkConfig,
+    ) -> Result<BenchmarkReport, OllamaError> {
+        let model = model.into();
+        // Cached replies would measure nothing
+        let ollama = Ollama {
+            cache: None,
+            ..self.clone()
+        };
+        if config.cold_start {
+            ollama.unload_model(&model)?;
+        }
+
+        let mut runs = Vec::new();
+        for prompt in prompts {
+            for _ in 0..config.runs {
+                let mut request = GenerateRequest::new(model.clone(), prompt.as_ref());
+                request.options = config.options.clone();
+                runs.push(ollama.generate(&request)?.stats);
+            }
+        }
+        Ok(BenchmarkReport {
+            load_duration: runs
+                .first()
+                .map(|run| run.load_duration)
+                .unwrap_or_default(),
+            model,
+            runs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockOllama;
+    use serde_json::{Value, json};
+
+    fn reply(load_ms: u64, eval_count: u64) -> Value {
+        json!({
+            "model": "llama3", "response": "...", "done": true,
+            "load_duration": load_ms * 1_000_000,
+            "prompt_eval_count": 10, "prompt_eval_duration": 100_000_000,
+            "eval_count": eval_count, "eval_duration": 1_000_000_000,
+            "total_duration": 2_000_000_000
+        })
+    }
+
+    #[test]
+    fn test_benchmark() {
+        let mock = MockOllama::new()
+            // The unload
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "", "done": true }),
+            )
+            .respond("POST", "/api/generate", 200, reply(1500, 40))
+            .respond("POST", "/api/generate", 200, reply(0, 60));
+        let config = BenchmarkConfig::default()
+            .runs(2)
+            .options(Options::default().num_predict(64));
+
+        let report = mock
+            .client()
+            .benchmark("llama3", &["Hi", "Why is the sky blue?"], &config)
+            .unwrap();
+        assert_eq!(report.runs.len(), 4);
+        assert_eq!(report.load_duration, Duration::from_millis(1500));
+        assert_eq!(report.total_duration(), Duration::from_secs(8));
+        let speed = report.tokens_per_second().unwrap();
+        assert_eq!((speed.min, speed.max, speed.mean), (40.0, 60.0, 55.0));
+        assert_eq!(report.prompt_tokens_per_second().unwrap().mean, 100.0);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1 + 1 + 4);
+        let body = mock.request_json(1);
+        assert_eq!(body["keep_alive"], 0);
+        let body = mock.request_json(2);
+        assert_eq!(body["options"]["num_predict"], 64);
+    }
+}
@@ -0,0 +1,133 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use crate::{Body, CreateRequest, IntoModelName, Ollama, OllamaError, sha256};
+
+// The digest the server files a blob under, "sha256:<hex>"
+pub fn blob_digest(path: impl AsRef<Path>) -> Result<String, OllamaError> {
+    let file = File::open(path).map_err(OllamaError::Io)?;
+    let hex = sha256::hex_digest(BufReader::new(file)).map_err(OllamaError::Io)?;
+    Ok(format!("sha256:{}", hex))
+}
+
+impl Ollama {
+    pub fn blob_exists(&self, digest: &str) -> Result<bool, OllamaError> {
+        match self.send("HEAD", &format!("/api/blobs/{}", digest), None) {
+            Ok(_) => Ok(true),
+            Err(OllamaError::Http { status: 404, .. }) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    // The server checks the upload against `digest` and rejects it on a mismatch
+    pub fn upload_blob(&self, digest: &str, path: impl AsRef<Path>) -> Result<(), OllamaError> {
+        let body = Body::File(path.as_ref().to_path_buf());
+        self.send("POST", &format!("/api/blobs/{}", digest), Some(body))?;
+        Ok(())
+    }
+
+    // Uploads the file unless the server already has it, returning its digest
+    pub fn create_blob(&self, path: impl AsRef<Path>) -> Result<String, OllamaError> {
+        let digest = blob_digest(&path)?;
+        if !self.blob_exists(&digest)? {
+            self.upload_blob(&digest, &path)?;
+        }
+        Ok(digest)
+    }
+
+    // Creates `model_name` from a local GGUF file
+    pub fn import_gguf(
+        &self,
+        path: impl AsRef<Path>,
+        model_name: impl IntoModelName,
+    ) -> Result<(), OllamaError> {
+        let model_name = model_name.into_model_name()?;
+        let path = path.as_ref();
+        let digest = self.create_blob(path)?;
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "model.gguf".to_string());
+        self.create_model(
+            &CreateRequest::new(model_name.to_string()).file(file_name, digest),
+            |_| {},
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockOllama;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_import_gguf() {
+        let path = std::env::temp_dir().join(format!("ollama-rs-{}.gguf", std::process::id()));
+        std::fs::write(&path, b"GGUF").unwrap();
+        let digest = blob_digest(&path).unwrap();
+        assert_eq!(
+            digest,
+            "sha256:b83633aa785344791618f2fddf131b010ea04912a60430760b070bad293f65bd"
+        );
+        let blob_path = format!("/api/blobs/{}", digest);
+
+        let mock = MockOllama::new()
+            .error("HEAD", &blob_path, 404, "")
+            .respond("POST", &blob_path, 201, Value::Null)
+            .stream(
+                "POST",
+                "/api/create",
+                vec![
+                    json!({ "status": "parsing GGUF" }),
+                    json!({ "status": "success" }),
+                ],
+            );
+        let server = mock.listen().unwrap();
+        server
+            .client()
+            .import_gguf(&path, "my-model".to_string())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let requests = mock.requests();
+        let calls: Vec<(&str, &str)> = requests
+            .iter()
+            .map(|r| (r.method.as_str(), r.path.as_str()))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                ("GET", "/api/version"),
+                ("HEAD", blob_path.as_str()),
+                ("POST", blob_path.as_str()),
+                ("POST", "/api/create")
+            ]
+        );
+
+        let body = mock.request_json(3);
+        assert_eq!(body["model"], "my-model:latest");
+        assert_eq!(
+            body["files"][path.file_name().unwrap().to_str().unwrap()],
+            digest
+        );
+    }
+
+    #[test]
+    fn test_existing_blob_is_not_uploaded() {
+        let path = std::env::temp_dir().join(format!("ollama-rs-{}-dup.gguf", std::process::id()));
+        std::fs::write(&path, b"GGUF").unwrap();
+        let digest = blob_digest(&path).unwrap();
+
+        let mock =
+            MockOllama::new().respond("HEAD", &format!("/api/blobs/{}", digest), 200, Value::Null);
+        assert_eq!(mock.client().create_blob(&path).unwrap(), digest);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mock.requests().len(), 2);
+
+        assert!(matches!(
+            blob_digest("/nonexistent/model.gguf"),
+            Err(OllamaError::Io(_))
+        ));
+    }
+}
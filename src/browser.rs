@@ -0,0 +1,68 @@
+use std::io::{Cursor, Error};
+
+use web_sys::{XmlHttpRequest, wasm_bindgen::JsValue};
+
+use crate::{Body, OllamaError, Request, Response, Transport};
+
+// Talks to Ollama from a web page, for clients compiled to wasm32-unknown-unknown:
+//
+//   let ollama = Ollama::connect_transport(BrowserTransport::new("http://localhost:11434"))?;
+//
+// Transport is blocking and fetch() has no blocking form, so this uses a synchronous
+// XMLHttpRequest. That works best from a web worker, browsers warn about it on the main
+// thread. Streamed replies arrive all at once when the response is complete. The server has
+// to allow the page's origin through OLLAMA_ORIGINS.
+//
+// Nothing can sleep in the browser either, so with_retry retries without a backoff, a
+// RateLimiter fails the requests it would hold back with InvalidRequest, and
+// wait_until_ready gives up after its first try.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserTransport {
+    // e.g. "http://localhost:11434", without a trailing slash
+    pub base_url: String,
+}
+
+impl BrowserTransport {
+    pub fn new(base_url: impl Into<String>) -> BrowserTransport {
+        BrowserTransport {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl Transport for BrowserTransport {
+    fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+        let xhr = XmlHttpRequest::new().map_err(js_error)?;
+        let url = format!("{}{}", self.base_url, request.path);
+        xhr.open_with_async(&request.method, &url, false)
+            .map_err(js_error)?;
+        for (name, value) in &request.headers {
+            xhr.set_request_header(name, value).map_err(js_error)?;
+        }
+
+        match &request.body {
+            None => xhr.send(),
+            Some(Body::Json(json)) => {
+                xhr.set_request_header("Content-Type", "application/json")
+                    .map_err(js_error)?;
+                xhr.send_with_opt_u8_array(Some(json))
+            }
+            Some(Body::File(_)) => {
+                return Err(OllamaError::Io(Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "file uploads are not supported in the browser",
+                )));
+            }
+        }
+        .map_err(js_error)?;
+
+        let status = xhr.status().map_err(js_error)?;
+        let text = xhr.response_text().map_err(js_error)?.unwrap_or_default();
+        Ok(Response::new(status, Cursor::new(text.into_bytes())))
+    }
+}
+
+// Network failures, CORS rejections included, surface as exceptions
+fn js_error(error: JsValue) -> OllamaError {
+    OllamaError::Connection(Error::other(format!("{:?}", error)))
+}
@@ -0,0 +1,242 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    Auth, Balance, Host, HostPool, HttpTransport, KeepAlive, Ollama, OllamaError, RetryPolicy,
+    ServerHandle, ServerOptions, Timeouts, Transport,
+};
+
+// Sets up a client before connecting:
+//
+//   let ollama = Ollama::builder().host("gpu-box").port(11434).build()?;
+//
+// Nothing is started unless asked for with auto_start, building only checks that the server
+// answers.
+#[derive(Debug, Clone)]
+pub struct OllamaBuilder {
+    // OLLAMA_HOST, or localhost:11434 when it's not set
+    pub host: Host,
+    pub timeouts: Timeouts,
+    pub retry: Option<RetryPolicy>,
+    pub auth: Option<Auth>,
+    pub headers: Vec<(String, String)>,
+    // Replaces the built-in HTTP client, the host is then unused
+    pub transport: Option<Arc<dyn Transport>>,
+    // Several servers to spread requests over instead of `host`, see HostPool
+    pub hosts: Vec<Host>,
+    pub balance: Balance,
+    // How a local daemon is started when nothing answers, `spawn` is off by default
+    pub server: ServerOptions,
+    pub default_model: Option<String>,
+    pub keep_alive: Option<KeepAlive>,
+}
+
+impl Default for OllamaBuilder {
+    fn default() -> OllamaBuilder {
+        OllamaBuilder {
+            host: Host::from_env().unwrap_or_default(),
+            timeouts: Timeouts::default(),
+            retry: None,
+            auth: None,
+            headers: Vec::new(),
+            transport: None,
+            hosts: Vec::new(),
+            balance: Balance::default(),
+            server: ServerOptions::default().spawn(false),
+            default_model: None,
+            keep_alive: None,
+        }
+    }
+}
+
+impl OllamaBuilder {
+    pub fn new() -> OllamaBuilder {
+        OllamaBuilder::default()
+    }
+
+    // The host name or address, keeping the scheme and port
+    pub fn host(mut self, host: impl Into<String>) -> OllamaBuilder {
+        self.host.host = host.into();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> OllamaBuilder {
+        self.host.port = port;
+        self
+    }
+
+    // Anything OLLAMA_HOST accepts, e.g. "https://ollama.example.com" or "unix:///run/ollama.sock"
+    pub fn url(mut self, url: &str) -> OllamaBuilder {
+        self.host = Host::parse(url);
+        self
+    }
+
+    // Spreads requests over these servers. Local auto_start doesn't apply to them.
+    pub fn hosts(
+        mut self,
+        hosts: impl IntoIterator<Item = Host>,
+        balance: Balance,
+    ) -> OllamaBuilder {
+        self.hosts = hosts.into_iter().collect();
+        self.balance = balance;
+        self
+    }
+
+    // Start `ollama serve` when nothing answers, see ServerOptions for how
+    pub fn auto_start(mut self, auto_start: bool) -> OllamaBuilder {
+        self.server.spawn = auto_start;
+        self
+    }
+
+    // Replaces the server options, auto_start included
+    pub fn server(mut self, server: ServerOptions) -> OllamaBuilder {
+        self.server = server;
+        self
+    }
+
+    // The same limit for connecting, each read and each write
+    pub fn timeout(mut self, timeout: Duration) -> OllamaBuilder {
+        self.timeouts = Timeouts::default()
+            .connect(timeout)
+            .read(timeout)
+            .write(timeout);
+        self
+    }
+
+    pub fn timeouts(mut self, timeouts: Timeouts) -> OllamaBuilder {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> OllamaBuilder {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn auth(mut self, auth: Auth) -> OllamaBuilder {
+        self.auth = Some(auth);
+        self
+    }
+
+    // Replaces any header of the same name, like Ollama::with_header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> OllamaBuilder {
+        let name = name.into();
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    // See Ollama::with_default_model
+    pub fn default_model(mut self, model: impl Into<String>) -> OllamaBuilder {
+        self.default_model = Some(model.into());
+        self
+    }
+
+    // See Ollama::with_keep_alive
+    pub fn keep_alive(mut self, keep_alive: KeepAlive) -> OllamaBuilder {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    pub fn transport(mut self, transport: impl Transport + 'static) -> OllamaBuilder {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    // Connects, starting the daemon first if auto_start is on and nothing answers
+    pub fn build(self) -> Result<Ollama, OllamaError> {
+        let mut ollama = Ollama::unconnected(self.host);
+        ollama.timeouts = self.timeouts;
+        ollama.retry = self.retry;
+        ollama.auth = self.auth;
+        ollama.headers = self.headers;
+        ollama.transport = self.transport;
+        if !self.hosts.is_empty() {
+            let mut pool = HostPool::new(self.balance);
+            for host in self.hosts {
+                pool = pool.http(HttpTransport {
+                    timeouts: self.timeouts,
+                    ..HttpTransport::new(host)
+                });
+            }
+            ollama.transport = Some(Arc::new(pool));
+        }
+        ollama.default_model = self.default_model;
+        ollama.keep_alive = self.keep_alive;
+
+        match ollama.version() {
+            Ok(version) => {
+                ollama.version = version;
+                ollama.server = Some(Arc::new(ServerHandle::external()));
+            }
+            Err(error) if !self.server.spawn || ollama.transport.is_some() => return Err(error),
+            Err(_) => {
+                ollama.server = Some(Arc::new(ServerHandle::spawn(&self.server)?));
+                // A freshly spawned daemon takes a moment before it accepts connections
+                ollama.version = ollama.wait_until_ready(self.server.ready_timeout)?;
+            }
+        }
+        Ok(ollama)
+    }
+}
+
+impl Ollama {
+    pub fn builder() -> OllamaBuilder {
+        OllamaBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockOllama;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_builder_settings() {
+        let builder = OllamaBuilder::new()
+            .url("https://ollama.example.com")
+            .host("gpu-box")
+            .port(8443)
+            .timeout(Duration::from_secs(5))
+            .header("X-Tenant", "acme")
+            .header("x-tenant", "globex");
+        assert_eq!(
+            builder.host,
+            Host {
+                scheme: "https".to_string(),
+                host: "gpu-box".to_string(),
+                port: 8443,
+                base_path: String::new()
+            }
+        );
+        assert_eq!(builder.timeouts.read, Some(Duration::from_secs(5)));
+        assert_eq!(
+            builder.headers,
+            [("x-tenant".to_string(), "globex".to_string())]
+        );
+        assert!(!builder.server.spawn);
+        assert!(builder.auto_start(true).server.spawn);
+    }
+
+    #[test]
+    fn test_build_does_not_start_a_server() {
+        let mock = MockOllama::new().version("0.6.2");
+        let ollama = Ollama::builder()
+            .transport(mock.clone())
+            .auth(Auth::bearer("secret"))
+            .build()
+            .unwrap();
+        assert_eq!(ollama.version.to_string(), "0.6.2");
+        assert!(!ollama.server.as_ref().unwrap().spawned());
+
+        // Nothing listens on port 1 and nothing gets spawned in its place
+        let error = Ollama::builder()
+            .host("127.0.0.1")
+            .port(1)
+            .server(ServerOptions::default().program(PathBuf::from("/nonexistent/ollama")))
+            .auto_start(false)
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, OllamaError::Connection(_)), "{:?}", error);
+    }
+}
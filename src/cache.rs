@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{Ollama, OllamaError, Options, sha256};
+
+// Where a ResponseCache keeps the replies, keyed by a hash of the request. Implement it to
+// share a cache between processes, e.g. on disk or in Redis.
+pub trait CacheBackend: Send + Sync {
+    fn get(&self, key: &str) -> Option<String>;
+    fn put(&self, key: &str, response: String);
+}
+
+// Keeps the `capacity` most recently used replies in memory
+pub struct LruCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    // Each reply with the tick it was last used at
+    entries: HashMap<String, (String, u64)>,
+    by_use: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl LruCache {
+    pub fn new(capacity: usize) -> LruCache {
+        LruCache {
+            capacity: capacity.max(1),
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str, old_tick: u64) -> u64 {
+        self.tick += 1;
+        self.by_use.remove(&old_tick);
+        self.by_use.insert(self.tick, key.to_string());
+        self.tick
+    }
+}
+
+impl CacheBackend for LruCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        let (response, used) = state.entries.get(key).cloned()?;
+        let tick = state.touch(key, used);
+        state.entries.get_mut(key)?.1 = tick;
+        Some(response)
+    }
+
+    fn put(&self, key: &str, response: String) {
+        let mut state = self.state.lock().unwrap();
+        let used = state.entries.get(key).map_or(0, |(_, used)| *used);
+        let tick = state.touch(key, used);
+        state.entries.insert(key.to_string(), (response, tick));
+        while state.entries.len() > self.capacity {
+            let Some((_, oldest)) = state.by_use.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+    }
+}
+
+// Answers repeated generate and chat calls without asking the server, for evaluation runs
+// that send the same prompts over and over. A request is the same when its model, prompt or
+// messages, options and every other field match. Streamed calls are never cached.
+//
+// By default only deterministic requests are cached, see Options::is_deterministic, since
+// anything else is expected to vary. Clones share the cache and its counts.
+#[derive(Clone)]
+pub struct ResponseCache {
+    backend: Arc<dyn CacheBackend>,
+    pub deterministic_only: bool,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl ResponseCache {
+    pub fn new(backend: impl CacheBackend + 'static) -> ResponseCache {
+        ResponseCache {
+            backend: Arc::new(backend),
+            deterministic_only: true,
+            hits: Arc::default(),
+            misses: Arc::default(),
+        }
+    }
+
+    pub fn in_memory(capacity: usize) -> ResponseCache {
+        ResponseCache::new(LruCache::new(capacity))
+    }
+
+    // Also caches requests that sample, so a repeat gets the first reply instead of a new one
+    pub fn deterministic_only(mut self, deterministic_only: bool) -> ResponseCache {
+        self.deterministic_only = deterministic_only;
+        self
+    }
+
+    // Calls answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    // Cacheable calls that went to the server
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    // None when the request isn't cached
+    fn key(&self, path: &str, body: &Value) -> Option<String> {
+        let deterministic =
+            Options::deserialize(&body["options"]).is_ok_and(|options| options.is_deterministic());
+        if self.deterministic_only && !deterministic {
+            return None;
+        }
+        // How long the model stays loaded doesn't change the reply
+        let mut body = body.clone();
+        if let Some(fields) = body.as_object_mut() {
+            fields.remove("keep_alive");
+        }
+        // Objects serialize with their keys sorted, so equal requests give equal keys
+        let text = format!("{}\n{}", path, body);
+        sha256::hex_digest(text.as_bytes()).ok()
+    }
+}
+
+// Two caches are equal when they are clones of each other
+impl PartialEq for ResponseCache {
+    fn eq(&self, other: &ResponseCache) -> bool {
+        Arc::ptr_eq(&self.hits, &other.hits)
+    }
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("deterministic_only", &self.deterministic_only)
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish()
+    }
+}
+
+impl Ollama {
+    // A copy of this client whose generate and chat calls go through `cache`
+    pub fn with_cache(&self, cache: ResponseCache) -> Ollama {
+        Ollama {
+            cache: Some(cache),
+            ..self.clone()
+        }
+    }
+
+    // post_json, answered from the client's cache when it holds the reply
+    pub(crate) fn post_json_cached<B: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<R, OllamaError> {
+        let Some(cache) = &self.cache else {
+            return self.post_json(path, body);
+        };
+        let body = serde_json::to_value(body)?;
+        let Some(key) = cache.key(path, &body) else {
+            return self.post_json(path, &body);
+        };
+        if let Some(text) = cache.backend.get(&key) {
+            cache.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(serde_json::from_str(&text)?);
+        }
+
+        cache.misses.fetch_add(1, Ordering::Relaxed);
+        let value: Value = self.post_json(path, &body)?;
+        let response = R::deserialize(&value)?;
+        cache.backend.put(&key, value.to_string());
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatMessage, ChatRequest, GenerateRequest, MockOllama};
+    use serde_json::json;
+
+    #[test]
+    fn test_lru_cache() {
+        let cache = LruCache::new(2);
+        cache.put("a", "1".to_string());
+        cache.put("b", "2".to_string());
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        // "b" is now the least recently used
+        cache.put("c", "3".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        cache.put("a", "4".to_string());
+        assert_eq!(cache.get("a").as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn test_response_cache() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "Paris", "done": true }),
+            )
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({ "model": "llama3", "message": { "role": "assistant", "content": "4" }, "done": true }),
+            );
+        let cache = ResponseCache::in_memory(16);
+        let ollama = mock.client().with_cache(cache.clone());
+
+        let request = GenerateRequest::new("llama3", "Capital of France?")
+            .options(Options::default().temperature(0.0));
+        for _ in 0..3 {
+            assert_eq!(ollama.generate(&request).unwrap().response, "Paris");
+        }
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+        // Different options are a different request
+        let seeded = request.clone().options(Options::default().seed(7));
+        ollama.generate(&seeded).unwrap();
+        assert_eq!(cache.misses(), 2);
+
+        // Sampling requests always go to the server unless asked otherwise
+        let chat = ChatRequest::new("llama3", vec![ChatMessage::user("2 + 2?")]);
+        ollama.chat(&chat).unwrap();
+        ollama.chat(&chat).unwrap();
+        assert_eq!(cache.hits(), 2);
+        let ollama = ollama.with_cache(cache.clone().deterministic_only(false));
+        ollama.chat(&chat).unwrap();
+        assert_eq!(ollama.chat(&chat).unwrap().message.content, "4");
+        assert_eq!(cache.hits(), 3);
+
+        // The version check, then one call per miss and per uncached chat
+        assert_eq!(mock.requests().len(), 1 + 2 + 2 + 1);
+    }
+}
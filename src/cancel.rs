@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+// Stops in-flight requests of a client set up with `with_cancellation`, e.g. for a
+// "stop generating" button. Cancelling closes the connection, which makes the server stop.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    cancelled: bool,
+    next_id: u64,
+    // Close the connections of the requests currently running under this token
+    hooks: HashMap<u64, Hook>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        let hooks = {
+            let mut state = self.state.lock().unwrap();
+            state.cancelled = true;
+            std::mem::take(&mut state.hooks)
+        };
+        for hook in hooks.into_values() {
+            hook();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    // Runs `hook` on cancel for as long as the guard lives, right away if already cancelled
+    pub(crate) fn register(&self, hook: impl FnOnce() + Send + 'static) -> CancelGuard {
+        let mut state = self.state.lock().unwrap();
+        if state.cancelled {
+            drop(state);
+            hook();
+            return CancelGuard {
+                token: self.clone(),
+                id: None,
+            };
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        state.hooks.insert(id, Box::new(hook));
+        CancelGuard {
+            token: self.clone(),
+            id: Some(id),
+        }
+    }
+}
+
+// Two tokens are equal when they are clones of each other
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &CancellationToken) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+// Unregisters its hook when the request it belongs to is done
+pub(crate) struct CancelGuard {
+    token: CancellationToken,
+    id: Option<u64>,
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.token.state.lock().unwrap().hooks.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_hooks() {
+        let token = CancellationToken::new();
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let counter = calls.clone();
+        let finished = token.register(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        drop(finished);
+
+        let counter = calls.clone();
+        let _running = token.register(move || {
+            counter.fetch_add(10, Ordering::SeqCst);
+        });
+
+        token.cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(calls.load(Ordering::SeqCst), 10);
+
+        let counter = calls.clone();
+        let _late = token.register(move || {
+            counter.fetch_add(100, Ordering::SeqCst);
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 110);
+    }
+}
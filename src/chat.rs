@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    GenerationStats, KeepAlive, Ollama, OllamaError, Options, TokenLogprob, Tool, ToolCall,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    // The result of a tool call, sent back after the assistant asked for it
+    Tool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+    // Base64-encoded images for multimodal models, see encode_image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    // The model's reasoning before its answer, only from thinking models with `think` enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+    // On a tool message, the function whose result it is, see tool_result
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    // On a tool message, the id of the call it answers, when the model gave one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: impl Into<String>) -> ChatMessage {
+        ChatMessage {
+            role,
+            content: content.into(),
+            images: None,
+            tool_calls: None,
+            thinking: None,
+            tool_name: None,
+            tool_call_id: None,
+        }
+    }
+
+    pub fn images(mut self, images: Vec<String>) -> ChatMessage {
+        self.images = Some(images);
+        self
+    }
+
+    pub fn system(content: impl Into<String>) -> ChatMessage {
+        Self::new(Role::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> ChatMessage {
+        Self::new(Role::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> ChatMessage {
+        Self::new(Role::Assistant, content)
+    }
+
+    pub fn tool(content: impl Into<String>) -> ChatMessage {
+        Self::new(Role::Tool, content)
+    }
+
+    // The result of `call`, labelled with its name and id so the model can tell which call
+    // it answers when it made several
+    pub fn tool_result(call: &ToolCall, content: impl Into<String>) -> ChatMessage {
+        ChatMessage {
+            tool_name: Some(call.name().to_string()),
+            tool_call_id: call.id.clone(),
+            ..Self::tool(content)
+        }
+    }
+
+    // tool_result with structured content, sent as JSON text
+    pub fn tool_result_json(
+        call: &ToolCall,
+        content: &impl Serialize,
+    ) -> Result<ChatMessage, OllamaError> {
+        Ok(Self::tool_result(call, serde_json::to_string(content)?))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    // Either "json" or a JSON schema the reply must conform to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Options>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<KeepAlive>,
+    // Thinking models reason first and return it apart from the answer. Some think by
+    // default, false turns it off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub think: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u32>,
+    stream: bool,
+}
+
+impl ChatRequest {
+    pub fn new(model: impl Into<String>, messages: Vec<ChatMessage>) -> ChatRequest {
+        ChatRequest {
+            model: model.into(),
+            messages,
+            tools: None,
+            format: None,
+            options: None,
+            keep_alive: None,
+            think: None,
+            logprobs: None,
+            top_logprobs: None,
+            stream: false,
+        }
+    }
+
+    pub fn tools(mut self, tools: Vec<Tool>) -> ChatRequest {
+        self.tools = Some(tools);
+        self
+    }
+
+    pub fn format(mut self, format: Value) -> ChatRequest {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> ChatRequest {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn keep_alive(mut self, keep_alive: KeepAlive) -> ChatRequest {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    pub fn think(mut self, think: bool) -> ChatRequest {
+        self.think = Some(think);
+        self
+    }
+
+    // Returns the log probability of each generated token, see TokenLogprob
+    pub fn logprobs(mut self, logprobs: bool) -> ChatRequest {
+        self.logprobs = Some(logprobs);
+        self
+    }
+
+    // Also returns the `top` most likely alternatives for each token, up to 20
+    pub fn top_logprobs(mut self, top: u32) -> ChatRequest {
+        self.logprobs = Some(true);
+        self.top_logprobs = Some(top);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChatResponse {
+    pub model: String,
+    pub message: ChatMessage,
+    #[serde(default)]
+    pub done: bool,
+    pub done_reason: Option<String>,
+    // Set when the request asked for logprobs, for the tokens in this chunk
+    pub logprobs: Option<Vec<TokenLogprob>>,
+    #[serde(flatten)]
+    pub stats: GenerationStats,
+}
+
+impl Ollama {
+    pub fn chat(&self, request: &ChatRequest) -> Result<ChatResponse, OllamaError> {
+        self.post_json_cached("/api/chat", &*self.with_defaults(request))
+    }
+
+    // Each chunk carries the next piece of the assistant message, the last one has `done` set
+    pub fn chat_stream(
+        &self,
+        request: &ChatRequest,
+    ) -> Result<impl Iterator<Item = Result<ChatResponse, OllamaError>> + Send + use<>, OllamaError>
+    {
+        let request = ChatRequest {
+            stream: true,
+            ..self.with_defaults(request).into_owned()
+        };
+        self.post_stream("/api/chat", &request)
+    }
+
+    // chat_stream as a futures Stream, the request runs on a background thread
+    #[cfg(feature = "async")]
+    pub fn chat_stream_async(
+        &self,
+        request: &ChatRequest,
+    ) -> impl futures_core::Stream<Item = Result<ChatResponse, OllamaError>> + Send + Unpin + use<>
+    {
+        let request = request.clone();
+        crate::stream::ThreadStream::call(self, move |ollama| ollama.chat_stream(&request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{json_response, serve};
+
+    #[test]
+    fn test_chat_request_body() {
+        let request = ChatRequest::new(
+            "llama3",
+            vec![ChatMessage::system("Be terse."), ChatMessage::user("Hi")],
+        )
+        .options(Options::default().temperature(0.0));
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "model": "llama3",
+                "messages": [
+                    { "role": "system", "content": "Be terse." },
+                    { "role": "user", "content": "Hi" }
+                ],
+                "options": { "temperature": 0.0 },
+                "stream": false
+            })
+        );
+    }
+
+    #[test]
+    fn test_message_images() {
+        let message = ChatMessage::user("What is in this picture?")
+            .images(vec![crate::encode_image(b"not really a png")]);
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            value["images"],
+            serde_json::json!(["bm90IHJlYWxseSBhIHBuZw=="])
+        );
+
+        let value = serde_json::to_value(ChatMessage::user("Hi")).unwrap();
+        assert!(value.get("images").is_none());
+    }
+
+    #[test]
+    fn test_tool_result_message() {
+        let call: ToolCall = serde_json::from_value(serde_json::json!({
+            "id": "call_1",
+            "function": { "name": "get_weather", "arguments": { "city": "Paris" } }
+        }))
+        .unwrap();
+        let message =
+            ChatMessage::tool_result_json(&call, &serde_json::json!({ "celsius": 22 })).unwrap();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({
+                "role": "tool",
+                "content": "{\"celsius\":22}",
+                "tool_name": "get_weather",
+                "tool_call_id": "call_1"
+            })
+        );
+        let round_trip: ChatMessage =
+            serde_json::from_value(serde_json::to_value(&message).unwrap()).unwrap();
+        assert_eq!(round_trip, message);
+    }
+
+    #[test]
+    fn test_chat_tool_calls() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response(
+                "200 OK",
+                r#"{"model":"llama3.1","message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"get_weather","arguments":{"city":"Paris"}}}]},"done":true}"#,
+            ),
+        ]);
+
+        let tool = Tool::function(
+            "get_weather",
+            "Get the weather for a city",
+            serde_json::json!({ "type": "object", "properties": { "city": { "type": "string" } } }),
+        );
+        let response = ollama
+            .chat(
+                &ChatRequest::new("llama3.1", vec![ChatMessage::user("Weather in Paris?")])
+                    .tools(vec![tool]),
+            )
+            .unwrap();
+
+        let calls = response.message.tool_calls.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name(), "get_weather");
+        assert_eq!(calls[0].function.arguments["city"], "Paris");
+
+        let requests = server.join().unwrap();
+        let body: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+    }
+
+    #[test]
+    fn test_chat() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response(
+                "200 OK",
+                r#"{"model":"llama3","message":{"role":"assistant","content":"Hello!"},"done":true,"done_reason":"stop"}"#,
+            ),
+        ]);
+
+        let response = ollama
+            .chat(&ChatRequest::new("llama3", vec![ChatMessage::user("Hi")]))
+            .unwrap();
+        server.join().unwrap();
+        assert_eq!(response.message, ChatMessage::assistant("Hello!"));
+        assert_eq!(response.done_reason.as_deref(), Some("stop"));
+    }
+
+    #[test]
+    fn test_chat_stream() {
+        let chunk = |content: &str, done: bool| {
+            serde_json::json!({
+                "model": "llama3",
+                "message": { "role": "assistant", "content": content },
+                "done": done
+            })
+        };
+        let mock = crate::MockOllama::new().stream(
+            "POST",
+            "/api/chat",
+            vec![chunk("Hel", false), chunk("lo!", false), chunk("", true)],
+        );
+
+        let request = ChatRequest::new("llama3", vec![ChatMessage::user("Hi")]);
+        let reply: String = mock
+            .client()
+            .chat_stream(&request)
+            .unwrap()
+            .map(|chunk| chunk.unwrap().message.content)
+            .collect();
+        assert_eq!(reply, "Hello!");
+    }
+
+    #[test]
+    fn test_chat_thinking() {
+        let chunk = |thinking: &str, content: &str, done: bool| {
+            serde_json::json!({
+                "model": "qwen3",
+                "message": { "role": "assistant", "content": content, "thinking": thinking },
+                "done": done
+            })
+        };
+        let mock = crate::MockOllama::new().stream(
+            "POST",
+            "/api/chat",
+            vec![
+                chunk("The user greets me. ", "", false),
+                chunk("Greet back.", "", false),
+                chunk("", "Hello!", true),
+            ],
+        );
+
+        let request = ChatRequest::new("qwen3", vec![ChatMessage::user("Hi")]).think(true);
+        let (mut thinking, mut reply) = (String::new(), String::new());
+        for chunk in mock.client().chat_stream(&request).unwrap() {
+            let message = chunk.unwrap().message;
+            thinking.push_str(message.thinking.as_deref().unwrap_or_default());
+            reply.push_str(&message.content);
+        }
+        assert_eq!(thinking, "The user greets me. Greet back.");
+        assert_eq!(reply, "Hello!");
+
+        let body = mock.request_json(1);
+        assert_eq!(body["think"], true);
+        assert!(body["messages"][0].get("thinking").is_none());
+    }
+}
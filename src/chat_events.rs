@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+
+use crate::{ChatRequest, ChatResponse, GenerationStats, Ollama, OllamaError, Role, ToolCall};
+
+// A streamed chat reply taken apart, so a UI can show the answer as it's typed and handle
+// tool calls separately. Events come in the order the server sent the pieces:
+// MessageStart first, Done last.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatEvent {
+    MessageStart {
+        model: String,
+        role: Role,
+    },
+    // The next piece of the model's reasoning, only from thinking models with `think` enabled
+    ThinkingDelta(String),
+    // The next piece of the visible answer
+    ContentDelta(String),
+    // Ollama sends every tool call whole, so each delta is a complete call. `index` is its
+    // position among the calls of the message.
+    ToolCallDelta {
+        index: usize,
+        call: ToolCall,
+    },
+    Done {
+        reason: Option<String>,
+        stats: GenerationStats,
+    },
+}
+
+// Turns each chunk of a chat stream into the events it holds
+struct ChatEvents<I> {
+    chunks: I,
+    pending: VecDeque<ChatEvent>,
+    started: bool,
+    tool_calls: usize,
+}
+
+impl<I> ChatEvents<I> {
+    fn push(&mut self, chunk: ChatResponse) {
+        let message = chunk.message;
+        if !self.started {
+            self.started = true;
+            self.pending.push_back(ChatEvent::MessageStart {
+                model: chunk.model,
+                role: message.role,
+            });
+        }
+        if let Some(thinking) = message.thinking.filter(|t| !t.is_empty()) {
+            self.pending.push_back(ChatEvent::ThinkingDelta(thinking));
+        }
+        if !message.content.is_empty() {
+            self.pending
+                .push_back(ChatEvent::ContentDelta(message.content));
+        }
+        for call in message.tool_calls.into_iter().flatten() {
+            self.pending.push_back(ChatEvent::ToolCallDelta {
+                index: self.tool_calls,
+                call,
+            });
+            self.tool_calls += 1;
+        }
+        if chunk.done {
+            self.pending.push_back(ChatEvent::Done {
+                reason: chunk.done_reason,
+                stats: chunk.stats,
+            });
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<ChatResponse, OllamaError>>> Iterator for ChatEvents<I> {
+    type Item = Result<ChatEvent, OllamaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            match self.chunks.next()? {
+                Ok(chunk) => self.push(chunk),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+impl Ollama {
+    // chat_stream as typed events, see ChatEvent
+    pub fn chat_events(
+        &self,
+        request: &ChatRequest,
+    ) -> Result<impl Iterator<Item = Result<ChatEvent, OllamaError>> + Send + use<>, OllamaError>
+    {
+        Ok(ChatEvents {
+            chunks: self.chat_stream(request)?,
+            pending: VecDeque::new(),
+            started: false,
+            tool_calls: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatMessage, MockOllama};
+    use serde_json::json;
+
+    #[test]
+    fn test_chat_events() {
+        let mock = MockOllama::new().stream(
+            "POST",
+            "/api/chat",
+            vec![
+                json!({ "model": "qwen3", "message": { "role": "assistant", "content": "", "thinking": "Weather, so" }, "done": false }),
+                json!({ "model": "qwen3", "message": { "role": "assistant", "content": "Let me check" }, "done": false }),
+                json!({ "model": "qwen3", "message": { "role": "assistant", "content": "", "tool_calls": [
+                    { "function": { "name": "get_weather", "arguments": { "city": "Paris" } } },
+                    { "function": { "name": "get_weather", "arguments": { "city": "Rome" } } }
+                ] }, "done": false }),
+                json!({ "model": "qwen3", "message": { "role": "assistant", "content": "" }, "done": true, "done_reason": "stop", "eval_count": 12 }),
+            ],
+        );
+        let request = ChatRequest::new("qwen3", vec![ChatMessage::user("Weather?")]);
+        let events: Vec<ChatEvent> = mock
+            .client()
+            .chat_events(&request)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(events.len(), 6);
+        assert_eq!(
+            events[0],
+            ChatEvent::MessageStart {
+                model: "qwen3".to_string(),
+                role: Role::Assistant
+            }
+        );
+        assert_eq!(
+            events[1],
+            ChatEvent::ThinkingDelta("Weather, so".to_string())
+        );
+        assert_eq!(
+            events[2],
+            ChatEvent::ContentDelta("Let me check".to_string())
+        );
+        let ChatEvent::ToolCallDelta { index: 1, call } = &events[4] else {
+            panic!("expected the second tool call, got {:?}", events[4]);
+        };
+        assert_eq!(call.function.arguments["city"], "Rome");
+        let ChatEvent::Done { reason, stats } = &events[5] else {
+            panic!("expected Done, got {:?}", events[5]);
+        };
+        assert_eq!(reason.as_deref(), Some("stop"));
+        assert_eq!(stats.eval_count, 12);
+    }
+}
@@ -0,0 +1,256 @@
+use std::ops::Range;
+
+// What a TextSplitter counts when it measures chunk size and overlap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkUnit {
+    Characters,
+    Sentences,
+    // Estimated at four characters per token, close for English text with most tokenizers
+    Tokens,
+}
+
+// Cuts documents into pieces small enough to embed. Chunks break between words, or between
+// sentences when counting sentences, and the last `overlap` units of a chunk start the next
+// one so text cut at a boundary still shows up whole somewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSplitter {
+    pub unit: ChunkUnit,
+    pub size: usize,
+    pub overlap: usize,
+}
+
+impl TextSplitter {
+    pub fn characters(size: usize) -> TextSplitter {
+        TextSplitter::new(ChunkUnit::Characters, size)
+    }
+
+    pub fn sentences(size: usize) -> TextSplitter {
+        TextSplitter::new(ChunkUnit::Sentences, size)
+    }
+
+    pub fn tokens(size: usize) -> TextSplitter {
+        TextSplitter::new(ChunkUnit::Tokens, size)
+    }
+
+    fn new(unit: ChunkUnit, size: usize) -> TextSplitter {
+        TextSplitter {
+            unit,
+            size,
+            overlap: 0,
+        }
+    }
+
+    // Counted in the splitter's unit, kept below the chunk size
+    pub fn overlap(mut self, overlap: usize) -> TextSplitter {
+        self.overlap = overlap;
+        self
+    }
+
+    // The chunks in document order with surrounding whitespace trimmed, ready to go into
+    // an EmbedRequest
+    pub fn split(&self, text: &str) -> Vec<String> {
+        let size = self.size.max(1);
+        let overlap = self.overlap.min(size - 1);
+        let pieces = self.pieces(text, size);
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < pieces.len() {
+            let mut end = start;
+            let mut cost = 0;
+            while end < pieces.len() && (end == start || cost + pieces[end].1 <= size) {
+                cost += pieces[end].1;
+                end += 1;
+            }
+            let chunk = text[pieces[start].0.start..pieces[end - 1].0.end].trim();
+            if !chunk.is_empty() {
+                chunks.push(chunk.to_string());
+            }
+            if end == pieces.len() {
+                break;
+            }
+
+            // Step back over as many pieces as the overlap allows, always moving forward
+            let mut next = end;
+            let mut back = 0;
+            while next > start + 1 && back + pieces[next - 1].1 <= overlap {
+                back += pieces[next - 1].1;
+                next -= 1;
+            }
+            start = next;
+        }
+        chunks
+    }
+
+    // The text cut into the smallest pieces a chunk is built from, with what each one counts
+    // for. Words bigger than a whole chunk are cut into chunk-sized parts.
+    fn pieces(&self, text: &str, size: usize) -> Vec<(Range<usize>, usize)> {
+        match self.unit {
+            ChunkUnit::Sentences => sentences(text).into_iter().map(|s| (s, 1)).collect(),
+            ChunkUnit::Characters => words(text)
+                .into_iter()
+                .flat_map(|w| split_long(text, w, size))
+                .map(|w| {
+                    let cost = text[w.clone()].chars().count();
+                    (w, cost)
+                })
+                .collect(),
+            ChunkUnit::Tokens => words(text)
+                .into_iter()
+                .flat_map(|w| split_long(text, w, size * 4))
+                .map(|w| {
+                    let cost = text[w.clone()].trim_end().chars().count().div_ceil(4);
+                    (w, cost.max(1))
+                })
+                .collect(),
+        }
+    }
+}
+
+// Each word with the whitespace after it
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let first = text.len() - text.trim_start().len();
+    let mut start = first;
+    let mut in_space = false;
+    for (i, c) in text.char_indices().skip_while(|&(i, _)| i < first) {
+        if c.is_whitespace() {
+            in_space = true;
+        } else if in_space {
+            words.push(start..i);
+            start = i;
+            in_space = false;
+        }
+    }
+    if start < text.len() {
+        words.push(start..text.len());
+    }
+    words
+}
+
+// Each sentence with the whitespace after it. A sentence ends at ., ! or ? (and any closing
+// quotes or brackets) followed by whitespace, or at a blank line.
+fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let first = text.len() - text.trim_start().len();
+    let mut start = first;
+    let mut chars = text
+        .char_indices()
+        .skip_while(|&(i, _)| i < first)
+        .peekable();
+    while let Some((_, c)) = chars.next() {
+        let ends = match c {
+            '.' | '!' | '?' => {
+                while let Some(&(_, '.' | '!' | '?' | '"' | '\'' | ')' | ']' | '”' | '’')) =
+                    chars.peek()
+                {
+                    chars.next();
+                }
+                matches!(chars.peek(), Some((_, c)) if c.is_whitespace())
+            }
+            '\n' => matches!(chars.peek(), Some((_, '\n' | '\r'))),
+            _ => false,
+        };
+        if ends {
+            while let Some(&(_, c)) = chars.peek() {
+                if !c.is_whitespace() {
+                    break;
+                }
+                chars.next();
+            }
+            let end = chars.peek().map_or(text.len(), |&(i, _)| i);
+            sentences.push(start..end);
+            start = end;
+        }
+    }
+    if start < text.len() {
+        sentences.push(start..text.len());
+    }
+    sentences
+}
+
+// `range` cut into parts of at most `max` characters
+fn split_long(text: &str, range: Range<usize>, max: usize) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut start = range.start;
+    let mut count = 0;
+    for (i, _) in text[range.clone()].char_indices() {
+        if count == max {
+            parts.push(start..range.start + i);
+            start = range.start + i;
+            count = 0;
+        }
+        count += 1;
+    }
+    parts.push(start..range.end);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_characters() {
+        let text = "  The quick brown fox jumps over the lazy dog.  ";
+        assert_eq!(
+            TextSplitter::characters(16).split(text),
+            ["The quick brown", "fox jumps over", "the lazy dog."]
+        );
+        assert_eq!(
+            TextSplitter::characters(16).overlap(6).split(text),
+            [
+                "The quick brown",
+                "brown fox jumps",
+                "jumps over the",
+                "the lazy dog."
+            ]
+        );
+        assert_eq!(
+            TextSplitter::characters(4).split("abcdefghij k"),
+            ["abcd", "efgh", "ij k"]
+        );
+        assert!(TextSplitter::characters(10).split(" \n ").is_empty());
+    }
+
+    #[test]
+    fn test_sentences() {
+        let text = "Hi there! Is this \"it?\" Yes, it is.\n\nA new paragraph 3.5 lines\nlong";
+        assert_eq!(
+            TextSplitter::sentences(2).split(text),
+            [
+                "Hi there! Is this \"it?\"",
+                "Yes, it is.\n\nA new paragraph 3.5 lines\nlong"
+            ]
+        );
+        assert_eq!(
+            TextSplitter::sentences(2).overlap(1).split(text),
+            [
+                "Hi there! Is this \"it?\"",
+                "Is this \"it?\" Yes, it is.",
+                "Yes, it is.\n\nA new paragraph 3.5 lines\nlong"
+            ]
+        );
+        assert_eq!(
+            TextSplitter::sentences(1).split("Title\n\nBody text"),
+            ["Title", "Body text"]
+        );
+    }
+
+    #[test]
+    fn test_tokens() {
+        let text = "one two three fourteen fifteen";
+        // three: 2 tokens, fourteen: 2, fifteen: 2
+        assert_eq!(
+            TextSplitter::tokens(4).split(text),
+            ["one two three", "fourteen fifteen"]
+        );
+        assert_eq!(
+            TextSplitter::tokens(4).overlap(2).split(text),
+            ["one two three", "three fourteen", "fourteen fifteen"]
+        );
+        // A word longer than a whole chunk gets cut
+        let chunks = TextSplitter::tokens(1).split("supercalifragilistic");
+        assert_eq!(chunks, ["supe", "rcal", "ifra", "gili", "stic"]);
+    }
+}
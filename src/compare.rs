@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use crate::{
+    ChatRequest, ChatResponse, GenerateRequest, GenerateResponse, Instant, Ollama, OllamaError,
+    config::Defaults,
+};
+
+// One model's part in a comparison. The response carries the server's token counts and
+// timings in `stats`, `elapsed` is the wall-clock time including any wait for the model to load.
+#[derive(Debug)]
+pub struct Comparison<T> {
+    pub model: String,
+    pub result: Result<T, OllamaError>,
+    pub elapsed: Duration,
+}
+
+impl Ollama {
+    // Sends the request to every model at once, with the model swapped in. The results are in
+    // the order of `models`, one failing model doesn't stop the others. The server runs as many
+    // at a time as OLLAMA_MAX_LOADED_MODELS and memory allow and queues the rest.
+    pub fn compare_generate(
+        &self,
+        models: &[impl AsRef<str> + Sync],
+        request: &GenerateRequest,
+    ) -> Vec<Comparison<GenerateResponse>> {
+        self.compare(models, request, |request| self.generate(request))
+    }
+
+    pub fn compare_chat(
+        &self,
+        models: &[impl AsRef<str> + Sync],
+        request: &ChatRequest,
+    ) -> Vec<Comparison<ChatResponse>> {
+        self.compare(models, request, |request| self.chat(request))
+    }
+
+    fn compare<R: Defaults + Sync, T: Send>(
+        &self,
+        models: &[impl AsRef<str> + Sync],
+        request: &R,
+        call: impl Fn(&R) -> Result<T, OllamaError> + Sync,
+    ) -> Vec<Comparison<T>> {
+        std::thread::scope(|scope| {
+            let runs: Vec<_> = models
+                .iter()
+                .map(|model| {
+                    let call = &call;
+                    scope.spawn(move || {
+                        let mut request = request.clone();
+                        *request.model_mut() = model.as_ref().to_string();
+                        let start = Instant::now();
+                        let result = call(&request);
+                        Comparison {
+                            model: model.as_ref().to_string(),
+                            result,
+                            elapsed: start.elapsed(),
+                        }
+                    })
+                })
+                .collect();
+            runs.into_iter()
+                .map(|run| run.join().expect("a comparison thread panicked"))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatMessage, MockOllama};
+    use serde_json::json;
+
+    #[test]
+    fn test_compare_chat() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({
+                    "model": "any",
+                    "message": { "role": "assistant", "content": "4" },
+                    "done": true,
+                    "eval_count": 1
+                }),
+            )
+            .error("POST", "/api/generate", 404, "model 'nope' not found");
+        let ollama = mock.client();
+
+        let request = ChatRequest::new("", vec![ChatMessage::user("2 + 2?")]);
+        let results = ollama.compare_chat(&["llama3", "phi3", "mistral"], &request);
+        let models: Vec<&str> = results.iter().map(|r| r.model.as_str()).collect();
+        assert_eq!(models, ["llama3", "phi3", "mistral"]);
+        for comparison in &results {
+            let response = comparison.result.as_ref().unwrap();
+            assert_eq!(response.message.content, "4");
+            assert_eq!(response.stats.eval_count, 1);
+        }
+
+        let mut sent: Vec<String> = (1..mock.requests().len())
+            .map(|i| mock.request_json(i)["model"].as_str().unwrap().to_string())
+            .collect();
+        sent.sort();
+        assert_eq!(sent, ["llama3", "mistral", "phi3"]);
+
+        let results = ollama.compare_generate(&["nope"], &GenerateRequest::new("", "Hi"));
+        assert!(matches!(
+            results[0].result,
+            Err(OllamaError::ModelNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{borrow::Cow, time::Duration};
+
+use serde::{Deserialize, Deserializer, de::Error};
+
+use crate::{
+    Auth, ChatRequest, EmbedRequest, GenerateRequest, KeepAlive, Ollama, OllamaBuilder,
+    OllamaError, Timeouts,
+};
+
+// Client settings as an application keeps them in its own config file, e.g. in TOML:
+//
+//   host = "https://ollama.example.com"
+//   default_model = "llama3"
+//   keep_alive = "30m"
+//   timeouts = { connect = "5s", read = 120 }
+//   auth = { bearer = "..." }
+//
+// Durations are seconds or Go-style strings like "500ms", "5m" or "1h30m". Everything is
+// optional, an empty config connects to OLLAMA_HOST like Ollama::new.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OllamaConfig {
+    // Anything OLLAMA_HOST accepts, OLLAMA_HOST or localhost when not set
+    pub host: Option<String>,
+    // Overrides the port in `host`
+    pub port: Option<u16>,
+    pub timeouts: Timeouts,
+    // Used by requests that leave the model empty
+    pub default_model: Option<String>,
+    // Used by generate, chat and embed requests that don't set their own
+    pub keep_alive: Option<KeepAlive>,
+    pub auth: Option<Auth>,
+}
+
+impl OllamaConfig {
+    // A builder with these settings, for anything a config file doesn't cover
+    pub fn builder(&self) -> OllamaBuilder {
+        let mut builder = OllamaBuilder::new().timeouts(self.timeouts);
+        if let Some(host) = &self.host {
+            builder = builder.url(host);
+        }
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        builder.default_model = self.default_model.clone();
+        builder.keep_alive = self.keep_alive;
+        builder.auth = self.auth.clone();
+        builder
+    }
+}
+
+impl Ollama {
+    pub fn from_config(config: &OllamaConfig) -> Result<Ollama, OllamaError> {
+        config.builder().build()
+    }
+
+    // A copy of this client whose requests use `model` when they leave the model empty
+    pub fn with_default_model(&self, model: impl Into<String>) -> Ollama {
+        Ollama {
+            default_model: Some(model.into()),
+            ..self.clone()
+        }
+    }
+
+    // A copy of this client whose generate, chat and embed requests keep the model loaded for
+    // `keep_alive` unless they say otherwise
+    pub fn with_keep_alive(&self, keep_alive: KeepAlive) -> Ollama {
+        Ollama {
+            keep_alive: Some(keep_alive),
+            ..self.clone()
+        }
+    }
+
+    // The request with the client's default model and keep_alive filled in where it has none,
+    // only copied when something changes
+    pub(crate) fn with_defaults<'a, R: Defaults>(&self, request: &'a R) -> Cow<'a, R> {
+        let fill_model = request.model().is_empty() && self.default_model.is_some();
+        let fill_keep_alive = request.keep_alive().is_none() && self.keep_alive.is_some();
+        if !fill_model && !fill_keep_alive {
+            return Cow::Borrowed(request);
+        }
+
+        let mut request = request.clone();
+        if fill_model {
+            *request.model_mut() = self.default_model.clone().unwrap_or_default();
+        }
+        if fill_keep_alive {
+            *request.keep_alive_mut() = self.keep_alive;
+        }
+        Cow::Owned(request)
+    }
+}
+
+// Requests the client defaults apply to
+pub(crate) trait Defaults: Clone {
+    fn model(&self) -> &str;
+    fn model_mut(&mut self) -> &mut String;
+    fn keep_alive(&self) -> Option<KeepAlive>;
+    fn keep_alive_mut(&mut self) -> &mut Option<KeepAlive>;
+}
+
+macro_rules! impl_defaults {
+    ($($request:ty),*) => {
+        $(
+            impl Defaults for $request {
+                fn model(&self) -> &str {
+                    &self.model
+                }
+
+                fn model_mut(&mut self) -> &mut String {
+                    &mut self.model
+                }
+
+                fn keep_alive(&self) -> Option<KeepAlive> {
+                    self.keep_alive
+                }
+
+                fn keep_alive_mut(&mut self) -> &mut Option<KeepAlive> {
+                    &mut self.keep_alive
+                }
+            }
+        )*
+    };
+}
+
+impl_defaults!(GenerateRequest, ChatRequest, EmbedRequest);
+
+// A number of seconds or a string like "1h30m", "500ms" or "2.5s"
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDuration {
+    Seconds(f64),
+    Text(String),
+}
+
+// Negative durations come back as None, the caller decides what they mean
+fn signed_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    let seconds = match RawDuration::deserialize(deserializer)? {
+        RawDuration::Seconds(seconds) => seconds,
+        RawDuration::Text(text) => parse_seconds(&text)
+            .ok_or_else(|| D::Error::custom(format!("invalid duration {:?}", text)))?,
+    };
+    if !seconds.is_finite() {
+        return Err(D::Error::custom("invalid duration"));
+    }
+    Ok((seconds >= 0.0).then(|| Duration::from_secs_f64(seconds)))
+}
+
+pub(crate) fn optional_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    signed_duration(deserializer)?
+        .map(Some)
+        .ok_or_else(|| D::Error::custom("durations can't be negative"))
+}
+
+// Like the server, "-1" or any negative duration keeps the model loaded and 0 unloads it
+pub(crate) fn keep_alive<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<KeepAlive, D::Error> {
+    Ok(match signed_duration(deserializer)? {
+        None => KeepAlive::Forever,
+        Some(Duration::ZERO) => KeepAlive::UnloadNow,
+        Some(duration) => KeepAlive::For(duration),
+    })
+}
+
+// Go duration syntax: a sequence of numbers with units, a bare number counts as seconds
+fn parse_seconds(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if let Ok(seconds) = text.parse::<f64>() {
+        return Some(seconds);
+    }
+    let (sign, mut rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text),
+    };
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_end] {
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        seconds += number * scale;
+        rest = &rest[unit_end..];
+    }
+    Some(sign * seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockOllama;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_seconds() {
+        assert_eq!(parse_seconds("90"), Some(90.0));
+        assert_eq!(parse_seconds("1h30m"), Some(5400.0));
+        assert_eq!(parse_seconds("500ms"), Some(0.5));
+        assert_eq!(parse_seconds("-1"), Some(-1.0));
+        assert_eq!(parse_seconds("-2m"), Some(-120.0));
+        assert_eq!(parse_seconds("5 minutes"), None);
+        assert_eq!(parse_seconds("m"), None);
+        assert_eq!(parse_seconds(""), None);
+    }
+
+    #[test]
+    fn test_deserialize_config() {
+        let config: OllamaConfig = serde_json::from_value(json!({
+            "host": "https://ollama.example.com",
+            "port": 8443,
+            "timeouts": { "connect": "5s", "read": 120 },
+            "default_model": "llama3",
+            "keep_alive": "30m",
+            "auth": { "bearer": "secret" }
+        }))
+        .unwrap();
+        assert_eq!(
+            config.timeouts,
+            Timeouts::default()
+                .connect(Duration::from_secs(5))
+                .read(Duration::from_secs(120))
+        );
+        assert_eq!(
+            config.keep_alive,
+            Some(KeepAlive::For(Duration::from_secs(1800)))
+        );
+        assert_eq!(config.auth, Some(Auth::bearer("secret")));
+
+        let builder = config.builder();
+        assert_eq!(builder.host.scheme, "https");
+        assert_eq!(builder.host.port, 8443);
+        assert_eq!(builder.default_model.as_deref(), Some("llama3"));
+
+        let parse = |value| serde_json::from_value::<OllamaConfig>(value);
+        assert_eq!(parse(json!({})).unwrap(), OllamaConfig::default());
+        assert_eq!(
+            parse(json!({ "keep_alive": -1 })).unwrap().keep_alive,
+            Some(KeepAlive::Forever)
+        );
+        assert!(parse(json!({ "timeouts": { "read": "-5s" } })).is_err());
+        assert!(parse(json!({ "hostname": "localhost" })).is_err());
+    }
+
+    #[test]
+    fn test_client_defaults() {
+        let reply = json!({ "model": "llama3", "response": "Hi", "done": true });
+        let mock = MockOllama::new()
+            .respond("POST", "/api/generate", 200, reply.clone())
+            .respond("POST", "/api/generate", 200, reply);
+        let config = OllamaConfig {
+            default_model: Some("llama3".to_string()),
+            keep_alive: Some(KeepAlive::Forever),
+            ..OllamaConfig::default()
+        };
+        let ollama = config.builder().transport(mock.clone()).build().unwrap();
+
+        ollama.generate(&GenerateRequest::new("", "Hello")).unwrap();
+        let request = GenerateRequest::new("phi3", "Hello").keep_alive(KeepAlive::UnloadNow);
+        ollama.generate(&request).unwrap();
+
+        let bodies: Vec<serde_json::Value> = (1..mock.requests().len())
+            .map(|i| mock.request_json(i))
+            .collect();
+        assert_eq!(bodies[0]["model"], "llama3");
+        assert_eq!(bodies[0]["keep_alive"], -1);
+        assert_eq!(bodies[1]["model"], "phi3");
+        assert_eq!(bodies[1]["keep_alive"], 0);
+    }
+}
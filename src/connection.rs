@@ -0,0 +1,236 @@
+use std::{
+    io::{Error, ErrorKind, Read, Write},
+    net::{Shutdown, TcpStream},
+};
+
+use crate::{Proxy, Timeouts};
+
+// An open socket to the server, plain TCP or TLS when the host uses https. Kept alive between
+// requests in a ConnectionPool.
+pub(crate) enum Connection {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+    #[cfg(all(unix, feature = "unix-socket"))]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl Connection {
+    pub fn open(
+        scheme: &str,
+        host: &str,
+        port: u16,
+        timeouts: &Timeouts,
+        proxy: Option<&Proxy>,
+    ) -> Result<Connection, Error> {
+        // For unix sockets the host is the socket path
+        if scheme == "unix" {
+            return connect_unix(host, timeouts);
+        }
+
+        let stream = match proxy {
+            None => connect_tcp(host, port, timeouts)?,
+            Some(proxy) => {
+                let mut stream = connect_tcp(&proxy.host, proxy.port, timeouts)?;
+                // Plain http is forwarded by the proxy itself, TLS has to go through a tunnel
+                if scheme == "https" {
+                    proxy.tunnel(&mut stream, host, port)?;
+                }
+                stream
+            }
+        };
+
+        match scheme {
+            "http" => Ok(Connection::Plain(stream)),
+            #[cfg(feature = "tls")]
+            "https" => tls::wrap(host, stream),
+            #[cfg(not(feature = "tls"))]
+            "https" => Err(Error::new(
+                ErrorKind::Unsupported,
+                "https hosts require the `tls` feature",
+            )),
+            other => Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("unsupported scheme: {}", other),
+            )),
+        }
+    }
+}
+
+impl Connection {
+    // Shuts the socket down from another thread, which unblocks a pending read
+    pub fn closer(&self) -> Result<Box<dyn FnOnce() + Send>, Error> {
+        Ok(match self {
+            Connection::Plain(stream) => {
+                let stream = stream.try_clone()?;
+                Box::new(move || {
+                    let _ = stream.shutdown(Shutdown::Both);
+                })
+            }
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => {
+                let stream = stream.sock.try_clone()?;
+                Box::new(move || {
+                    let _ = stream.shutdown(Shutdown::Both);
+                })
+            }
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Connection::Unix(stream) => {
+                let stream = stream.try_clone()?;
+                Box::new(move || {
+                    let _ = stream.shutdown(Shutdown::Both);
+                })
+            }
+        })
+    }
+
+    // A pooled connection may be picked up by a client with other timeouts
+    pub fn set_timeouts(&self, timeouts: &Timeouts) -> Result<(), Error> {
+        match self {
+            Connection::Plain(stream) => {
+                stream.set_read_timeout(timeouts.read)?;
+                stream.set_write_timeout(timeouts.write)
+            }
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => {
+                stream.sock.set_read_timeout(timeouts.read)?;
+                stream.sock.set_write_timeout(timeouts.write)
+            }
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Connection::Unix(stream) => {
+                stream.set_read_timeout(timeouts.read)?;
+                stream.set_write_timeout(timeouts.write)
+            }
+        }
+    }
+}
+
+// There are no sockets in the browser, see BrowserTransport
+#[cfg(target_arch = "wasm32")]
+fn connect_tcp(_host: &str, _port: u16, _timeouts: &Timeouts) -> Result<TcpStream, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "TCP connections are not available on wasm32, use a Transport",
+    ))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn connect_tcp(host: &str, port: u16, timeouts: &Timeouts) -> Result<TcpStream, Error> {
+    use std::net::ToSocketAddrs;
+
+    let stream = match timeouts.connect {
+        None => TcpStream::connect((host, port))?,
+        Some(timeout) => {
+            let mut last_error = Error::new(ErrorKind::NotFound, "host did not resolve");
+            let mut connected = None;
+            for addr in (host, port).to_socket_addrs()? {
+                match TcpStream::connect_timeout(&addr, timeout) {
+                    Ok(stream) => {
+                        connected = Some(stream);
+                        break;
+                    }
+                    Err(e) => last_error = e,
+                }
+            }
+            connected.ok_or(last_error)?
+        }
+    };
+
+    stream.set_read_timeout(timeouts.read)?;
+    stream.set_write_timeout(timeouts.write)?;
+    Ok(stream)
+}
+
+#[cfg(all(unix, feature = "unix-socket"))]
+fn connect_unix(path: &str, timeouts: &Timeouts) -> Result<Connection, Error> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    stream.set_read_timeout(timeouts.read)?;
+    stream.set_write_timeout(timeouts.write)?;
+    Ok(Connection::Unix(stream))
+}
+
+#[cfg(not(all(unix, feature = "unix-socket")))]
+fn connect_unix(_path: &str, _timeouts: &Timeouts) -> Result<Connection, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "unix sockets require the `unix-socket` feature on a unix platform",
+    ))
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self {
+            Connection::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.read(buf),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Connection::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        match self {
+            Connection::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.write(buf),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Connection::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        match self {
+            Connection::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.flush(),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Connection::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+mod tls {
+    use std::{
+        io::{Error, ErrorKind},
+        net::TcpStream,
+        sync::{Arc, OnceLock},
+    };
+
+    use rustls::{
+        ClientConfig, ClientConnection, RootCertStore, StreamOwned, pki_types::ServerName,
+    };
+
+    use super::Connection;
+
+    // Built once, loading the root certificates is not free
+    fn config() -> Result<Arc<ClientConfig>, Error> {
+        static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+        if let Some(config) = CONFIG.get() {
+            return Ok(config.clone());
+        }
+
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(Error::other)?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+
+        Ok(CONFIG.get_or_init(|| Arc::new(config)).clone())
+    }
+
+    pub fn wrap(host: &str, stream: TcpStream) -> Result<Connection, Error> {
+        let name = ServerName::try_from(host.to_string())
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let connection = ClientConnection::new(config()?, name).map_err(Error::other)?;
+        Ok(Connection::Tls(Box::new(StreamOwned::new(
+            connection, stream,
+        ))))
+    }
+}
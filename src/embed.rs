@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{GenerationStats, KeepAlive, Ollama, OllamaError, Options};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmbedRequest {
+    pub model: String,
+    // Every input gets its own embedding, in the same order
+    pub input: Vec<String>,
+    // When false, inputs longer than the context window are an error instead of being cut
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncate: Option<bool>,
+    // Shortens the embeddings, for models trained to support it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Options>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<KeepAlive>,
+}
+
+impl EmbedRequest {
+    pub fn new(
+        model: impl Into<String>,
+        input: impl IntoIterator<Item = impl Into<String>>,
+    ) -> EmbedRequest {
+        EmbedRequest {
+            model: model.into(),
+            input: input.into_iter().map(Into::into).collect(),
+            truncate: None,
+            dimensions: None,
+            options: None,
+            keep_alive: None,
+        }
+    }
+
+    pub fn truncate(mut self, truncate: bool) -> EmbedRequest {
+        self.truncate = Some(truncate);
+        self
+    }
+
+    pub fn dimensions(mut self, dimensions: u32) -> EmbedRequest {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> EmbedRequest {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn keep_alive(mut self, keep_alive: KeepAlive) -> EmbedRequest {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EmbedResponse {
+    pub model: String,
+    pub embeddings: Vec<Vec<f32>>,
+    // Only the durations and prompt_eval_count are filled in
+    #[serde(flatten)]
+    pub stats: GenerationStats,
+}
+
+impl Ollama {
+    // All inputs go out in one request, see embed_batch for large inputs
+    pub fn embed(&self, request: &EmbedRequest) -> Result<EmbedResponse, OllamaError> {
+        let response: EmbedResponse =
+            self.post_json("/api/embed", &*self.with_defaults(request))?;
+        if response.embeddings.len() != request.input.len() {
+            return Err(OllamaError::InvalidResponse(format!(
+                "expected {} embeddings, got {}",
+                request.input.len(),
+                response.embeddings.len()
+            )));
+        }
+        Ok(response)
+    }
+
+    // Embeds any number of inputs, `batch_size` at a time, in input order
+    pub fn embed_batch(
+        &self,
+        request: &EmbedRequest,
+        batch_size: usize,
+    ) -> Result<Vec<Vec<f32>>, OllamaError> {
+        let mut embeddings = Vec::with_capacity(request.input.len());
+        for batch in request.input.chunks(batch_size.max(1)) {
+            let batch = EmbedRequest {
+                input: batch.to_vec(),
+                ..request.clone()
+            };
+            embeddings.extend(self.embed(&batch)?.embeddings);
+        }
+        Ok(embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockOllama;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_embed_batch() {
+        let reply = |embeddings: Value| json!({ "model": "all-minilm", "embeddings": embeddings });
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/embed",
+                200,
+                reply(json!([[0.1, 0.2], [0.3, 0.4]])),
+            )
+            .respond(
+                "POST",
+                "/api/embed",
+                200,
+                reply(json!([[0.5, 0.6], [0.7, 0.8]])),
+            )
+            .respond("POST", "/api/embed", 200, reply(json!([[0.9, 1.0]])));
+
+        let texts = ["a", "b", "c", "d", "e"];
+        let request = EmbedRequest::new("all-minilm", texts).truncate(false);
+        let embeddings = mock.client().embed_batch(&request, 2).unwrap();
+        assert_eq!(embeddings.len(), 5);
+        assert_eq!(embeddings[4], [0.9, 1.0]);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 4);
+        let body = mock.request_json(3);
+        assert_eq!(body["input"], json!(["e"]));
+        assert_eq!(body["truncate"], false);
+    }
+
+    #[test]
+    fn test_embedding_count_mismatch() {
+        let mock = MockOllama::new().respond(
+            "POST",
+            "/api/embed",
+            200,
+            json!({ "model": "all-minilm", "embeddings": [[0.1]] }),
+        );
+        let error = mock
+            .client()
+            .embed(&EmbedRequest::new(
+                "all-minilm",
+                vec!["a".to_string(), "b".to_string()],
+            ))
+            .unwrap_err();
+        assert!(
+            matches!(error, OllamaError::InvalidResponse(_)),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum OllamaError {
+    // The server could not be reached or the connection failed mid-request
+    Connection(io::Error),
+    // A socket operation did not complete in time
+    Timeout,
+    // The server answered with a non-success status the variants below don't cover, e.g. 401
+    // from an authenticating proxy or 429
+    Http {
+        status: u16,
+        message: String,
+    },
+    // 400: the server rejected the request, e.g. an invalid option or a malformed format
+    BadRequest(String),
+    // 503: the server is busy loading a model or has too many requests queued, try again later
+    ModelLoading(String),
+    // Any other 5xx, e.g. the model runner crashed or ran out of memory
+    ServerError {
+        status: u16,
+        message: String,
+    },
+    // The server reported an error inside an otherwise successful response
+    Api {
+        message: String,
+    },
+    // A response body was not the JSON we expected
+    Json(serde_json::Error),
+    // The response was well-formed JSON/HTTP but not shaped like an Ollama response
+    InvalidResponse(String),
+    // The model's reply did not deserialize into the requested type
+    InvalidOutput {
+        output: String,
+        error: serde_json::Error,
+    },
+    // The model's reply still failed to deserialize or validate after every repair attempt,
+    // see prompt_validated
+    ValidationFailed {
+        output: String,
+        attempts: usize,
+        message: String,
+    },
+    // An Agent made this many chat requests without the model giving a final answer
+    StepLimit(usize),
+    // The call was stopped through its CancellationToken
+    Cancelled,
+    // The call panicked on the async client's worker thread, e.g. inside an Interceptor
+    Panicked(String),
+    // A PromptTemplate didn't parse, or a variable it needs had no value
+    Template(String),
+    // The request can't be sent as given, e.g. a header value with a line break in it.
+    // Retrying or another host won't help.
+    InvalidRequest(String),
+    // A model name that isn't `[host/][namespace/]model[:tag][@digest]`, see ModelName
+    InvalidModelName(String),
+    // The named model isn't installed: the server said so with a 404, or ensure_model found it
+    // missing and wasn't allowed to pull it
+    ModelNotFound(String),
+    // A local file (image, model blob, ...) could not be read or written, or the
+    // `ollama serve` process could not be started or stopped
+    Io(io::Error),
+}
+
+impl fmt::Display for OllamaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OllamaError::Connection(e) => write!(f, "connection error: {}", e),
+            OllamaError::Timeout => write!(f, "request timed out"),
+            OllamaError::Http { status, message } => write!(f, "HTTP {}: {}", status, message),
+            OllamaError::BadRequest(message) => write!(f, "bad request: {}", message),
+            OllamaError::ModelLoading(message) => {
+                write!(f, "server busy, try again later: {}", message)
+            }
+            OllamaError::ServerError { status, message } => {
+                write!(f, "server error {}: {}", status, message)
+            }
+            OllamaError::Api { message } => write!(f, "ollama error: {}", message),
+            OllamaError::Json(e) => write!(f, "JSON parse error: {}", e),
+            OllamaError::InvalidResponse(message) => write!(f, "invalid response: {}", message),
+            OllamaError::InvalidOutput { error, .. } => {
+                write!(
+                    f,
+                    "model output did not match the requested format: {}",
+                    error
+                )
+            }
+            OllamaError::ValidationFailed {
+                attempts, message, ..
+            } => write!(
+                f,
+                "model output was still invalid after {} attempts: {}",
+                attempts, message
+            ),
+            OllamaError::StepLimit(steps) => {
+                write!(f, "the model gave no final answer within {} steps", steps)
+            }
+            OllamaError::Cancelled => write!(f, "request was cancelled"),
+            OllamaError::Panicked(message) => write!(f, "the call panicked: {}", message),
+            OllamaError::Template(message) => write!(f, "prompt template error: {}", message),
+            OllamaError::InvalidRequest(message) => write!(f, "invalid request: {}", message),
+            OllamaError::InvalidModelName(message) => write!(f, "invalid model name {}", message),
+            OllamaError::ModelNotFound(name) => {
+                write!(f, "model {} is not installed, pull it first", name)
+            }
+            OllamaError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl OllamaError {
+    // Sorts a non-success response into the variant for its cause, `message` is the server's
+    // {"error": ...} text
+    pub(crate) fn from_status(status: u16, message: String) -> OllamaError {
+        match status {
+            400 => OllamaError::BadRequest(message),
+            404 => match missing_model(&message) {
+                Some(name) => OllamaError::ModelNotFound(name),
+                None => OllamaError::Http { status, message },
+            },
+            503 => OllamaError::ModelLoading(message),
+            500..=599 => OllamaError::ServerError { status, message },
+            _ => OllamaError::Http { status, message },
+        }
+    }
+
+    // The HTTP status the server failed the call with, None for failures on this side
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            OllamaError::Http { status, .. } | OllamaError::ServerError { status, .. } => {
+                Some(*status)
+            }
+            OllamaError::BadRequest(_) => Some(400),
+            OllamaError::ModelLoading(_) => Some(503),
+            _ => None,
+        }
+    }
+}
+
+// The name in `model "llama3" not found, try pulling it first`, with either kind of quotes
+fn missing_model(message: &str) -> Option<String> {
+    let rest = message.strip_prefix("model ")?;
+    let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    let (name, tail) = rest[1..].split_once(quote)?;
+    tail.trim_start()
+        .starts_with("not found")
+        .then(|| name.to_string())
+}
+
+impl std::error::Error for OllamaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OllamaError::Connection(e) | OllamaError::Io(e) => Some(e),
+            OllamaError::Json(e) | OllamaError::InvalidOutput { error: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for OllamaError {
+    fn from(error: io::Error) -> OllamaError {
+        match error.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => OllamaError::Timeout,
+            // Raised by the HTTP layer when the server's reply can't be framed
+            io::ErrorKind::InvalidData => OllamaError::InvalidResponse(error.to_string()),
+            // Raised before anything is sent, when the request itself is malformed
+            io::ErrorKind::InvalidInput => OllamaError::InvalidRequest(error.to_string()),
+            _ => OllamaError::Connection(error),
+        }
+    }
+}
+
+impl From<serde_json::Error> for OllamaError {
+    fn from(error: serde_json::Error) -> OllamaError {
+        OllamaError::Json(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_error_mapping() {
+        let timeout = io::Error::new(io::ErrorKind::TimedOut, "slow");
+        assert!(matches!(OllamaError::from(timeout), OllamaError::Timeout));
+
+        let refused = io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+        assert!(matches!(
+            OllamaError::from(refused),
+            OllamaError::Connection(_)
+        ));
+
+        let framing = io::Error::new(io::ErrorKind::InvalidData, "bad chunk");
+        assert!(matches!(
+            OllamaError::from(framing),
+            OllamaError::InvalidResponse(_)
+        ));
+
+        let header = io::Error::new(io::ErrorKind::InvalidInput, "invalid header: X-Evil");
+        assert!(matches!(
+            OllamaError::from(header),
+            OllamaError::InvalidRequest(message) if message == "invalid header: X-Evil"
+        ));
+    }
+
+    #[test]
+    fn test_status_mapping() {
+        let error = |status, message: &str| OllamaError::from_status(status, message.to_string());
+        assert!(matches!(
+            error(404, "model \"llama9\" not found, try pulling it first"),
+            OllamaError::ModelNotFound(name) if name == "llama9"
+        ));
+        assert!(matches!(
+            error(404, "model 'nope' not found"),
+            OllamaError::ModelNotFound(name) if name == "nope"
+        ));
+        assert!(matches!(
+            error(404, "404 page not found"),
+            OllamaError::Http { status: 404, .. }
+        ));
+        assert!(matches!(
+            error(400, "invalid format"),
+            OllamaError::BadRequest(_)
+        ));
+        assert!(matches!(
+            error(503, "server busy, please try again"),
+            OllamaError::ModelLoading(_)
+        ));
+        assert!(matches!(
+            error(500, "llama runner process has terminated"),
+            OllamaError::ServerError { status: 500, .. }
+        ));
+        assert!(matches!(
+            error(401, "unauthorized"),
+            OllamaError::Http { status: 401, .. }
+        ));
+        assert_eq!(error(503, "busy").status(), Some(503));
+        assert_eq!(OllamaError::Timeout.status(), None);
+    }
+}
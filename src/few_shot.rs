@@ -0,0 +1,144 @@
+use crate::{ChatMessage, ChatRequest, Ollama, OllamaError};
+
+// An instruction plus worked input -> output examples, for classification and extraction
+// tasks. The same examples go out as one completion prompt or as a chat transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FewShot {
+    pub instruction: String,
+    pub examples: Vec<(String, String)>,
+    // Prefixes for inputs and outputs in the completion prompt
+    pub input_label: String,
+    pub output_label: String,
+}
+
+impl FewShot {
+    pub fn new(instruction: impl Into<String>) -> FewShot {
+        FewShot {
+            instruction: instruction.into(),
+            examples: Vec::new(),
+            input_label: "Input".to_string(),
+            output_label: "Output".to_string(),
+        }
+    }
+
+    pub fn example(mut self, input: impl Into<String>, output: impl Into<String>) -> FewShot {
+        self.examples.push((input.into(), output.into()));
+        self
+    }
+
+    // e.g. ("Review", "Sentiment") instead of ("Input", "Output")
+    pub fn labels(mut self, input: impl Into<String>, output: impl Into<String>) -> FewShot {
+        self.input_label = input.into();
+        self.output_label = output.into();
+        self
+    }
+
+    // A completion prompt that ends right where the model should write the answer
+    pub fn prompt(&self, input: &str) -> String {
+        let mut prompt = String::new();
+        if !self.instruction.is_empty() {
+            prompt.push_str(&self.instruction);
+            prompt.push_str("\n\n");
+        }
+        for (example, answer) in &self.examples {
+            prompt.push_str(&format!(
+                "{}: {}\n{}: {}\n\n",
+                self.input_label, example, self.output_label, answer
+            ));
+        }
+        prompt.push_str(&format!(
+            "{}: {}\n{}:",
+            self.input_label, input, self.output_label
+        ));
+        prompt
+    }
+
+    // The instruction as system message, each example as a user/assistant exchange, then
+    // `input` as the last user message
+    pub fn messages(&self, input: &str) -> Vec<ChatMessage> {
+        let mut messages = Vec::new();
+        if !self.instruction.is_empty() {
+            messages.push(ChatMessage::system(self.instruction.clone()));
+        }
+        for (example, answer) in &self.examples {
+            messages.push(ChatMessage::user(example.clone()));
+            messages.push(ChatMessage::assistant(answer.clone()));
+        }
+        messages.push(ChatMessage::user(input));
+        messages
+    }
+}
+
+impl Ollama {
+    // Runs `input` through the examples as a chat and returns the trimmed answer
+    pub fn few_shot(
+        &self,
+        model: String,
+        few_shot: &FewShot,
+        input: &str,
+    ) -> Result<String, OllamaError> {
+        let response = self.chat(&ChatRequest::new(model, few_shot.messages(input)))?;
+        Ok(response.message.content.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockOllama, Role};
+    use serde_json::json;
+
+    fn sentiment() -> FewShot {
+        FewShot::new("Classify the sentiment of the review.")
+            .labels("Review", "Sentiment")
+            .example("Loved it!", "positive")
+            .example("Waste of money.", "negative")
+    }
+
+    #[test]
+    fn test_prompt() {
+        assert_eq!(
+            sentiment().prompt("It was fine."),
+            "Classify the sentiment of the review.\n\n\
+             Review: Loved it!\nSentiment: positive\n\n\
+             Review: Waste of money.\nSentiment: negative\n\n\
+             Review: It was fine.\nSentiment:"
+        );
+        assert_eq!(FewShot::new("").prompt("2+2"), "Input: 2+2\nOutput:");
+    }
+
+    #[test]
+    fn test_few_shot_chat() {
+        let mock = MockOllama::new().respond(
+            "POST",
+            "/api/chat",
+            200,
+            json!({
+                "model": "llama3",
+                "message": { "role": "assistant", "content": " neutral\n" },
+                "done": true
+            }),
+        );
+        let answer = mock
+            .client()
+            .few_shot("llama3".to_string(), &sentiment(), "It was fine.")
+            .unwrap();
+        assert_eq!(answer, "neutral");
+
+        let body = mock.request_json(1);
+        let messages: Vec<ChatMessage> = serde_json::from_value(body["messages"].clone()).unwrap();
+        let roles: Vec<Role> = messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            [
+                Role::System,
+                Role::User,
+                Role::Assistant,
+                Role::User,
+                Role::Assistant,
+                Role::User
+            ]
+        );
+        assert_eq!(messages[5].content, "It was fine.");
+    }
+}
@@ -0,0 +1,709 @@
+use std::{
+    io::Write,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    GenerationStats, IntoModelName, KeepAlive, Ollama, OllamaError, Options, TokenLogprob,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GenerateRequest {
+    pub model: String,
+    pub prompt: String,
+    // Text after the insertion point, for fill-in-the-middle with code models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<Vec<i64>>,
+    // Base64-encoded images for multimodal models, see encode_image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<String>>,
+    // Either "json" or a JSON schema the reply must conform to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<Value>,
+    // Sends the prompt as-is, bypassing the model's prompt template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Options>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<KeepAlive>,
+    // Thinking models reason first and return it apart from the answer. Some think by
+    // default, false turns it off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub think: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u32>,
+    stream: bool,
+}
+
+impl GenerateRequest {
+    pub fn new(model: impl Into<String>, prompt: impl Into<String>) -> GenerateRequest {
+        GenerateRequest {
+            model: model.into(),
+            prompt: prompt.into(),
+            suffix: None,
+            system: None,
+            template: None,
+            context: None,
+            images: None,
+            format: None,
+            raw: None,
+            options: None,
+            keep_alive: None,
+            think: None,
+            logprobs: None,
+            top_logprobs: None,
+            stream: false,
+        }
+    }
+
+    pub fn suffix(mut self, suffix: impl Into<String>) -> GenerateRequest {
+        self.suffix = Some(suffix.into());
+        self
+    }
+
+    // Overrides the system message defined in the Modelfile
+    pub fn system(mut self, system: impl Into<String>) -> GenerateRequest {
+        self.system = Some(system.into());
+        self
+    }
+
+    // Overrides the prompt template defined in the Modelfile
+    pub fn template(mut self, template: impl Into<String>) -> GenerateRequest {
+        self.template = Some(template.into());
+        self
+    }
+
+    // Continues from the `context` returned by a previous response
+    pub fn context(mut self, context: Vec<i64>) -> GenerateRequest {
+        self.context = Some(context);
+        self
+    }
+
+    pub fn images(mut self, images: Vec<String>) -> GenerateRequest {
+        self.images = Some(images);
+        self
+    }
+
+    pub fn format(mut self, format: Value) -> GenerateRequest {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn raw(mut self, raw: bool) -> GenerateRequest {
+        self.raw = Some(raw);
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> GenerateRequest {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn keep_alive(mut self, keep_alive: KeepAlive) -> GenerateRequest {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    pub fn think(mut self, think: bool) -> GenerateRequest {
+        self.think = Some(think);
+        self
+    }
+
+    // Returns the log probability of each generated token, see TokenLogprob
+    pub fn logprobs(mut self, logprobs: bool) -> GenerateRequest {
+        self.logprobs = Some(logprobs);
+        self
+    }
+
+    // Also returns the `top` most likely alternatives for each token, up to 20
+    pub fn top_logprobs(mut self, top: u32) -> GenerateRequest {
+        self.logprobs = Some(true);
+        self.top_logprobs = Some(top);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GenerateResponse {
+    pub model: String,
+    #[serde(default)]
+    pub response: String,
+    // The model's reasoning, kept out of `response`, see GenerateRequest::think
+    pub thinking: Option<String>,
+    #[serde(default)]
+    pub done: bool,
+    pub done_reason: Option<String>,
+    // Encoding of the conversation so far, pass it to the next request to keep a short memory
+    pub context: Option<Vec<i64>>,
+    // Set when the request asked for logprobs, for the tokens in this chunk
+    pub logprobs: Option<Vec<TokenLogprob>>,
+    #[serde(flatten)]
+    pub stats: GenerationStats,
+}
+
+impl Ollama {
+    pub fn generate(&self, request: &GenerateRequest) -> Result<GenerateResponse, OllamaError> {
+        self.post_json_cached("/api/generate", &*self.with_defaults(request))
+    }
+
+    // Yields the reply piece by piece as the model produces it, the last chunk has `done` set
+    pub fn generate_stream(
+        &self,
+        request: &GenerateRequest,
+    ) -> Result<
+        impl Iterator<Item = Result<GenerateResponse, OllamaError>> + Send + use<>,
+        OllamaError,
+    > {
+        let request = GenerateRequest {
+            stream: true,
+            ..self.with_defaults(request).into_owned()
+        };
+        self.post_stream("/api/generate", &request)
+    }
+
+    // generate_stream as a futures Stream, the request runs on a background thread
+    #[cfg(feature = "async")]
+    pub fn generate_stream_async(
+        &self,
+        request: &GenerateRequest,
+    ) -> impl futures_core::Stream<Item = Result<GenerateResponse, OllamaError>> + Send + Unpin + use<>
+    {
+        let request = request.clone();
+        crate::stream::ThreadStream::call(self, move |ollama| ollama.generate_stream(&request))
+    }
+
+    // Asks the client's default model, see with_default_model. Without one the server
+    // rejects the request.
+    pub fn prompt(&self, prompt: impl Into<String>) -> Result<String, OllamaError> {
+        self.prompt_with("", prompt)
+    }
+
+    pub fn prompt_with(
+        &self,
+        model: impl Into<String>,
+        prompt: impl Into<String>,
+    ) -> Result<String, OllamaError> {
+        Ok(self
+            .generate(&GenerateRequest::new(model, prompt))?
+            .response)
+    }
+
+    // Writes the reply into `writer` piece by piece as the model produces it, so a long
+    // completion can go to stdout, a file or a response body without being held in memory.
+    // Flushes after every piece. Returns the counts and timings of the finished generation.
+    pub fn prompt_to_writer(
+        &self,
+        model: impl Into<String>,
+        prompt: impl Into<String>,
+        writer: &mut impl Write,
+    ) -> Result<GenerationStats, OllamaError> {
+        self.generate_to_writer(&GenerateRequest::new(model, prompt), writer)
+    }
+
+    pub fn generate_to_writer(
+        &self,
+        request: &GenerateRequest,
+        writer: &mut impl Write,
+    ) -> Result<GenerationStats, OllamaError> {
+        let mut stats = GenerationStats::default();
+        for chunk in self.generate_stream(request)? {
+            let chunk = chunk?;
+            writer
+                .write_all(chunk.response.as_bytes())
+                .and_then(|()| writer.flush())
+                .map_err(OllamaError::Io)?;
+            if chunk.done {
+                stats = chunk.stats;
+            }
+        }
+        Ok(stats)
+    }
+
+    // Tokens `text` takes up, to check against `context_length` from show_model. The server
+    // doesn't count a prefix still cached from the previous request, so that can come out low.
+    pub fn count_tokens(&self, model: String, text: String) -> Result<u64, OllamaError> {
+        let request = GenerateRequest::new(model, text)
+            .raw(true)
+            .options(Options::default().num_predict(0));
+        Ok(self.generate(&request)?.stats.prompt_eval_count)
+    }
+
+    // Runs the prompts with at most `concurrency` requests in flight. The results are in the
+    // order of `prompts`, one failed prompt doesn't stop the others.
+    pub fn prompt_batch(
+        &self,
+        model: String,
+        prompts: Vec<String>,
+        concurrency: usize,
+    ) -> Vec<Result<String, OllamaError>> {
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<Result<String, OllamaError>>>> =
+            Mutex::new((0..prompts.len()).map(|_| None).collect());
+
+        std::thread::scope(|scope| {
+            for _ in 0..concurrency.clamp(1, prompts.len().max(1)) {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(prompt) = prompts.get(i) else {
+                            break;
+                        };
+                        let result = self.prompt_with(model.clone(), prompt.clone());
+                        results.lock().unwrap()[i] = Some(result);
+                    }
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.expect("every prompt was run"))
+            .collect()
+    }
+
+    // Fills in the code between prefix and suffix, the model must support insertion (FIM)
+    pub fn complete_code(
+        &self,
+        model: String,
+        prefix: String,
+        suffix: String,
+    ) -> Result<String, OllamaError> {
+        Ok(self
+            .generate(&GenerateRequest::new(model, prefix).suffix(suffix))?
+            .response)
+    }
+
+    // Constrains the reply with T's JSON schema and deserializes it
+    #[cfg(feature = "schemars")]
+    pub fn prompt_structured<T: serde::de::DeserializeOwned + schemars::JsonSchema>(
+        &self,
+        model: String,
+        prompt: String,
+    ) -> Result<T, OllamaError> {
+        let schema = serde_json::to_value(schemars::schema_for!(T))?;
+        let response = self.generate(&GenerateRequest::new(model, prompt).format(schema))?;
+
+        serde_json::from_str(&response.response).map_err(|error| OllamaError::InvalidOutput {
+            output: response.response,
+            error,
+        })
+    }
+
+    // An empty prompt only loads the model into memory
+    pub fn preload_model(&self, name: impl IntoModelName) -> Result<(), OllamaError> {
+        let name = name.into_model_name()?.to_string();
+        self.generate(&GenerateRequest::new(name, ""))?;
+        Ok(())
+    }
+
+    pub fn unload_model(&self, name: impl IntoModelName) -> Result<(), OllamaError> {
+        let name = name.into_model_name()?.to_string();
+        self.generate(&GenerateRequest::new(name, "").keep_alive(KeepAlive::UnloadNow))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{json_response, serve};
+
+    #[test]
+    fn test_request_escapes_prompt() {
+        let prompt = "Say \"hi\"\nthen a backslash: \\ and a tab\t";
+        let request = GenerateRequest::new("llama3", prompt);
+        let body = serde_json::to_string(&request).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed["prompt"], prompt);
+        assert_eq!(parsed["stream"], false);
+    }
+
+    #[test]
+    fn test_system_and_template_overrides() {
+        let request = GenerateRequest::new("llama3", "Why is the sky blue?")
+            .system("Answer like a pirate.")
+            .template("{{ .System }} {{ .Prompt }}");
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["system"], "Answer like a pirate.");
+        assert_eq!(value["template"], "{{ .System }} {{ .Prompt }}");
+
+        let value = serde_json::to_value(GenerateRequest::new("llama3", "Hi")).unwrap();
+        assert!(value.get("system").is_none());
+        assert!(value.get("template").is_none());
+    }
+
+    #[test]
+    fn test_raw_mode() {
+        let prompt = "[INST] why is the sky blue? [/INST]";
+        let value =
+            serde_json::to_value(GenerateRequest::new("mistral", prompt).raw(true)).unwrap();
+        assert_eq!(value["raw"], true);
+        assert_eq!(value["prompt"], prompt);
+    }
+
+    #[test]
+    fn test_complete_code() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response(
+                "200 OK",
+                r#"{"model":"codellama:code","response":"a + b","done":true}"#,
+            ),
+        ]);
+
+        let completion = ollama
+            .complete_code(
+                "codellama:code".to_string(),
+                "def add(a, b):\n    return ".to_string(),
+                "\n\nprint(add(1, 2))".to_string(),
+            )
+            .unwrap();
+        assert_eq!(completion, "a + b");
+
+        let requests = server.join().unwrap();
+        let body: Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body["prompt"], "def add(a, b):\n    return ");
+        assert_eq!(body["suffix"], "\n\nprint(add(1, 2))");
+    }
+
+    #[test]
+    fn test_context_round_trip() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response(
+                "200 OK",
+                r#"{"model":"llama3","response":"Hi Bob","done":true,"context":[1,2,3]}"#,
+            ),
+            json_response(
+                "200 OK",
+                r#"{"model":"llama3","response":"Bob","done":true,"context":[1,2,3,4,5]}"#,
+            ),
+        ]);
+
+        let first = ollama
+            .generate(&GenerateRequest::new("llama3", "I'm Bob"))
+            .unwrap();
+        assert_eq!(first.context, Some(vec![1, 2, 3]));
+
+        let second = ollama
+            .generate(
+                &GenerateRequest::new("llama3", "What's my name?").context(first.context.unwrap()),
+            )
+            .unwrap();
+        assert_eq!(second.response, "Bob");
+
+        let requests = server.join().unwrap();
+        let body: serde_json::Value = serde_json::from_str(&requests[2].body).unwrap();
+        assert_eq!(body["context"], serde_json::json!([1, 2, 3]));
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_prompt_structured() {
+        #[derive(Debug, PartialEq, Deserialize, schemars::JsonSchema)]
+        struct Country {
+            name: String,
+            capital: String,
+        }
+
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response(
+                "200 OK",
+                r#"{"model":"llama3","response":"{\"name\":\"Canada\",\"capital\":\"Ottawa\"}","done":true}"#,
+            ),
+            json_response(
+                "200 OK",
+                r#"{"model":"llama3","response":"{\"name\":\"Canada\"}","done":true}"#,
+            ),
+        ]);
+
+        let country: Country = ollama
+            .prompt_structured("llama3".to_string(), "Tell me about Canada".to_string())
+            .unwrap();
+        assert_eq!(country.capital, "Ottawa");
+
+        let error = ollama
+            .prompt_structured::<Country>("llama3".to_string(), "Tell me about Canada".to_string())
+            .unwrap_err();
+        assert!(
+            matches!(error, OllamaError::InvalidOutput { ref output, .. } if output == r#"{"name":"Canada"}"#)
+        );
+
+        let requests = server.join().unwrap();
+        let body: Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body["format"]["properties"]["capital"]["type"], "string");
+    }
+
+    #[test]
+    fn test_generate_stream() {
+        let mock = crate::MockOllama::new().stream(
+            "POST",
+            "/api/generate",
+            vec![
+                serde_json::json!({ "model": "llama3", "response": "The sky", "done": false }),
+                serde_json::json!({ "model": "llama3", "response": " is blue.", "done": false }),
+                serde_json::json!({
+                    "model": "llama3", "response": "", "done": true, "done_reason": "stop",
+                    "eval_count": 4, "eval_duration": 200_000_000
+                }),
+            ],
+        );
+        let server = mock.listen().unwrap();
+        let ollama = server.client();
+
+        let chunks: Vec<GenerateResponse> = ollama
+            .generate_stream(&GenerateRequest::new("llama3", "Why is the sky blue?"))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let text: String = chunks.iter().map(|c| c.response.as_str()).collect();
+        assert_eq!(text, "The sky is blue.");
+        assert!(chunks.last().unwrap().done);
+        assert_eq!(chunks[0].stats, GenerationStats::default());
+        assert_eq!(chunks[2].stats.tokens_per_second(), Some(20.0));
+
+        let body = mock.request_json(1);
+        assert_eq!(body["stream"], true);
+
+        #[cfg(feature = "async")]
+        {
+            let stream = ollama
+                .generate_stream_async(&GenerateRequest::new("llama3", "Why is the sky blue?"));
+            let chunks = crate::stream::tests::collect(stream);
+            assert_eq!(chunks.len(), 3);
+            assert!(chunks[2].as_ref().unwrap().done);
+        }
+    }
+
+    // The client's half of the guarantee: nothing varies between identical requests
+    #[test]
+    fn test_deterministic_requests_repeat() {
+        let mock = crate::MockOllama::new().respond(
+            "POST",
+            "/api/generate",
+            200,
+            serde_json::json!({ "model": "llama3", "response": "Blue.", "done": true }),
+        );
+        let ollama = mock.client();
+        let request = || {
+            GenerateRequest::new("llama3", "Sky colour?")
+                .system("Answer in one word.")
+                .options(Options::deterministic(7))
+        };
+        for _ in 0..3 {
+            assert_eq!(ollama.generate(&request()).unwrap().response, "Blue.");
+        }
+
+        let bodies: Vec<_> = mock.requests()[1..]
+            .iter()
+            .map(|r| r.body.clone().unwrap())
+            .collect();
+        assert!(bodies.windows(2).all(|pair| pair[0] == pair[1]));
+        let body = mock.request_json(1);
+        assert_eq!(
+            body["options"],
+            serde_json::json!({ "temperature": 0.0, "top_k": 1, "seed": 7 })
+        );
+    }
+
+    #[test]
+    fn test_prompt_to_writer() {
+        let mock = crate::MockOllama::new().stream(
+            "POST",
+            "/api/generate",
+            vec![
+                serde_json::json!({ "model": "llama3", "response": "Once upon", "done": false }),
+                serde_json::json!({ "model": "llama3", "response": " a time", "done": false }),
+                serde_json::json!({ "model": "llama3", "response": "", "done": true, "eval_count": 3 }),
+            ],
+        );
+        let ollama = mock.client();
+
+        let mut out = Vec::new();
+        let stats = ollama
+            .prompt_to_writer("llama3", "Tell a story", &mut out)
+            .unwrap();
+        assert_eq!(out, b"Once upon a time");
+        assert_eq!(stats.eval_count, 3);
+
+        // A failed write ends the call
+        let mut full = &mut [0u8; 4][..];
+        assert!(matches!(
+            ollama.prompt_to_writer("llama3", "Tell a story", &mut full),
+            Err(OllamaError::Io(_))
+        ));
+
+        #[cfg(feature = "async")]
+        {
+            let future = ollama.clone().into_async().prompt_to_writer(
+                "llama3".into(),
+                "Tell a story".into(),
+                Vec::new(),
+            );
+            let (out, stats) = crate::stream::tests::block_on(future).unwrap();
+            assert_eq!(out, b"Once upon a time");
+            assert_eq!(stats.eval_count, 3);
+
+            let ollama = ollama.clone().into_async();
+            let mut out = Vec::new();
+            let future =
+                ollama.prompt_to_async_writer("llama3".into(), "Tell a story".into(), &mut out);
+            let stats = crate::stream::tests::block_on(Box::pin(future)).unwrap();
+            assert_eq!(out, b"Once upon a time");
+            assert_eq!(stats.eval_count, 3);
+        }
+    }
+
+    #[test]
+    fn test_prompt_batch() {
+        use crate::{Request, Response, Transport};
+        use std::{sync::Arc, time::Duration};
+
+        // Echoes the prompt back in upper case, slowly, and tracks how many calls overlap
+        #[derive(Default)]
+        struct Echo {
+            running: AtomicUsize,
+            max_running: AtomicUsize,
+        }
+
+        impl Transport for Arc<Echo> {
+            fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+                let Some(crate::Body::Json(body)) = &request.body else {
+                    return Ok(Response::new(200, &br#"{"version":"0.5.1"}"#[..]));
+                };
+                let body: Value = serde_json::from_slice(body).unwrap();
+                let prompt = body["prompt"].as_str().unwrap().to_string();
+                if prompt == "fail" {
+                    return Ok(Response::new(500, &br#"{"error":"boom"}"#[..]));
+                }
+
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_running.fetch_max(running, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                self.running.fetch_sub(1, Ordering::SeqCst);
+
+                let reply = serde_json::json!({
+                    "model": body["model"],
+                    "response": prompt.to_uppercase(),
+                    "done": true
+                });
+                Ok(Response::new(200, std::io::Cursor::new(reply.to_string())))
+            }
+        }
+
+        let echo = Arc::new(Echo::default());
+        let ollama = Ollama::connect_transport(echo.clone()).unwrap();
+        let prompts: Vec<String> = ["a", "b", "fail", "c", "d", "e", "f"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+
+        let results = ollama.prompt_batch("llama3".to_string(), prompts, 3);
+        assert_eq!(results.len(), 7);
+        assert!(matches!(
+            results[2],
+            Err(OllamaError::ServerError { status: 500, .. })
+        ));
+        let answers: Vec<&str> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(String::as_str)
+            .collect();
+        assert_eq!(answers, ["A", "B", "C", "D", "E", "F"]);
+        assert!(echo.max_running.load(Ordering::SeqCst) <= 3);
+        assert!(echo.max_running.load(Ordering::SeqCst) > 1);
+
+        assert!(
+            ollama
+                .prompt_batch("llama3".to_string(), vec![], 0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_logprobs() {
+        let mock = crate::MockOllama::new().respond(
+            "POST",
+            "/api/generate",
+            200,
+            serde_json::json!({
+                "model": "llama3", "response": "Blue", "done": true,
+                "logprobs": [{
+                    "token": "Blue", "logprob": -0.25,
+                    "top_logprobs": [{ "token": "Blue", "logprob": -0.25 }, { "token": "Gray", "logprob": -1.75 }]
+                }]
+            }),
+        );
+        let response = mock
+            .client()
+            .generate(&GenerateRequest::new("llama3", "What color is the sky?").top_logprobs(2))
+            .unwrap();
+        let logprobs = response.logprobs.unwrap();
+        assert_eq!(logprobs[0].top_logprobs[1].token, "Gray");
+        assert_eq!(crate::perplexity(&logprobs), Some(0.25f64.exp()));
+
+        let body = mock.request_json(1);
+        assert_eq!(body["logprobs"], true);
+        assert_eq!(body["top_logprobs"], 2);
+        let value = serde_json::to_value(GenerateRequest::new("llama3", "Hi")).unwrap();
+        assert!(value.get("logprobs").is_none());
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        let mock = crate::MockOllama::new().respond(
+            "POST",
+            "/api/generate",
+            200,
+            serde_json::json!({
+                "model": "llama3", "response": "", "done": true, "done_reason": "length",
+                "prompt_eval_count": 7
+            }),
+        );
+        let count = mock
+            .client()
+            .count_tokens("llama3".to_string(), "Why is the sky blue?".to_string())
+            .unwrap();
+        assert_eq!(count, 7);
+
+        let body = mock.request_json(1);
+        assert_eq!(body["raw"], true);
+        assert_eq!(body["options"]["num_predict"], 0);
+    }
+
+    #[test]
+    fn test_unload_model() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response(
+                "200 OK",
+                r#"{"model":"llama3","response":"","done":true,"done_reason":"unload"}"#,
+            ),
+        ]);
+
+        ollama.unload_model("llama3".to_string()).unwrap();
+        let requests = server.join().unwrap();
+        let body: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body["model"], "llama3:latest");
+        assert_eq!(body["keep_alive"], 0);
+    }
+}
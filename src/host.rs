@@ -0,0 +1,167 @@
+use std::net::IpAddr;
+
+// Where an Ollama server can be reached, parsed the same way the ollama CLI parses OLLAMA_HOST
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    // Put before every API path, for a server behind a gateway at e.g. /ollama. Empty or
+    // starting with "/" and without a trailing one.
+    pub base_path: String,
+}
+
+impl Default for Host {
+    fn default() -> Host {
+        Host {
+            scheme: "http".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 11434,
+            base_path: String::new(),
+        }
+    }
+}
+
+impl Host {
+    // The socket path is kept in `host`, the port is unused
+    pub fn unix(path: impl Into<String>) -> Host {
+        Host {
+            scheme: "unix".to_string(),
+            host: path.into(),
+            port: 0,
+            base_path: String::new(),
+        }
+    }
+
+    pub fn from_env() -> Option<Host> {
+        let value = std::env::var("OLLAMA_HOST").ok()?;
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if value.is_empty() {
+            return None;
+        }
+        Some(Host::parse(value))
+    }
+
+    // Accepts "host", "host:port", "scheme://host[:port][/path]" and bracketed IPv6 forms.
+    // Anything unparseable falls back to the default host, like the CLI does.
+    // "unix:///path/to/socket" selects a unix socket, see Host::unix.
+    pub fn parse(value: &str) -> Host {
+        let value = value.trim();
+        if let Some(path) = value.strip_prefix("unix://") {
+            return Host::unix(path);
+        }
+
+        let (scheme, rest, default_port) = match value.split_once("://") {
+            None => ("http", value, 11434),
+            Some(("http", rest)) => ("http", rest, 80),
+            Some(("https", rest)) => ("https", rest, 443),
+            Some((scheme, rest)) => (scheme, rest, 11434),
+        };
+
+        let (host_port, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let path = path.trim_end_matches('/');
+        let base_path = if path.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", path)
+        };
+
+        let (host, port) = match split_host_port(host_port) {
+            // An empty host means the local machine, e.g. ":1234"
+            Some(("", port)) => ("127.0.0.1".to_string(), port),
+            Some((host, port)) => (host.to_string(), port),
+            None => {
+                let bare = host_port.trim_start_matches('[').trim_end_matches(']');
+                let host = if let Ok(ip) = bare.parse::<IpAddr>() {
+                    ip.to_string()
+                } else if !host_port.is_empty() {
+                    host_port.to_string()
+                } else {
+                    "127.0.0.1".to_string()
+                };
+                (host, default_port.to_string())
+            }
+        };
+
+        match port.parse::<u16>() {
+            Ok(port) => Host {
+                scheme: scheme.to_string(),
+                host,
+                port,
+                base_path,
+            },
+            Err(_) => Host::default(),
+        }
+    }
+}
+
+fn split_host_port(value: &str) -> Option<(&str, String)> {
+    if let Some(rest) = value.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:")?;
+        return Some((host, port.to_string()));
+    }
+
+    let (host, port) = value.rsplit_once(':')?;
+    if host.contains(':') {
+        // An unbracketed IPv6 address, there is no port
+        return None;
+    }
+    Some((host, port.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(scheme: &str, host: &str, port: u16) -> Host {
+        Host {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            port,
+            base_path: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_host_forms() {
+        assert_eq!(Host::parse(""), host("http", "127.0.0.1", 11434));
+        assert_eq!(Host::parse("1.2.3.4"), host("http", "1.2.3.4", 11434));
+        assert_eq!(Host::parse(":1234"), host("http", "127.0.0.1", 1234));
+        assert_eq!(Host::parse("1.2.3.4:1234"), host("http", "1.2.3.4", 1234));
+        assert_eq!(
+            Host::parse("example.com"),
+            host("http", "example.com", 11434)
+        );
+        assert_eq!(
+            Host::parse("example.com:1234"),
+            host("http", "example.com", 1234)
+        );
+        assert_eq!(
+            Host::parse("http://example.com"),
+            host("http", "example.com", 80)
+        );
+        assert_eq!(
+            Host::parse("https://example.com"),
+            host("https", "example.com", 443)
+        );
+        assert_eq!(
+            Host::parse("https://example.com:8443/ollama"),
+            Host {
+                base_path: "/ollama".to_string(),
+                ..host("https", "example.com", 8443)
+            }
+        );
+        assert_eq!(
+            Host::parse("https://gw.example.com/team/ollama/").base_path,
+            "/team/ollama"
+        );
+        assert_eq!(Host::parse("http://example.com/").base_path, "");
+        assert_eq!(Host::parse("[0:0:0:0:0:0:0:1]"), host("http", "::1", 11434));
+        assert_eq!(Host::parse("[::1]:1234"), host("http", "::1", 1234));
+        assert_eq!(Host::parse("example.com:99999"), Host::default());
+        assert_eq!(
+            Host::parse("unix:///run/ollama.sock"),
+            host("unix", "/run/ollama.sock", 0)
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Error, ErrorKind, Read, Write},
+};
+
+use crate::{Body, Response};
+
+// Minimal HTTP/1.1 client side: enough to talk to the Ollama API, which answers with either
+// a Content-Length body or a chunked NDJSON stream.
+
+// Hands the connection back once a keep-alive response body has been read to the end
+pub(crate) type Release<R> = Box<dyn FnOnce(R) + Send>;
+
+pub(crate) fn write_request(
+    stream: &mut impl Write,
+    method: &str,
+    host: &str,
+    path: &str,
+    headers: &[(String, String)],
+    body: Option<&Body>,
+    keep_alive: bool,
+) -> Result<(), Error> {
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\n\
+         Host: {}\r\n\
+         Accept: application/json\r\n\
+         Connection: {}\r\n",
+        method,
+        path,
+        host,
+        if keep_alive { "keep-alive" } else { "close" }
+    );
+
+    for (name, value) in headers {
+        if [name, value].iter().any(|s| s.contains(['\r', '\n'])) || name.contains(':') {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid header: {}", name.trim()),
+            ));
+        }
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    // Opened before anything is sent so a missing file doesn't leave a half-written request
+    let file = match body {
+        Some(Body::File(path)) => Some(File::open(path)?),
+        _ => None,
+    };
+    match (body, &file) {
+        (Some(Body::Json(json)), _) => {
+            head.push_str("Content-Type: application/json\r\n");
+            head.push_str(&format!("Content-Length: {}\r\n", json.len()));
+        }
+        (Some(Body::File(_)), Some(file)) => {
+            head.push_str("Content-Type: application/octet-stream\r\n");
+            head.push_str(&format!("Content-Length: {}\r\n", file.metadata()?.len()));
+        }
+        _ => {}
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes())?;
+    match (body, file) {
+        (Some(Body::Json(json)), _) => stream.write_all(json)?,
+        (_, Some(mut file)) => {
+            std::io::copy(&mut file, stream)?;
+        }
+        _ => {}
+    }
+    stream.flush()
+}
+
+fn invalid_data(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+pub(crate) fn read_response<R: Read + Send + 'static>(
+    stream: R,
+    method: &str,
+    release: Option<Release<R>>,
+) -> Result<Response, Error> {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        // What a kept-alive connection the server has since closed looks like
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "Connection closed before a response was received",
+        ));
+    }
+
+    // e.g. "HTTP/1.1 200 OK"
+    let mut parts = line.split_whitespace();
+    let status = match (parts.next(), parts.next()) {
+        (Some(version), Some(code)) if version.starts_with("HTTP/") => code
+            .parse::<u16>()
+            .map_err(|_| invalid_data(format!("Invalid HTTP status line: {}", line.trim())))?,
+        _ => {
+            return Err(invalid_data(format!(
+                "Invalid HTTP status line: {}",
+                line.trim()
+            )));
+        }
+    };
+
+    let status_line = line.clone();
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_data("Invalid HTTP response (missing body)"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let find = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+
+    // Replies to HEAD describe the body without sending it
+    let kind = if method == "HEAD" || status == 204 || status == 304 {
+        BodyKind::Length(0)
+    } else if find("Transfer-Encoding").is_some_and(|v| v.to_ascii_lowercase().contains("chunked"))
+    {
+        BodyKind::Chunked {
+            remaining: 0,
+            done: false,
+        }
+    } else if let Some(length) = find("Content-Length") {
+        BodyKind::Length(
+            length
+                .parse()
+                .map_err(|_| invalid_data(format!("Invalid Content-Length: {}", length)))?,
+        )
+    } else {
+        BodyKind::UntilClose
+    };
+
+    let keep_alive = status_line.starts_with("HTTP/1.1")
+        && !find("Connection").is_some_and(|v| v.eq_ignore_ascii_case("close"))
+        && !matches!(kind, BodyKind::UntilClose);
+
+    Ok(Response::new(
+        status,
+        FramedBody {
+            reader: Some(reader),
+            kind,
+            keep_alive,
+            release,
+        },
+    ))
+}
+
+enum BodyKind {
+    Length(u64),
+    Chunked { remaining: u64, done: bool },
+    UntilClose,
+}
+
+struct FramedBody<R> {
+    // None once the connection went back to the pool
+    reader: Option<BufReader<R>>,
+    kind: BodyKind,
+    keep_alive: bool,
+    // Held until the body is dropped even when the connection can't be reused
+    release: Option<Release<R>>,
+}
+
+impl<R: Read> FramedBody<R> {
+    fn finished(&self) -> bool {
+        matches!(
+            self.kind,
+            BodyKind::Length(0) | BodyKind::Chunked { done: true, .. }
+        )
+    }
+
+    // Only a connection with nothing left unread can carry the next request
+    fn release(&mut self) {
+        if !self.keep_alive || !self.finished() {
+            return;
+        }
+        if let Some(release) = self.release.take()
+            && let Some(reader) = self.reader.take_if(|r| r.buffer().is_empty())
+        {
+            release(reader.into_inner());
+        }
+    }
+
+    fn read_framed(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let Some(reader) = &mut self.reader else {
+            return Ok(0);
+        };
+
+        match &mut self.kind {
+            BodyKind::Length(remaining) => {
+                if *remaining == 0 {
+                    return Ok(0);
+                }
+                let max = buf.len().min(*remaining as usize);
+                let n = reader.read(&mut buf[..max])?;
+                if n == 0 {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "Connection closed before the full body was received",
+                    ));
+                }
+                *remaining -= n as u64;
+                Ok(n)
+            }
+            BodyKind::Chunked { remaining, done } => {
+                if *done {
+                    return Ok(0);
+                }
+
+                if *remaining == 0 {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line)? == 0 {
+                        return Err(Error::new(
+                            ErrorKind::UnexpectedEof,
+                            "Connection closed inside a chunked body",
+                        ));
+                    }
+                    // Chunk extensions after ';' are ignored
+                    let size = line.trim().split(';').next().unwrap_or_default();
+                    let size = u64::from_str_radix(size.trim(), 16).map_err(|_| {
+                        invalid_data(format!("Invalid chunk size: {}", line.trim()))
+                    })?;
+
+                    if size == 0 {
+                        // Drain the optional trailers up to the final empty line
+                        loop {
+                            line.clear();
+                            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                                break;
+                            }
+                        }
+                        *done = true;
+                        return Ok(0);
+                    }
+                    *remaining = size;
+                }
+
+                let max = buf.len().min(*remaining as usize);
+                let n = reader.read(&mut buf[..max])?;
+                if n == 0 {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "Connection closed inside a chunked body",
+                    ));
+                }
+                *remaining -= n as u64;
+
+                if *remaining == 0 {
+                    // Every chunk is followed by CRLF
+                    let mut crlf = String::new();
+                    reader.read_line(&mut crlf)?;
+                }
+                Ok(n)
+            }
+            BodyKind::UntilClose => reader.read(buf),
+        }
+    }
+}
+
+impl<R: Read> Read for FramedBody<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let n = self.read_framed(buf)?;
+        if n == 0 {
+            self.release();
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_write_request() {
+        let mut out = Vec::new();
+        let headers = [("Authorization".to_string(), "Bearer abc".to_string())];
+        write_request(
+            &mut out,
+            "POST",
+            "localhost",
+            "/api/show",
+            &headers,
+            Some(&Body::Json(b"{}".to_vec())),
+            true,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Authorization: Bearer abc\r\n"));
+        assert!(out.starts_with("POST /api/show HTTP/1.1\r\nHost: localhost\r\n"));
+        assert!(out.contains("Content-Length: 2\r\n"));
+        assert!(out.contains("Connection: keep-alive\r\n"));
+        assert!(out.ends_with("\r\n\r\n{}"));
+    }
+
+    #[test]
+    fn test_content_length_body() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloEXTRA";
+        let response = read_response(Cursor::new(raw), "GET", None).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.text().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                   7\r\n{\"a\":1}\r\n\
+                   4;ext=1\r\n\n{\"b\r\n\
+                   4\r\n\":2}\r\n\
+                   0\r\n\r\n";
+        let response = read_response(Cursor::new(raw), "GET", None).unwrap();
+        let lines: Vec<String> = response.lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, vec![r#"{"a":1}"#, r#"{"b":2}"#]);
+    }
+
+    #[test]
+    fn test_connection_is_released_after_the_body() {
+        let released = |raw: &'static str| {
+            let (send, receive) = std::sync::mpsc::channel();
+            let release: Release<Cursor<&str>> = Box::new(move |_| send.send(()).unwrap());
+            let response = read_response(Cursor::new(raw), "GET", Some(release)).unwrap();
+            response.text().unwrap();
+            receive.try_recv().is_ok()
+        };
+
+        assert!(released("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}"));
+        assert!(released(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n{}\r\n0\r\n\r\n"
+        ));
+        assert!(!released(
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+        ));
+        assert!(!released("HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\n{}"));
+        assert!(!released("HTTP/1.1 200 OK\r\n\r\n{}"));
+    }
+
+    #[test]
+    fn test_body_until_close() {
+        let raw = "HTTP/1.0 404 Not Found\r\n\r\nmissing";
+        let response = read_response(Cursor::new(raw), "GET", None).unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.text().unwrap(), "missing");
+    }
+
+    #[test]
+    fn test_truncated_body_is_an_error() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort";
+        let response = read_response(Cursor::new(raw), "GET", None).unwrap();
+        assert!(response.text().is_err());
+    }
+
+    #[test]
+    fn test_head_response_has_no_body() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 4096\r\n\r\n";
+        let response = read_response(Cursor::new(raw), "HEAD", None).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.text().unwrap(), "");
+    }
+
+    #[test]
+    fn test_write_file_body() {
+        let path = std::env::temp_dir().join(format!("ollama-rs-body-{}.bin", std::process::id()));
+        std::fs::write(&path, b"GGUF\x00\x01").unwrap();
+
+        let mut out = Vec::new();
+        let body = Body::File(path.clone());
+        write_request(
+            &mut out,
+            "POST",
+            "localhost",
+            "/api/blobs/sha256:ab",
+            &[],
+            Some(&body),
+            false,
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(out.ends_with(b"Content-Length: 6\r\n\r\nGGUF\x00\x01"));
+        let head = String::from_utf8_lossy(&out);
+        assert!(head.contains("Content-Type: application/octet-stream\r\n"));
+
+        let missing = write_request(
+            &mut Vec::new(),
+            "POST",
+            "localhost",
+            "/",
+            &[],
+            Some(&body),
+            false,
+        );
+        assert_eq!(missing.unwrap_err().kind(), ErrorKind::NotFound);
+    }
+}
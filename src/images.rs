@@ -0,0 +1,261 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{OllamaError, base64};
+
+// Multimodal models take images as standard, padded base64
+pub fn encode_image(bytes: &[u8]) -> String {
+    base64::encode(bytes)
+}
+
+pub fn encode_image_file(path: impl AsRef<Path>) -> Result<String, OllamaError> {
+    let bytes = std::fs::read(path).map_err(OllamaError::Io)?;
+    Ok(encode_image(&bytes))
+}
+
+// The formats the server's vision models can decode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl ImageFormat {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::WebP => "image/webp",
+        }
+    }
+}
+
+// An image checked to be something the server can decode, with its size read from the header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: Vec<u8>,
+}
+
+impl Image {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Image, OllamaError> {
+        let bytes = std::fs::read(path).map_err(OllamaError::Io)?;
+        Image::from_bytes(bytes)
+    }
+
+    // Fails unless the bytes start like a PNG, JPEG or WebP image
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Result<Image, OllamaError> {
+        let bytes = bytes.into();
+        let (format, width, height) = probe(&bytes).ok_or_else(|| {
+            OllamaError::Io(Error::new(
+                ErrorKind::InvalidData,
+                "not a PNG, JPEG or WebP image",
+            ))
+        })?;
+        Ok(Image {
+            format,
+            width,
+            height,
+            bytes,
+        })
+    }
+
+    // Shrinks images whose longer side exceeds `max_side`, keeping the aspect ratio. The crate
+    // has no image codecs, so `resize` does the work, e.g. with the `image` crate:
+    //
+    //   image.fit(1024, |image, width, height| {
+    //       let decoded = image::load_from_memory(&image.bytes)?;
+    //       ... decoded.thumbnail(width, height).write_to(..., ImageFormat::Png) ...
+    //   })
+    //
+    // Vision models scale their input down anyway, this saves sending megabytes they discard.
+    pub fn fit(
+        self,
+        max_side: u32,
+        resize: impl FnOnce(&Image, u32, u32) -> Result<Vec<u8>, OllamaError>,
+    ) -> Result<Image, OllamaError> {
+        let longest = self.width.max(self.height);
+        if longest <= max_side || max_side == 0 {
+            return Ok(self);
+        }
+        let scale = |side: u32| ((side as u64 * max_side as u64 / longest as u64) as u32).max(1);
+        let bytes = resize(&self, scale(self.width), scale(self.height))?;
+        Image::from_bytes(bytes)
+    }
+
+    // Base64 for the `images` of a request or message
+    pub fn encode(&self) -> String {
+        encode_image(&self.bytes)
+    }
+}
+
+fn probe(bytes: &[u8]) -> Option<(ImageFormat, u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // The IHDR chunk always comes first
+        if bytes.get(12..16)? != b"IHDR" {
+            return None;
+        }
+        return Some((ImageFormat::Png, be32(bytes, 16)?, be32(bytes, 20)?));
+    }
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        let (width, height) = jpeg_size(bytes)?;
+        return Some((ImageFormat::Jpeg, width, height));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12)? == b"WEBP" {
+        let (width, height) = webp_size(bytes)?;
+        return Some((ImageFormat::WebP, width, height));
+    }
+    None
+}
+
+// Walks the segments up to the start-of-frame marker, which holds the size
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    loop {
+        if *bytes.get(at)? != 0xff {
+            return None;
+        }
+        let marker = *bytes.get(at + 1)?;
+        at += 2;
+        match marker {
+            // Padding before a marker
+            0xff => at -= 1,
+            // Markers without a segment
+            0x01 | 0xd0..=0xd7 => {}
+            // SOF0 to SOF15, except DHT, JPG and DAC which share the range
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                let height = be16(bytes, at + 3)?;
+                let width = be16(bytes, at + 5)?;
+                return Some((width, height));
+            }
+            // Image data starts without a frame header having been seen
+            0xda | 0xd9 => return None,
+            _ => at += be16(bytes, at)? as usize,
+        }
+    }
+}
+
+fn webp_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        // Lossy: a keyframe header after the chunk header and 3-byte frame tag
+        b"VP8 " => {
+            if bytes.get(23..26)? != [0x9d, 0x01, 0x2a] {
+                return None;
+            }
+            Some((le16(bytes, 26)? & 0x3fff, le16(bytes, 28)? & 0x3fff))
+        }
+        // Lossless: 14 bits each of width - 1 and height - 1 after a signature byte
+        b"VP8L" => {
+            if *bytes.get(20)? != 0x2f {
+                return None;
+            }
+            let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+            Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+        }
+        // Extended: 24 bits each of canvas width - 1 and height - 1
+        b"VP8X" => Some((le24(bytes, 24)? + 1, le24(bytes, 27)? + 1)),
+        _ => None,
+    }
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn be32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le24(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend(width.to_be_bytes());
+        bytes.extend(height.to_be_bytes());
+        bytes.extend([8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_encode_image() {
+        assert_eq!(encode_image(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode_image(&[0xff, 0xfe, 0x00]), "//4A");
+    }
+
+    #[test]
+    fn test_encode_missing_file() {
+        assert!(matches!(
+            encode_image_file("does/not/exist.png"),
+            Err(OllamaError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_image_formats() {
+        let image = Image::from_bytes(png(640, 480)).unwrap();
+        assert_eq!(
+            (image.format, image.width, image.height),
+            (ImageFormat::Png, 640, 480)
+        );
+
+        // APP0 segment, then a baseline frame header
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x11, 0x08, 0x01,
+            0xe0, 0x02, 0x80, 0x03,
+        ];
+        let image = Image::from_bytes(jpeg).unwrap();
+        assert_eq!(
+            (image.format, image.width, image.height),
+            (ImageFormat::Jpeg, 640, 480)
+        );
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend([0x7f, 0x02, 0x00, 0xdf, 0x01, 0x00]);
+        let image = Image::from_bytes(webp).unwrap();
+        assert_eq!(
+            (image.format, image.width, image.height),
+            (ImageFormat::WebP, 640, 480)
+        );
+        assert_eq!(image.format.mime_type(), "image/webp");
+
+        assert!(matches!(
+            Image::from_bytes(b"GIF89a".to_vec()),
+            Err(OllamaError::Io(e)) if e.kind() == ErrorKind::InvalidData
+        ));
+    }
+
+    #[test]
+    fn test_fit() {
+        let image = Image::from_bytes(png(4000, 3000)).unwrap();
+        let small = image
+            .clone()
+            .fit(1000, |original, width, height| {
+                assert_eq!(original.width, 4000);
+                Ok(png(width, height))
+            })
+            .unwrap();
+        assert_eq!((small.width, small.height), (1000, 750));
+
+        let unchanged = image
+            .clone()
+            .fit(5000, |_, _, _| panic!("no resize needed"))
+            .unwrap();
+        assert_eq!(unchanged, image);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{Request, Response};
+
+// Hooks around every call a client makes, for audit logging, redaction or extra headers.
+// Each method defaults to doing nothing, so implement only the ones you need.
+pub trait Interceptor: Send + Sync {
+    // Before the request goes out, once however often it's retried. Changes to the headers
+    // or body are what gets sent.
+    fn on_request(&self, _request: &mut Request) {}
+
+    // When a successful response arrives, before its body is read. Replace the response to
+    // wrap or substitute the body.
+    fn on_response(&self, _request: &Request, _response: &mut Response) {}
+
+    // Each JSON object of a streamed reply, before it's turned into a response type
+    fn on_stream_chunk(&self, _chunk: &mut Value) {}
+}
+
+impl fmt::Debug for dyn Interceptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dyn Interceptor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, ChatMessage, ChatRequest, GenerateRequest, MockOllama};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    // Swaps email addresses out of prompts and logs what it sees
+    #[derive(Default)]
+    struct Redactor {
+        log: Mutex<Vec<String>>,
+    }
+
+    impl Interceptor for Arc<Redactor> {
+        fn on_request(&self, request: &mut Request) {
+            request
+                .headers
+                .push(("X-Audit".to_string(), "on".to_string()));
+            if let Some(Body::Json(body)) = &mut request.body {
+                let text = String::from_utf8_lossy(body).replace("bob@example.com", "[email]");
+                *body = text.into_bytes();
+            }
+            self.log.lock().unwrap().push(request.path.clone());
+        }
+
+        fn on_response(&self, request: &Request, response: &mut Response) {
+            let line = format!("{} {}", response.status, request.path);
+            self.log.lock().unwrap().push(line);
+        }
+
+        fn on_stream_chunk(&self, chunk: &mut Value) {
+            if let Some(content) = chunk["message"]["content"].as_str() {
+                chunk["message"]["content"] = content.to_uppercase().into();
+            }
+        }
+    }
+
+    #[test]
+    fn test_interceptor() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "Noted.", "done": true }),
+            )
+            .stream(
+                "POST",
+                "/api/chat",
+                vec![
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": "hi" }, "done": false }),
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": " there" }, "done": true }),
+                ],
+            );
+        let redactor = Arc::new(Redactor::default());
+        let ollama = mock.client().with_interceptor(redactor.clone());
+
+        let request = GenerateRequest::new("llama3", "Mail bob@example.com");
+        assert_eq!(ollama.generate(&request).unwrap().response, "Noted.");
+        let sent = &mock.requests()[1];
+        assert!(
+            sent.headers
+                .contains(&("X-Audit".to_string(), "on".to_string()))
+        );
+        assert_eq!(mock.request_json(1)["prompt"], "Mail [email]");
+
+        let request = ChatRequest::new("llama3", vec![ChatMessage::user("Hi")]);
+        let text: String = ollama
+            .chat_stream(&request)
+            .unwrap()
+            .map(|chunk| chunk.unwrap().message.content)
+            .collect();
+        assert_eq!(text, "HI THERE");
+
+        assert_eq!(
+            *redactor.log.lock().unwrap(),
+            [
+                "/api/generate",
+                "200 /api/generate",
+                "/api/chat",
+                "200 /api/chat"
+            ]
+        );
+    }
+}
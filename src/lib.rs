@@ -1,200 +1,1169 @@
+mod agent;
+#[cfg(feature = "async")]
+mod async_client;
+mod auth;
+mod balance;
+mod base64;
+mod benchmark;
+mod blobs;
+#[cfg(feature = "wasm")]
+mod browser;
+mod builder;
+mod cache;
+mod cancel;
+mod chat;
+mod chat_events;
+mod chunk;
+mod compare;
+mod config;
+mod connection;
+mod embed;
+mod error;
+mod few_shot;
+mod generate;
+mod host;
+mod http;
+mod images;
+mod intercept;
+mod limit;
+#[cfg(feature = "llm")]
+mod llm_provider;
+mod logprobs;
+mod metrics;
+#[cfg(any(test, feature = "test-util"))]
+mod mock;
+mod model_name;
+mod models;
+#[cfg(feature = "openai")]
+pub mod openai;
+mod options;
+mod pool;
+mod provider;
+mod proxy;
+mod record;
+mod retry;
+mod saved;
+mod server;
+mod session;
+mod sha256;
+mod stats;
+mod store;
+#[cfg(feature = "async")]
+mod stream;
+mod template;
+mod timeouts;
+mod tools;
+mod trace;
+mod transport;
+#[cfg(feature = "schemars")]
+mod validate;
+mod vector;
+mod version;
+
+pub use agent::{Agent, AgentRun, AgentStep, ToolHandler};
+#[cfg(feature = "async")]
+pub use async_client::AsyncOllama;
+pub use auth::Auth;
+pub use balance::{Balance, HostPool, HostStatus};
+pub use benchmark::{BenchmarkConfig, BenchmarkReport, Throughput};
+pub use blobs::blob_digest;
+#[cfg(feature = "wasm")]
+pub use browser::BrowserTransport;
+pub use builder::OllamaBuilder;
+pub use cache::{CacheBackend, LruCache, ResponseCache};
+pub use cancel::CancellationToken;
+pub use chat::{ChatMessage, ChatRequest, ChatResponse, Role};
+pub use chat_events::ChatEvent;
+pub use chunk::{ChunkUnit, TextSplitter};
+pub use compare::Comparison;
+pub use config::OllamaConfig;
+pub use embed::{EmbedRequest, EmbedResponse};
+pub use error::OllamaError;
+pub use few_shot::FewShot;
+pub use generate::{GenerateRequest, GenerateResponse};
+pub use host::Host;
+pub use images::{Image, ImageFormat, encode_image, encode_image_file};
+pub use intercept::Interceptor;
+pub use limit::RateLimiter;
+pub use logprobs::{TokenLogprob, perplexity};
+pub use metrics::{Histogram, Metrics, MetricsSnapshot, ModelUsage};
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::{MockOllama, MockServer};
+pub use model_name::{IntoModelName, ModelName};
+pub use models::{CreateRequest, ModelDetails, ModelInfo, Progress, PullPolicy, RunningModel};
+pub use options::{KeepAlive, Options};
+pub use pool::ConnectionPool;
+pub use provider::{OllamaProvider, Provider};
+pub use proxy::Proxy;
+pub use record::Recorder;
+pub use retry::{RetryOn, RetryPolicy};
+pub use server::{ServerHandle, ServerOptions};
+pub use session::{ChatSession, ContextPolicy};
+pub use stats::GenerationStats;
+pub use store::{Document, VectorStore};
+pub use template::PromptTemplate;
+pub use timeouts::Timeouts;
+pub use tools::{Tool, ToolCall, ToolCallFunction, ToolFunction};
+pub use transport::{Body, HttpTransport, Request, Response, Transport};
+pub use vector::{cosine_similarity, dot, norm, normalize, top_k};
+pub use version::Version;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::Value;
+use trace::CallSpan;
+
+use std::{sync::Arc, time::Duration};
+
+// std's Instant panics in the browser
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub(crate) use std::time::Instant;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub(crate) use web_time::Instant;
 
-use std::{
-    io::{Error, ErrorKind, Read, Write},
-    net::TcpStream,
-    process::{Command, Stdio},
-};
+// Neither does the browser let a thread sleep or wait, std panics when asked to
+pub(crate) const CAN_WAIT: bool = !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
 
+#[derive(Debug, Clone)]
 pub struct Ollama {
-    pub version: String,
+    // "http", "https" (needs the `tls` feature) or "unix" (needs the `unix-socket` feature)
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    // See Host::base_path
+    pub base_path: String,
+    pub version: Version,
+    pub timeouts: Timeouts,
+    pub retry: Option<RetryPolicy>,
+    pub auth: Option<Auth>,
+    // Sent with every request, e.g. tracing IDs or tenant headers for a gateway
+    pub headers: Vec<(String, String)>,
+    // Picked up from HTTP_PROXY/HTTPS_PROXY on connect, never used for unix sockets
+    pub proxy: Option<Proxy>,
+    // Idle keep-alive connections, shared with every clone of this client
+    pub pool: ConnectionPool,
+    // Queues requests beyond its limits, shared with every clone of this client
+    pub limiter: Option<RateLimiter>,
+    // Counts requests, errors, latency and tokens, shared with every clone of this client
+    pub metrics: Option<Metrics>,
+    // Run around every call in the order they were added, see with_interceptor
+    pub interceptors: Vec<Arc<dyn Interceptor>>,
+    // Replaces the built-in HTTP client, scheme/host/port/timeouts/proxy are then unused
+    pub transport: Option<Arc<dyn Transport>>,
+    // The `ollama serve` process behind this client when it was set up by `start`
+    pub server: Option<Arc<ServerHandle>>,
+    // Shared by every call made through this client, see with_cancellation
+    pub cancel: Option<CancellationToken>,
+    // Used by requests that leave the model empty, see with_default_model
+    pub default_model: Option<String>,
+    // Used by generate, chat and embed requests that don't set their own
+    pub keep_alive: Option<KeepAlive>,
+    // Answers repeated generate and chat calls, shared with every clone of this client
+    pub cache: Option<ResponseCache>,
+    // Writes every call to a file, see with_recorder
+    pub recorder: Option<Recorder>,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    version: String,
 }
 
 impl Ollama {
-    // Ollama default port is 11434
-    pub fn new() -> Result<Ollama, Box<dyn std::error::Error>> {
-        let _ = Command::new("ollama")
-            .arg("serve")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn();
-
-        let version = Self::version();
-        Ok(Ollama { version })
-    }
-
-    pub fn version() -> String {
-        if let Ok(mut stream) = TcpStream::connect("127.0.0.1:11434") {
-            let request = "GET /api/version HTTP/1.1\r\n\
-                       Host: localhost\r\n\
-                       Connection: close\r\n\r\n";
-
-            if stream.write_all(request.as_bytes()).is_err() {
-                return "write error".to_string();
-            }
+    // Connects to OLLAMA_HOST, or localhost:11434 when it's not set, see builder for more
+    pub fn new() -> Result<Ollama, OllamaError> {
+        Self::builder().build()
+    }
 
-            let mut response = String::new();
-            if stream.read_to_string(&mut response).is_err() {
-                return "read error".to_string();
-            }
+    // Connects to the local daemon, starting `ollama serve` first if nothing answers and
+    // options.spawn is on
+    pub fn start(options: ServerOptions) -> Result<Ollama, OllamaError> {
+        Self::builder().server(options).build()
+    }
 
-            if let Some(start) = response.find("\r\n\r\n") {
-                let json_body = &response[start + 4..].trim();
-                if let Ok(parsed) = serde_json::from_str::<Value>(json_body) {
-                    if let Some(version) = parsed["version"].as_str() {
-                        return version.to_string();
-                    }
-                }
-            }
+    pub fn connect(host: impl Into<String>, port: u16) -> Result<Ollama, OllamaError> {
+        Self::connect_host(Host {
+            scheme: "http".to_string(),
+            host: host.into(),
+            port,
+            base_path: String::new(),
+        })
+    }
 
-            response
-                .lines()
-                .find(|l| l.contains("version"))
-                .unwrap_or("invalid response")
-                .to_string()
-        } else {
-            "not connected".to_string()
+    #[cfg(all(unix, feature = "unix-socket"))]
+    pub fn connect_unix(path: impl Into<String>) -> Result<Ollama, OllamaError> {
+        Self::connect_host(Host::unix(path))
+    }
+
+    pub fn connect_host(host: Host) -> Result<Ollama, OllamaError> {
+        let mut ollama = Self::unconnected(host);
+        ollama.version = ollama.version()?;
+        Ok(ollama)
+    }
+
+    fn unconnected(host: Host) -> Ollama {
+        let proxy = Proxy::from_env(&host);
+        Ollama {
+            scheme: host.scheme,
+            host: host.host,
+            port: host.port,
+            base_path: host.base_path,
+            version: Version::default(),
+            timeouts: Timeouts::default(),
+            retry: None,
+            auth: None,
+            headers: Vec::new(),
+            proxy,
+            pool: ConnectionPool::default(),
+            limiter: None,
+            metrics: None,
+            interceptors: Vec::new(),
+            transport: None,
+            server: None,
+            cancel: None,
+            default_model: None,
+            keep_alive: None,
+            cache: None,
+            recorder: None,
         }
     }
 
-    pub fn available_models() -> Result<Vec<String>, std::io::Error> {
-        let mut stream = TcpStream::connect("127.0.0.1:11434")?;
-        let request = "GET /api/tags HTTP/1.1\r\n\
-                   Host: localhost\r\n\
-                   Connection: close\r\n\r\n";
+    // Sends every call through `transport` instead of connecting to a host
+    pub fn connect_transport(transport: impl Transport + 'static) -> Result<Ollama, OllamaError> {
+        let mut ollama = Ollama {
+            transport: Some(Arc::new(transport)),
+            ..Self::unconnected(Host::default())
+        };
+        ollama.version = ollama.version()?;
+        Ok(ollama)
+    }
 
-        stream.write_all(request.as_bytes())?;
-        let mut response = String::new();
-        stream.read_to_string(&mut response)?;
+    // A copy of this client for one-off calls that need different timeouts
+    pub fn with_timeouts(&self, timeouts: Timeouts) -> Ollama {
+        Ollama {
+            timeouts,
+            ..self.clone()
+        }
+    }
 
-        let body_start = response
-            .find("\r\n\r\n")
-            .ok_or_else(|| Error::new(ErrorKind::Other, "Invalid HTTP response (missing body)"))?
-            + 4;
+    // A copy of this client that retries failed calls according to the policy
+    pub fn with_retry(&self, retry: RetryPolicy) -> Ollama {
+        Ollama {
+            retry: Some(retry),
+            ..self.clone()
+        }
+    }
 
-        let json_body = &response[body_start..];
-        let parsed: Value = serde_json::from_str(json_body)
-            .map_err(|e| Error::new(ErrorKind::Other, format!("JSON parse error: {}", e)))?;
+    // A copy of this client that authenticates every request
+    pub fn with_auth(&self, auth: Auth) -> Ollama {
+        Ollama {
+            auth: Some(auth),
+            ..self.clone()
+        }
+    }
 
-        let models_arr = parsed["models"]
-            .as_array()
-            .ok_or_else(|| Error::new(ErrorKind::Other, "Invalid models format in response"))?;
+    // A copy of this client with an extra header, replacing any header of the same name.
+    // Chain it for a single call to override a client-wide header.
+    pub fn with_header(&self, name: impl Into<String>, value: impl Into<String>) -> Ollama {
+        let name = name.into();
+        let mut ollama = self.clone();
+        ollama
+            .headers
+            .retain(|(n, _)| !n.eq_ignore_ascii_case(&name));
+        ollama.headers.push((name, value.into()));
+        ollama
+    }
 
-        let models = models_arr
-            .iter()
-            .filter_map(|m| {
-                m.get("name")
-                    .and_then(|n| n.as_str())
-                    .map(|s| s.to_string())
-            })
-            .collect();
+    // A copy of this client that goes through the given proxy instead of the environment's
+    pub fn with_proxy(&self, proxy: Proxy) -> Ollama {
+        Ollama {
+            proxy: Some(proxy),
+            ..self.clone()
+        }
+    }
 
-        Ok(models)
+    // A copy of this client that connects directly, ignoring HTTP_PROXY/HTTPS_PROXY
+    pub fn without_proxy(&self) -> Ollama {
+        Ollama {
+            proxy: None,
+            ..self.clone()
+        }
     }
 
-    //pub fn preload_model(&mut self, model: String) {
-    //    Command::new("ollama")
-    //        .arg("serve");
-    //}
+    // A copy of this client whose requests wait their turn under `limiter`
+    pub fn with_rate_limiter(&self, limiter: RateLimiter) -> Ollama {
+        Ollama {
+            limiter: Some(limiter),
+            ..self.clone()
+        }
+    }
 
-    pub fn prompt(&self, model: String, prompt: String) -> Result<String, std::io::Error> {
-        let mut stream = TcpStream::connect("127.0.0.1:11434")?;
+    // A copy of this client whose calls are counted in `metrics`
+    pub fn with_metrics(&self, metrics: Metrics) -> Ollama {
+        Ollama {
+            metrics: Some(metrics),
+            ..self.clone()
+        }
+    }
 
-        let body = format!(
-            r#"{{
-            "model": "{}",
-            "prompt": "{}",
-            "stream": false
-        }}"#,
-            model, prompt
-        );
+    // What the client's metrics recorder has counted so far, None without one
+    pub fn metrics(&self) -> Option<MetricsSnapshot> {
+        self.metrics.as_ref().map(Metrics::snapshot)
+    }
 
-        let request = format!(
-            "POST /api/generate HTTP/1.1\r\n\
-         Host: localhost\r\n\
-         Content-Type: application/json\r\n\
-         Content-Length: {}\r\n\
-         Connection: close\r\n\r\n\
-         {}",
-            body.len(),
-            body
-        );
+    // A copy of this client that also runs `interceptor`'s hooks, after those already added
+    pub fn with_interceptor(&self, interceptor: impl Interceptor + 'static) -> Ollama {
+        let mut ollama = self.clone();
+        ollama.interceptors.push(Arc::new(interceptor));
+        ollama
+    }
 
-        stream.write_all(request.as_bytes())?;
+    // A copy of this client that sends its calls through `transport`
+    pub fn with_transport(&self, transport: impl Transport + 'static) -> Ollama {
+        Ollama {
+            transport: Some(Arc::new(transport)),
+            ..self.clone()
+        }
+    }
 
-        let mut response = String::new();
-        stream.read_to_string(&mut response)?;
+    // A copy of this client whose calls stop when `token` is cancelled
+    pub fn with_cancellation(&self, token: CancellationToken) -> Ollama {
+        Ollama {
+            cancel: Some(token),
+            ..self.clone()
+        }
+    }
 
-        let body_start = response.find("\r\n\r\n").ok_or_else(|| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Invalid HTTP response (missing body)",
-            )
-        })? + 4;
-        let json_body = &response[body_start..];
-
-        let mut full_text = String::new();
-        for line in json_body.lines() {
-            if let Ok(value) = serde_json::from_str::<Value>(line) {
-                if let Some(chunk) = value["response"].as_str() {
-                    full_text.push_str(chunk);
+    fn cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    // Whatever went wrong after a cancel is down to the closed connection
+    fn cancelled_or(&self, error: impl Into<OllamaError>) -> OllamaError {
+        if self.cancelled() {
+            OllamaError::Cancelled
+        } else {
+            error.into()
+        }
+    }
+
+    // The built-in transport as configured on this client
+    pub fn http_transport(&self) -> HttpTransport {
+        HttpTransport {
+            scheme: self.scheme.clone(),
+            host: self.host.clone(),
+            port: self.port,
+            base_path: self.base_path.clone(),
+            timeouts: self.timeouts,
+            proxy: self.proxy.clone(),
+            pool: self.pool.clone(),
+        }
+    }
+
+    fn request_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(auth) = &self.auth
+            && !self
+                .headers
+                .iter()
+                .any(|(n, _)| n.eq_ignore_ascii_case("Authorization"))
+        {
+            headers.push(("Authorization".to_string(), auth.header_value()));
+        }
+        headers.extend(self.headers.iter().cloned());
+        headers
+    }
+
+    pub(crate) fn send(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Body>,
+    ) -> Result<Response, OllamaError> {
+        let span = CallSpan::new(method, path, body.as_ref(), self.metrics.clone());
+        self.send_in(&span, method, path, body)
+    }
+
+    // Retries only cover getting a response, a stream that breaks halfway is not replayed
+    fn send_in(
+        &self,
+        span: &CallSpan,
+        method: &str,
+        path: &str,
+        body: Option<Body>,
+    ) -> Result<Response, OllamaError> {
+        let mut request = Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: self.request_headers(),
+            body,
+            cancel: self.cancel.clone(),
+        };
+        for interceptor in &self.interceptors {
+            interceptor.on_request(&mut request);
+        }
+        span.in_scope(|| {
+            span.request(&request);
+            let mut result = match &self.retry {
+                Some(policy) => policy.run(self.cancel.as_ref(), || self.send_once(&request)),
+                None => self.send_once(&request),
+            }
+            .map_err(|error| self.cancelled_or(error));
+            span.response(&result);
+            if let Ok(response) = &mut result {
+                for interceptor in &self.interceptors {
+                    interceptor.on_response(&request, response);
                 }
             }
+            result
+        })
+    }
+
+    fn send_once(&self, request: &Request) -> Result<Response, OllamaError> {
+        if self.cancelled() {
+            return Err(OllamaError::Cancelled);
         }
+        let permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire(self.cancel.as_ref())?),
+            None => None,
+        };
 
-        if full_text.is_empty() {
-            if let Ok(parsed) = serde_json::from_str::<Value>(json_body) {
-                if let Some(text) = parsed["response"].as_str() {
-                    full_text = text.to_string();
-                }
+        let http;
+        let transport: &dyn Transport = match &self.transport {
+            Some(transport) => &**transport,
+            None => {
+                http = self.http_transport();
+                &http
             }
+        };
+        let response = match &self.recorder {
+            Some(recorder) => recorder.record(transport, request)?,
+            None => transport.send(request)?,
+        };
+
+        if !(200..300).contains(&response.status) {
+            let status = response.status;
+            let text = response.text()?;
+            // {"error": "..."} from the native API, {"error": {"message": "..."}} from /v1
+            let message = serde_json::from_str::<Value>(&text)
+                .ok()
+                .and_then(|v| {
+                    let error = &v["error"];
+                    error
+                        .as_str()
+                        .or(error["message"].as_str())
+                        .map(|s| s.to_string())
+                })
+                .unwrap_or_else(|| text.trim().to_string());
+            return Err(OllamaError::from_status(status, message));
         }
 
-        if full_text.is_empty() {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("No 'response' field in response: {}", json_body),
-            ))
-        } else {
-            Ok(full_text)
+        Ok(match permit {
+            Some(permit) => permit.hold(response),
+            None => response,
+        })
+    }
+
+    // Ollama reports some failures as {"error": "..."} even on a success status
+    fn parse_json<R: DeserializeOwned>(span: &CallSpan, text: &str) -> Result<R, OllamaError> {
+        Self::parse_value(span, serde_json::from_str(text)?)
+    }
+
+    // One line of a streamed reply, after the interceptors have seen it
+    fn parse_chunk<R: DeserializeOwned>(
+        span: &CallSpan,
+        interceptors: &[Arc<dyn Interceptor>],
+        text: &str,
+    ) -> Result<R, OllamaError> {
+        let mut value: Value = serde_json::from_str(text)?;
+        for interceptor in interceptors {
+            interceptor.on_stream_chunk(&mut value);
+        }
+        Self::parse_value(span, value)
+    }
+
+    fn parse_value<R: DeserializeOwned>(span: &CallSpan, value: Value) -> Result<R, OllamaError> {
+        span.reply(&value);
+
+        if let Some(error) = value["error"].as_str() {
+            return Err(OllamaError::Api {
+                message: error.to_string(),
+            });
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub(crate) fn get_json<R: DeserializeOwned>(&self, path: &str) -> Result<R, OllamaError> {
+        let span = CallSpan::new("GET", path, None, self.metrics.clone());
+        let text = self
+            .send_in(&span, "GET", path, None)?
+            .text()
+            .map_err(|e| self.cancelled_or(e))?;
+        Self::parse_json(&span, &text)
+    }
+
+    pub(crate) fn post_json<B: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<R, OllamaError> {
+        let body = Body::Json(serde_json::to_vec(body)?);
+        let span = CallSpan::new("POST", path, Some(&body), self.metrics.clone());
+        let text = self
+            .send_in(&span, "POST", path, Some(body))?
+            .text()
+            .map_err(|e| self.cancelled_or(e))?;
+        Self::parse_json(&span, &text)
+    }
+
+    // Streamed endpoints answer with one JSON object per line
+    pub(crate) fn post_stream<B: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<impl Iterator<Item = Result<R, OllamaError>> + Send + use<B, R>, OllamaError> {
+        let (span, lines) = self.post_lines(path, body)?;
+        let interceptors = self.interceptors.clone();
+        Ok(lines.map(move |line| {
+            line.and_then(|line| Self::parse_chunk::<R>(&span, &interceptors, &line))
+        }))
+    }
+
+    // The non-empty lines of a streamed reply as they arrive, with the span to parse them in
+    pub(crate) fn post_lines<B: Serialize>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<
+        (
+            CallSpan,
+            impl Iterator<Item = Result<String, OllamaError>> + Send + use<B>,
+        ),
+        OllamaError,
+    > {
+        let body = Body::Json(serde_json::to_vec(body)?);
+        let span = CallSpan::new("POST", path, Some(&body), self.metrics.clone());
+        let response = self.send_in(&span, "POST", path, Some(body))?;
+
+        // After a cancel the stream ends with a single Cancelled error
+        let cancel = self.cancel.clone();
+        let mut stopped = false;
+        let lines = response.lines().map_while(move |line| {
+            if stopped {
+                return None;
+            }
+            if cancel.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                stopped = true;
+                return Some(Err(OllamaError::Cancelled));
+            }
+            Some(line.map_err(OllamaError::from))
+        });
+        Ok((span, lines))
+    }
+
+    pub fn version(&self) -> Result<Version, OllamaError> {
+        let version = self.get_json::<VersionResponse>("/api/version")?.version;
+        Version::parse(&version)
+            .ok_or_else(|| OllamaError::InvalidResponse(format!("Invalid version: {}", version)))
+    }
+
+    // Polls /api/version with backoff until the server answers, returning the last error once
+    // `timeout` has passed
+    pub fn wait_until_ready(&self, timeout: Duration) -> Result<Version, OllamaError> {
+        let deadline = Instant::now() + timeout;
+        let mut delay = Duration::from_millis(50);
+
+        loop {
+            // No single probe may outlive the deadline
+            let remaining = deadline
+                .saturating_duration_since(Instant::now())
+                .max(Duration::from_millis(1));
+            let probe = Ollama {
+                timeouts: Timeouts::default()
+                    .connect(remaining)
+                    .read(remaining)
+                    .write(remaining),
+                retry: None,
+                ..self.clone()
+            };
+
+            match probe.version() {
+                Ok(version) => return Ok(version),
+                Err(error) if !CAN_WAIT || Instant::now() + delay >= deadline => {
+                    return Err(error);
+                }
+                Err(_) => {
+                    std::thread::sleep(delay);
+                    delay = (delay * 2).min(Duration::from_secs(1));
+                }
+            }
         }
     }
 }
 
 // --- Tests ---
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use std::io::{Read, Write};
 
     #[test]
     fn test_version() {
-        let ollama = Ollama::new().unwrap();
-        println!("Ollama version: {}", ollama.version);
-        assert!(!ollama.version.is_empty());
+        let ollama = MockOllama::new().version("0.5.4").client();
+        assert_eq!(ollama.version, Version::new(0, 5, 4));
+        assert_eq!(ollama.version().unwrap(), Version::new(0, 5, 4));
+    }
+
+    pub(crate) fn json_response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    pub(crate) struct Captured {
+        pub head: String,
+        pub body: String,
+    }
+
+    // Answers one connection per canned raw HTTP response, in order, and hands back the
+    // requests it received. The first response is consumed by the version check in
+    // Ollama::connect.
+    pub(crate) fn serve(
+        responses: Vec<String>,
+    ) -> (Ollama, std::thread::JoinHandle<Vec<Captured>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let mut captured = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body_start = loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break i + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&request[..body_start]).to_string();
+                let length = head
+                    .to_lowercase()
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .map(|v| v.trim().parse::<usize>().unwrap())
+                    .unwrap_or(0);
+                while request.len() < body_start + length {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                captured.push(Captured {
+                    head,
+                    body: String::from_utf8_lossy(&request[body_start..]).to_string(),
+                });
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            captured
+        });
+
+        (Ollama::connect("127.0.0.1", port).unwrap(), server)
     }
 
     #[test]
-    fn test_available_models() {
-        let models = Ollama::available_models().unwrap();
-        println!("Available models: {:?}", models);
-        assert!(!models.is_empty());
+    fn test_connect_custom_port() {
+        let (ollama, server) = serve(vec![json_response("200 OK", r#"{"version":"0.5.1"}"#)]);
+        server.join().unwrap();
+        assert_eq!(ollama.version, Version::new(0, 5, 1));
     }
 
     #[test]
-    fn test_prompt() {
-        let ollama = Ollama::new().unwrap();
-        let available_models = Ollama::available_models().unwrap();
-        if available_models.is_empty() {
-            panic!("No available models to test prompt");
+    fn test_read_timeout() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let body = r#"{"version":"0.5.1"}"#;
+            stream
+                .write_all(json_response("200 OK", body).as_bytes())
+                .unwrap();
+            drop(stream);
+
+            // Accept the next request but never answer it
+            let (stream, _) = listener.accept().unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            drop(stream);
+        });
+
+        let ollama = Ollama::connect("127.0.0.1", port).unwrap();
+        let error = ollama
+            .with_timeouts(Timeouts::default().read(std::time::Duration::from_millis(50)))
+            .version()
+            .unwrap_err();
+        assert!(matches!(error, OllamaError::Timeout), "{}", error);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_retry_on_server_error() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response("503 Service Unavailable", r#"{"error":"loading model"}"#),
+            json_response("200 OK", r#"{"version":"0.5.2"}"#),
+        ]);
+
+        let policy = RetryPolicy::default().backoff(
+            std::time::Duration::from_millis(1),
+            std::time::Duration::from_millis(1),
+        );
+        assert_eq!(
+            ollama.with_retry(policy).version().unwrap(),
+            Version::new(0, 5, 2)
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_host_header() {
+        let (mut ollama, server) = serve(vec![json_response("200 OK", r#"{"version":"0.5.1"}"#)]);
+        server.join().unwrap();
+
+        ollama.host = "example.com".to_string();
+        ollama.port = 11434;
+        assert_eq!(ollama.http_transport().host_header(), "example.com:11434");
+
+        ollama.scheme = "https".to_string();
+        ollama.port = 443;
+        assert_eq!(ollama.http_transport().host_header(), "example.com");
+
+        ollama.host = "::1".to_string();
+        assert_eq!(ollama.http_transport().host_header(), "[::1]");
+    }
+
+    #[test]
+    fn test_base_path() {
+        let version = json_response("200 OK", r#"{"version":"0.5.1"}"#);
+        let (mut ollama, server) = serve(vec![version.clone(), version]);
+        ollama.base_path = "/ollama".to_string();
+        ollama.version().unwrap();
+        let requests = server.join().unwrap();
+        assert!(
+            requests[1].head.starts_with("GET /ollama/api/version "),
+            "{}",
+            requests[1].head
+        );
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn test_https_requires_tls_feature() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let error = Ollama::connect_host(Host {
+            scheme: "https".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            base_path: String::new(),
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("`tls` feature"), "{}", error);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_handshake_against_plain_server_fails() {
+        let (plain, server) = serve(vec![json_response("200 OK", r#"{"version":"0.5.1"}"#)]);
+        server.join().unwrap();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(json_response("200 OK", "{}").as_bytes());
+        });
+
+        let tls = Ollama {
+            scheme: "https".to_string(),
+            host: "localhost".to_string(),
+            port,
+            ..plain
+        };
+        assert!(tls.version().is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_custom_headers() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+        ]);
+
+        let tenant = ollama
+            .with_auth(Auth::bearer("secret"))
+            .with_header("X-Tenant", "acme");
+        tenant.version().unwrap();
+        tenant.with_header("x-tenant", "globex").version().unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].head.contains("X-Tenant: acme\r\n"));
+        assert!(
+            requests[1]
+                .head
+                .contains("Authorization: Bearer secret\r\n")
+        );
+        assert!(requests[2].head.contains("x-tenant: globex\r\n"));
+        assert!(!requests[2].head.contains("acme"));
+    }
+
+    #[test]
+    fn test_header_injection_is_rejected() {
+        let (ollama, server) = serve(vec![json_response("200 OK", r#"{"version":"0.5.1"}"#)]);
+        server.join().unwrap();
+
+        let error = ollama
+            .with_header("X-Evil", "a\r\nHost: elsewhere")
+            .version()
+            .unwrap_err();
+        assert!(matches!(error, OllamaError::InvalidRequest(_)), "{}", error);
+    }
+
+    #[test]
+    fn test_custom_transport() {
+        use std::sync::Mutex;
+
+        // Answers from memory and keeps every request it was given
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<Request>>);
+
+        impl Transport for Arc<Recorder> {
+            fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+                self.0.lock().unwrap().push(request.clone());
+                let body = match request.path.as_str() {
+                    "/api/version" => r#"{"version":"0.5.1"}"#,
+                    _ => r#"{"error":"model 'nope' not found"}"#,
+                };
+                Ok(Response::new(200, body.as_bytes()))
+            }
         }
 
-        let model = &available_models[0];
-        println!("Testing prompt with model: {}", model);
+        let recorder = Arc::new(Recorder::default());
+        let ollama = Ollama::connect_transport(recorder.clone())
+            .unwrap()
+            .with_header("X-Tenant", "acme");
+        assert_eq!(ollama.version, Version::new(0, 5, 1));
+
+        let error = ollama.show_model("nope".to_string()).unwrap_err();
+        assert!(matches!(error, OllamaError::Api { .. }), "{}", error);
+
+        let requests = recorder.0.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].path, "/api/show");
+        assert_eq!(
+            requests[1].headers,
+            vec![("X-Tenant".to_string(), "acme".to_string())]
+        );
+        assert_eq!(
+            requests[1].body,
+            Some(Body::Json(br#"{"model":"nope:latest"}"#.to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_wait_until_ready() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // Refuses connections until it has been asked `down` times
+        struct Starting {
+            down: u32,
+            calls: AtomicU32,
+            mock: MockOllama,
+        }
+
+        impl Transport for Arc<Starting> {
+            fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+                if self.calls.fetch_add(1, Ordering::SeqCst) < self.down {
+                    let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+                    return Err(OllamaError::Connection(refused));
+                }
+                self.mock.send(request)
+            }
+        }
+
+        let starting = Arc::new(Starting {
+            down: 3,
+            calls: AtomicU32::new(0),
+            mock: MockOllama::new(),
+        });
+        let ollama = MockOllama::new().client().with_transport(starting.clone());
+        assert_eq!(
+            ollama.wait_until_ready(Duration::from_secs(5)).unwrap(),
+            Version::new(0, 5, 1)
+        );
+        assert_eq!(starting.calls.load(Ordering::SeqCst), 4);
+
+        let never = Arc::new(Starting {
+            down: u32::MAX,
+            calls: AtomicU32::new(0),
+            mock: MockOllama::new(),
+        });
+        let started = Instant::now();
+        let error = ollama
+            .with_transport(never)
+            .wait_until_ready(Duration::from_millis(200))
+            .unwrap_err();
+        assert!(matches!(error, OllamaError::Connection(_)), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_cancel_stream() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
 
-        let reply = ollama
-            .prompt(model.to_string(), "Hello, world!".to_string())
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(json_response("200 OK", r#"{"version":"0.5.1"}"#).as_bytes())
+                .unwrap();
+            drop(stream);
+
+            // One chunk, then the model "keeps thinking" until the client hangs up
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut buf).unwrap();
+            let chunk = "{\"model\":\"llama3\",\"response\":\"Once\",\"done\":false}\n";
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                chunk.len(),
+                chunk
+            )
+            .unwrap();
+            while stream.read(&mut buf).unwrap() > 0 {}
+        });
+
+        let token = CancellationToken::new();
+        let ollama = Ollama::connect("127.0.0.1", port)
+            .unwrap()
+            .with_cancellation(token.clone());
+        let mut chunks = ollama
+            .generate_stream(&GenerateRequest::new("llama3", "Tell me a story"))
+            .unwrap();
+        assert_eq!(chunks.next().unwrap().unwrap().response, "Once");
+
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            token.cancel();
+        });
+        assert!(matches!(chunks.next(), Some(Err(OllamaError::Cancelled))));
+        assert!(chunks.next().is_none());
+        canceller.join().unwrap();
+
+        // The server saw the connection close
+        server.join().unwrap();
+
+        let error = ollama.version().unwrap_err();
+        assert!(matches!(error, OllamaError::Cancelled), "{}", error);
+    }
+
+    #[test]
+    fn test_connection_reuse() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Answers every request on the first connection, then closes it
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            for _ in 0..3 {
+                let n = stream.read(&mut buf).unwrap();
+                assert!(String::from_utf8_lossy(&buf[..n]).contains("Connection: keep-alive"));
+                stream
+                    .write_all(json_response("200 OK", r#"{"version":"0.5.1"}"#).as_bytes())
+                    .unwrap();
+            }
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(json_response("200 OK", r#"{"version":"0.5.2"}"#).as_bytes())
+                .unwrap();
+        });
+
+        let ollama = Ollama::connect("127.0.0.1", port).unwrap();
+        assert_eq!(ollama.pool.idle_connections(), 1);
+        ollama.version().unwrap();
+        ollama.clone().version().unwrap();
+        // The kept-alive connection is gone by now, the call goes out on a new one
+        assert_eq!(ollama.version().unwrap(), Version::new(0, 5, 2));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_http_proxy() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+        ]);
+
+        // The test server stands in for the proxy, the remote host is never resolved
+        let proxy = Proxy::new(ollama.host.clone(), ollama.port).auth(Auth::basic("me", "pw"));
+        let remote = Ollama {
+            host: "ollama.internal".to_string(),
+            port: 11434,
+            ..ollama.with_proxy(proxy)
+        };
+        remote.version().unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(
+            requests[1]
+                .head
+                .starts_with("GET http://ollama.internal:11434/api/version HTTP/1.1\r\n")
+        );
+        assert!(requests[1].head.contains("Host: ollama.internal:11434\r\n"));
+        assert!(
+            requests[1]
+                .head
+                .contains("Proxy-Authorization: Basic bWU6cHc=\r\n")
+        );
+    }
+
+    #[cfg(all(unix, feature = "unix-socket"))]
+    #[test]
+    fn test_unix_socket() {
+        let path = std::env::temp_dir().join(format!("ollama-rs-test-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(json_response("200 OK", r#"{"version":"0.5.1"}"#).as_bytes())
+                .unwrap();
+        });
+
+        let ollama = Ollama::connect_unix(path.to_str().unwrap()).unwrap();
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(ollama.version, Version::new(0, 5, 1));
+        assert_eq!(ollama.http_transport().host_header(), "localhost");
+    }
+
+    #[test]
+    fn test_push_model_chunked_progress() {
+        let chunks = [
+            "{\"status\":\"retrieving manifest\"}\n",
+            "{\"status\":\"pushing\",\"digest\":\"sha256:abc\",",
+            "\"total\":10,\"completed\":10}\n{\"status\":\"success\"}\n",
+        ];
+        let mut response =
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: keep-alive\r\n\r\n"
+                .to_string();
+        for chunk in chunks {
+            response.push_str(&format!("{:x}\r\n{}\r\n", chunk.len(), chunk));
+        }
+        response.push_str("0\r\n\r\n");
+
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            response,
+        ]);
+
+        let mut statuses = Vec::new();
+        ollama
+            .push_model("user/model".to_string(), false, |p| {
+                statuses.push(p.status.clone())
+            })
             .unwrap();
-        println!("Ollama reply: {}", reply);
-        assert!(!reply.is_empty());
+        server.join().unwrap();
+        assert_eq!(statuses, vec!["retrieving manifest", "pushing", "success"]);
+    }
+
+    #[test]
+    fn test_http_error_status() {
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            json_response("404 Not Found", r#"{"error":"model 'nope' not found"}"#),
+        ]);
+
+        let error = ollama.show_model("nope".to_string()).unwrap_err();
+        server.join().unwrap();
+        match error {
+            OllamaError::ModelNotFound(name) => assert_eq!(name, "nope"),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_available_models() {
+        let ollama = MockOllama::new()
+            .respond(
+                "GET",
+                "/api/tags",
+                200,
+                serde_json::json!({
+                    "models": [{ "name": "llama3:latest" }, { "name": "mistral:latest" }]
+                }),
+            )
+            .client();
+        let models = ollama.available_models().unwrap();
+        assert_eq!(models, ["llama3:latest", "mistral:latest"]);
+    }
+
+    #[test]
+    fn test_prompt() {
+        let mock = MockOllama::new().respond(
+            "POST",
+            "/api/generate",
+            200,
+            serde_json::json!({ "model": "llama3", "response": "Hello!", "done": true }),
+        );
+
+        let reply = mock
+            .client()
+            .prompt_with("llama3", "Hello, world!")
+            .unwrap();
+        assert_eq!(reply, "Hello!");
+
+        let body = mock.request_json(1);
+        assert_eq!(body["model"], "llama3");
+        assert_eq!(body["prompt"], "Hello, world!");
+    }
+
+    #[test]
+    fn test_prompt_default_model() {
+        let reply = serde_json::json!({ "model": "llama3", "response": "Hi", "done": true });
+        let mock = MockOllama::new()
+            .respond("POST", "/api/generate", 200, reply.clone())
+            .respond("POST", "/api/generate", 200, reply);
+        let ollama = mock.client().with_default_model("llama3");
+
+        ollama.prompt("Hello").unwrap();
+        ollama.prompt_with("phi3", "Hello").unwrap();
+
+        let models: Vec<Value> = (1..mock.requests().len())
+            .map(|i| mock.request_json(i)["model"].clone())
+            .collect();
+        assert_eq!(models, ["llama3", "phi3"]);
     }
 }
@@ -0,0 +1,228 @@
+use std::{
+    fmt,
+    io::{Error, Read},
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use crate::{CAN_WAIT, CancellationToken, Instant, OllamaError, Response};
+
+// How often a queued request checks whether it was cancelled
+pub(crate) const CANCEL_POLL: Duration = Duration::from_millis(25);
+
+// Holds requests back so bursty callers don't overwhelm a shared server. Requests over the
+// limit wait their turn instead of failing. Clones share the limit, like clones of a client do.
+#[derive(Clone)]
+pub struct RateLimiter {
+    max_concurrent: Option<usize>,
+    max_per_second: Option<f64>,
+    shared: Arc<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    // Signalled whenever a request finishes
+    finished: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    in_flight: usize,
+    // The earliest the next request may start under max_per_second
+    next_start: Option<Instant>,
+}
+
+impl RateLimiter {
+    // No limits until one is set
+    pub fn new() -> RateLimiter {
+        RateLimiter {
+            max_concurrent: None,
+            max_per_second: None,
+            shared: Arc::default(),
+        }
+    }
+
+    // A streamed call counts as in flight until its body is read to the end or dropped
+    pub fn max_concurrent(mut self, max: usize) -> RateLimiter {
+        self.max_concurrent = Some(max.max(1));
+        self
+    }
+
+    // Spaces request starts evenly, e.g. 2.0 lets one request start every 500ms
+    pub fn max_per_second(mut self, max: f64) -> RateLimiter {
+        self.max_per_second = Some(max).filter(|max| *max > 0.0);
+        self
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.shared.state.lock().unwrap().in_flight
+    }
+
+    // Blocks until the request may go out. Where threads can't wait, in the browser, a request
+    // that would have to fails with InvalidRequest instead.
+    pub(crate) fn acquire(
+        &self,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<Permit, OllamaError> {
+        let cancelled = || cancel.is_some_and(CancellationToken::is_cancelled);
+
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(max) = self.max_concurrent {
+            while state.in_flight >= max {
+                if cancelled() {
+                    return Err(OllamaError::Cancelled);
+                }
+                if !CAN_WAIT {
+                    return Err(cannot_wait());
+                }
+                state = self
+                    .shared
+                    .finished
+                    .wait_timeout(state, CANCEL_POLL)
+                    .unwrap()
+                    .0;
+            }
+        }
+        state.in_flight += 1;
+        let permit = Permit {
+            shared: self.shared.clone(),
+        };
+
+        let now = Instant::now();
+        let start = match self.max_per_second {
+            Some(max) => {
+                let start = state.next_start.map_or(now, |next| next.max(now));
+                state.next_start = Some(start + Duration::from_secs_f64(1.0 / max));
+                start
+            }
+            None => now,
+        };
+        drop(state);
+
+        while let Some(wait) = start.checked_duration_since(Instant::now()) {
+            if wait.is_zero() {
+                break;
+            }
+            if cancelled() {
+                return Err(OllamaError::Cancelled);
+            }
+            if !CAN_WAIT {
+                return Err(cannot_wait());
+            }
+            std::thread::sleep(wait.min(CANCEL_POLL));
+        }
+        Ok(permit)
+    }
+}
+
+fn cannot_wait() -> OllamaError {
+    OllamaError::InvalidRequest("over the rate limit, and this platform can't wait".to_string())
+}
+
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter::new()
+    }
+}
+
+// Two limiters are equal when they are clones of each other
+impl PartialEq for RateLimiter {
+    fn eq(&self, other: &RateLimiter) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+            && self.max_concurrent == other.max_concurrent
+            && self.max_per_second == other.max_per_second
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("max_concurrent", &self.max_concurrent)
+            .field("max_per_second", &self.max_per_second)
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+// One request's place under the limit, given back on drop
+pub(crate) struct Permit {
+    shared: Arc<Shared>,
+}
+
+impl Permit {
+    // Keeps the place taken until the response body is done with
+    pub fn hold(self, response: Response) -> Response {
+        Response::new(
+            response.status,
+            Held {
+                body: response.into_body(),
+                _permit: self,
+            },
+        )
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().in_flight -= 1;
+        self.shared.finished.notify_one();
+    }
+}
+
+struct Held {
+    body: Box<dyn Read + Send>,
+    _permit: Permit,
+}
+
+impl Read for Held {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.body.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_concurrent() {
+        let limiter = RateLimiter::new().max_concurrent(2);
+        let first = limiter.acquire(None).unwrap();
+        let _second = limiter.acquire(None).unwrap();
+        assert_eq!(limiter.in_flight(), 2);
+
+        let queued = {
+            let limiter = limiter.clone();
+            std::thread::spawn(move || {
+                let _third = limiter.acquire(None).unwrap();
+                Instant::now()
+            })
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        let released = Instant::now();
+        drop(first);
+        assert!(queued.join().unwrap() >= released);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let _third = limiter.acquire(None).unwrap();
+        assert!(matches!(
+            limiter.acquire(Some(&token)),
+            Err(OllamaError::Cancelled)
+        ));
+        assert_eq!(limiter.in_flight(), 2);
+    }
+
+    #[test]
+    fn test_max_per_second() {
+        let limiter = RateLimiter::new().max_per_second(20.0);
+        let start = Instant::now();
+        for _ in 0..5 {
+            drop(limiter.acquire(None).unwrap());
+        }
+        // The first one goes right away, then one every 50ms
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(limiter.in_flight(), 0);
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use llm::{
+    FunctionCall, ToolCall as LlmToolCall, async_trait,
+    chat::{
+        ChatMessage as LlmMessage, ChatProvider, ChatResponse as LlmResponse, ChatRole,
+        MessageType, Tool as LlmTool, Usage,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+};
+use serde_json::Value;
+
+use crate::{
+    ChatMessage, ChatRequest, ChatResponse, EmbedRequest, GenerateRequest, OllamaError,
+    OllamaProvider, Options, Role, Tool, ToolCall, ToolCallFunction, encode_image,
+};
+
+// OllamaProvider as a backend of the `llm` crate, for pipelines built on its
+// ChatProvider, CompletionProvider and EmbeddingProvider traits. The calls run through
+// AsyncOllama, so they don't block the caller's runtime.
+
+impl OllamaProvider {
+    fn chat_request(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[LlmTool]>,
+    ) -> Result<ChatRequest, LLMError> {
+        let mut converted = Vec::new();
+        for message in messages {
+            converted.extend(from_llm_message(message)?);
+        }
+        let mut request = ChatRequest::new(self.model.as_str(), converted);
+        if let Some(tools) = tools {
+            request.tools = Some(
+                tools
+                    .iter()
+                    .map(|tool| {
+                        Tool::function(
+                            tool.function.name.as_str(),
+                            tool.function.description.as_str(),
+                            tool.function.parameters.clone(),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl ChatProvider for OllamaProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[LlmTool]>,
+    ) -> Result<Box<dyn LlmResponse>, LLMError> {
+        let request = self.chat_request(messages, tools)?;
+        let response = self
+            .ollama
+            .clone()
+            .into_async()
+            .chat(&request)
+            .await
+            .map_err(llm_error)?;
+        Ok(Box::new(Reply(response)))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[LlmMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        let request = self.chat_request(messages, None)?;
+        let chunks = self.ollama.chat_stream_async(&request);
+        Ok(Box::pin(Texts(chunks)))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for OllamaProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let options = Options {
+            num_predict: request.max_tokens.and_then(|n| i32::try_from(n).ok()),
+            temperature: request.temperature,
+            ..Options::default()
+        };
+        let request =
+            GenerateRequest::new(self.model.as_str(), request.prompt.as_str()).options(options);
+        let response = self
+            .ollama
+            .clone()
+            .into_async()
+            .generate(&request)
+            .await
+            .map_err(llm_error)?;
+        Ok(CompletionResponse {
+            text: response.response,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        let model = self.embedding_model.as_ref().unwrap_or(&self.model);
+        let response = self
+            .ollama
+            .clone()
+            .into_async()
+            .embed(&EmbedRequest::new(model.as_str(), input))
+            .await
+            .map_err(llm_error)?;
+        Ok(response.embeddings)
+    }
+}
+
+// A reply as the `llm` crate reads it
+#[derive(Debug)]
+struct Reply(ChatResponse);
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.message.content)
+    }
+}
+
+impl LlmResponse for Reply {
+    fn text(&self) -> Option<String> {
+        Some(self.0.message.content.clone())
+    }
+
+    fn tool_calls(&self) -> Option<Vec<LlmToolCall>> {
+        let calls = self.0.message.tool_calls.as_ref()?;
+        Some(calls.iter().enumerate().map(to_llm_call).collect())
+    }
+
+    fn thinking(&self) -> Option<String> {
+        self.0.message.thinking.clone()
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        let stats = &self.0.stats;
+        let (prompt, completion) = (stats.prompt_eval_count as u32, stats.eval_count as u32);
+        Some(Usage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+            completion_tokens_details: None,
+            prompt_tokens_details: None,
+        })
+    }
+}
+
+// The text of each streamed chunk
+struct Texts<S>(S);
+
+impl<S: Stream<Item = Result<ChatResponse, OllamaError>> + Unpin> Stream for Texts<S> {
+    type Item = Result<String, LLMError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0)
+            .poll_next(cx)
+            .map(|chunk| chunk.map(|chunk| chunk.map(|c| c.message.content).map_err(llm_error)))
+    }
+}
+
+// One `llm` message can carry several tool results, each becomes a message of its own
+fn from_llm_message(message: &LlmMessage) -> Result<Vec<ChatMessage>, LLMError> {
+    let role = match message.role {
+        ChatRole::User => Role::User,
+        ChatRole::Assistant => Role::Assistant,
+    };
+    let text = ChatMessage::new(role, message.content.as_str());
+    let converted = match &message.message_type {
+        MessageType::Text => text,
+        MessageType::Image((_, bytes)) => text.images(vec![encode_image(bytes)]),
+        MessageType::ToolUse(calls) => ChatMessage {
+            tool_calls: Some(calls.iter().map(from_llm_call).collect()),
+            ..text
+        },
+        MessageType::ToolResult(results) => {
+            // The result travels in the arguments, as the other `llm` backends expect it
+            return Ok(results
+                .iter()
+                .map(|result| {
+                    ChatMessage::tool_result(&from_llm_call(result), &result.function.arguments)
+                })
+                .collect());
+        }
+        MessageType::ImageURL(_) => return Err(unsupported("image URLs")),
+        MessageType::Pdf(_) => return Err(unsupported("PDFs")),
+        MessageType::Audio(_) => return Err(unsupported("audio")),
+    };
+    Ok(vec![converted])
+}
+
+fn unsupported(what: &str) -> LLMError {
+    LLMError::InvalidRequest(format!("ollama can't take {} in messages", what))
+}
+
+fn from_llm_call(call: &LlmToolCall) -> ToolCall {
+    ToolCall {
+        id: Some(call.id.clone()).filter(|id| !id.is_empty()),
+        function: ToolCallFunction {
+            name: call.function.name.clone(),
+            arguments: serde_json::from_str(&call.function.arguments)
+                .unwrap_or_else(|_| Value::String(call.function.arguments.clone())),
+        },
+    }
+}
+
+// Ollama leaves out the id of most calls, the `llm` crate needs one to match up the results
+fn to_llm_call((index, call): (usize, &ToolCall)) -> LlmToolCall {
+    LlmToolCall {
+        id: call.id.clone().unwrap_or_else(|| format!("call_{}", index)),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: call.function.name.clone(),
+            arguments: call.function.arguments.to_string(),
+        },
+    }
+}
+
+fn llm_error(error: OllamaError) -> LLMError {
+    let message = error.to_string();
+    match error {
+        OllamaError::Connection(_) | OllamaError::Timeout => LLMError::HttpError(message),
+        OllamaError::Http {
+            status: 401 | 403, ..
+        } => LLMError::AuthError(message),
+        OllamaError::BadRequest(_)
+        | OllamaError::InvalidRequest(_)
+        | OllamaError::InvalidModelName(_)
+        | OllamaError::ModelNotFound(_) => LLMError::InvalidRequest(message),
+        OllamaError::Json(_) => LLMError::JsonError(message),
+        _ => LLMError::ProviderError(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MockOllama,
+        stream::tests::{block_on, collect},
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_llm_chat() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({
+                    "model": "qwen3",
+                    "message": { "role": "assistant", "content": "", "tool_calls": [
+                        { "function": { "name": "get_weather", "arguments": { "city": "Paris" } } }
+                    ] },
+                    "done": true, "prompt_eval_count": 12, "eval_count": 5
+                }),
+            )
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({ "model": "qwen3", "message": { "role": "assistant", "content": "Sunny." }, "done": true }),
+            );
+        let provider = OllamaProvider::new(mock.client(), "qwen3");
+        let tool = LlmTool {
+            tool_type: "function".to_string(),
+            function: llm::chat::FunctionTool {
+                name: "get_weather".to_string(),
+                description: "Get the weather for a city".to_string(),
+                parameters: json!({ "type": "object" }),
+            },
+            cache_control: None,
+        };
+
+        let mut messages = vec![LlmMessage::user().content("Weather in Paris?").build()];
+        let reply = block_on(provider.chat_with_tools(&messages, Some(&[tool]))).unwrap();
+        let calls = reply.tool_calls().unwrap();
+        assert_eq!(calls[0].id, "call_0");
+        assert_eq!(calls[0].function.arguments, r#"{"city":"Paris"}"#);
+        assert_eq!(reply.usage().unwrap().total_tokens, 17);
+
+        let mut result = calls[0].clone();
+        result.function.arguments = "22 degrees".to_string();
+        messages.push(LlmMessage::assistant().tool_use(calls).build());
+        messages.push(LlmMessage::user().tool_result(vec![result]).build());
+        let reply = block_on(provider.chat(&messages)).unwrap();
+        assert_eq!(reply.text().as_deref(), Some("Sunny."));
+
+        assert_eq!(
+            mock.request_json(1)["tools"][0]["function"]["name"],
+            "get_weather"
+        );
+        let body = mock.request_json(2);
+        let roles: Vec<&str> = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["user", "assistant", "tool"]);
+        assert_eq!(
+            body["messages"][1]["tool_calls"][0]["function"]["arguments"]["city"],
+            "Paris"
+        );
+        assert_eq!(body["messages"][2]["content"], "22 degrees");
+        assert_eq!(body["messages"][2]["tool_name"], "get_weather");
+    }
+
+    #[test]
+    fn test_llm_complete_embed_and_stream() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "Paris", "done": true }),
+            )
+            .respond(
+                "POST",
+                "/api/embed",
+                200,
+                json!({ "model": "nomic-embed-text", "embeddings": [[0.5, 0.25]] }),
+            )
+            .stream(
+                "POST",
+                "/api/chat",
+                vec![
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": "Hel" }, "done": false }),
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": "lo" }, "done": true }),
+                ],
+            );
+        let provider =
+            OllamaProvider::new(mock.client(), "llama3").embedding_model("nomic-embed-text");
+
+        let request = CompletionRequest::builder("Capital of France?")
+            .max_tokens(8)
+            .temperature(0.0)
+            .build();
+        let completion = block_on(provider.complete(&request)).unwrap();
+        assert_eq!(completion.text, "Paris");
+        let options = &mock.request_json(1)["options"];
+        assert_eq!(
+            (
+                options["num_predict"].clone(),
+                options["temperature"].clone()
+            ),
+            (json!(8), json!(0.0))
+        );
+
+        let embeddings = block_on(EmbeddingProvider::embed(
+            &provider,
+            vec!["Hello".to_string()],
+        ));
+        assert_eq!(embeddings.unwrap(), [[0.5, 0.25]]);
+        assert_eq!(mock.request_json(2)["model"], "nomic-embed-text");
+
+        let messages = [LlmMessage::user().content("Hi").build()];
+        let stream = block_on(provider.chat_stream(&messages)).unwrap();
+        let text: Vec<String> = collect(stream).into_iter().map(Result::unwrap).collect();
+        assert_eq!(text, ["Hel", "lo"]);
+
+        let error = llm_error(OllamaError::ModelNotFound("nope".to_string()));
+        assert!(matches!(error, LLMError::InvalidRequest(_)));
+    }
+}
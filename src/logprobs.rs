@@ -0,0 +1,58 @@
+use serde::Deserialize;
+
+// One generated token with its log probability, requested with `logprobs` on generate and
+// chat requests (Ollama 0.12.11 and later, older servers leave it out)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TokenLogprob {
+    pub token: String,
+    // Natural log of the probability the model gave this token
+    pub logprob: f64,
+    // The token's raw UTF-8 bytes, a token can hold part of a multi-byte character
+    #[serde(default)]
+    pub bytes: Option<Vec<u8>>,
+    // The most likely alternatives at this position, when `top_logprobs` was asked for
+    #[serde(default)]
+    pub top_logprobs: Vec<TokenLogprob>,
+}
+
+impl TokenLogprob {
+    pub fn probability(&self) -> f64 {
+        self.logprob.exp()
+    }
+}
+
+// exp of the mean negative log probability, lower means the model found the text more likely
+pub fn perplexity(logprobs: &[TokenLogprob]) -> Option<f64> {
+    if logprobs.is_empty() {
+        return None;
+    }
+    let total: f64 = logprobs.iter().map(|l| l.logprob).sum();
+    Some((-total / logprobs.len() as f64).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logprobs() {
+        let logprobs: Vec<TokenLogprob> = serde_json::from_str(
+            r#"[
+                {"token": "Blue", "logprob": -0.1, "bytes": [66, 108, 117, 101],
+                 "top_logprobs": [
+                    {"token": "Blue", "logprob": -0.1, "bytes": [66, 108, 117, 101]},
+                    {"token": "Gray", "logprob": -2.5}
+                 ]},
+                {"token": ".", "logprob": -0.3}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(logprobs[0].top_logprobs[1].token, "Gray");
+        assert_eq!(logprobs[0].bytes.as_deref(), Some(&b"Blue"[..]));
+        assert!(logprobs[1].top_logprobs.is_empty());
+        assert!((logprobs[1].probability() - 0.7408).abs() < 0.0001);
+
+        assert!((perplexity(&logprobs).unwrap() - 0.2f64.exp()).abs() < 1e-9);
+        assert_eq!(perplexity(&[]), None);
+    }
+}
@@ -0,0 +1,188 @@
+// A small command line client built on the library, enabled with the `cli` feature:
+//
+//   ollama-rs list
+//   ollama-rs pull <model>
+//   ollama-rs run <model> [prompt]
+//   ollama-rs embed <model> <text>...
+//
+// Connects to OLLAMA_HOST, or localhost:11434 when it's not set.
+
+use std::{
+    io::{self, BufRead, Write},
+    process::ExitCode,
+};
+
+use ollama_rs::{ChatMessage, ChatRequest, EmbedRequest, Host, Ollama, OllamaError};
+
+const USAGE: &str = "usage:
+  ollama-rs list                     list local models
+  ollama-rs pull <model>             download a model
+  ollama-rs run <model> [prompt]     answer the prompt, or chat when there is none
+  ollama-rs embed <model> <text>...  print one embedding per text as a JSON array";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["list"] => connect().and_then(|ollama| list(&ollama)),
+        ["pull", model] => connect().and_then(|ollama| pull(&ollama, model)),
+        ["run", model] => connect().and_then(|ollama| chat(&ollama, model)),
+        ["run", model, prompt @ ..] => {
+            connect().and_then(|ollama| run(&ollama, model, &prompt.join(" ")))
+        }
+        ["embed", model, texts @ ..] if !texts.is_empty() => {
+            connect().and_then(|ollama| embed(&ollama, model, texts))
+        }
+        ["help" | "-h" | "--help"] => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn connect() -> Result<Ollama, OllamaError> {
+    Ollama::connect_host(Host::from_env().unwrap_or_default())
+}
+
+fn list(ollama: &Ollama) -> Result<(), OllamaError> {
+    let models = ollama.list_models()?;
+    let width = models
+        .iter()
+        .map(|m| m.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:width$}  {:>8}  {:>10}  MODIFIED",
+        "NAME", "SIZE", "PARAMS"
+    );
+    for model in models {
+        println!(
+            "{:width$}  {:>8}  {:>10}  {}",
+            model.name,
+            format_size(model.size),
+            model.parameter_size.unwrap_or_default(),
+            model.modified_at
+        );
+    }
+    Ok(())
+}
+
+fn pull(ollama: &Ollama, model: &str) -> Result<(), OllamaError> {
+    let mut last = String::new();
+    ollama.pull_model(model.to_string(), false, |progress| {
+        let line = match (progress.completed, progress.total) {
+            (Some(completed), Some(total)) if total > 0 => format!(
+                "{} {}% of {}",
+                progress.status,
+                completed * 100 / total,
+                format_size(total)
+            ),
+            _ => progress.status.clone(),
+        };
+        // Layers report progress many times a second, only redraw when the line changes
+        if line != last {
+            print!("\r\x1b[K{}", line);
+            let _ = io::stdout().flush();
+            last = line;
+        }
+    })?;
+    println!();
+    Ok(())
+}
+
+fn run(ollama: &Ollama, model: &str, prompt: &str) -> Result<(), OllamaError> {
+    let mut messages = vec![ChatMessage::user(prompt)];
+    stream_reply(ollama, model, &mut messages)
+}
+
+// Reads a message per line until end of input or /bye, keeping the conversation going
+fn chat(ollama: &Ollama, model: &str) -> Result<(), OllamaError> {
+    eprintln!(
+        "Chatting with {}. /clear forgets the conversation, /bye quits.",
+        model
+    );
+    let mut messages = Vec::new();
+    let mut stdin = io::stdin().lock();
+    loop {
+        print!(">>> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        if stdin.read_line(&mut line).map_err(OllamaError::Io)? == 0 {
+            println!();
+            return Ok(());
+        }
+        match line.trim() {
+            "" => continue,
+            "/bye" => return Ok(()),
+            "/clear" => messages.clear(),
+            input => {
+                messages.push(ChatMessage::user(input));
+                if let Err(error) = stream_reply(ollama, model, &mut messages) {
+                    // A failed turn is forgotten so the conversation can go on
+                    messages.pop();
+                    eprintln!("error: {}", error);
+                }
+            }
+        }
+    }
+}
+
+// Prints the reply as it arrives and adds it to `messages`
+fn stream_reply(
+    ollama: &Ollama,
+    model: &str,
+    messages: &mut Vec<ChatMessage>,
+) -> Result<(), OllamaError> {
+    let request = ChatRequest::new(model, messages.clone());
+    let mut reply = String::new();
+    let mut stdout = io::stdout();
+    for chunk in ollama.chat_stream(&request)? {
+        let content = chunk?.message.content;
+        let _ = stdout.write_all(content.as_bytes());
+        let _ = stdout.flush();
+        reply.push_str(&content);
+    }
+    println!();
+    messages.push(ChatMessage::assistant(reply));
+    Ok(())
+}
+
+fn embed(ollama: &Ollama, model: &str, texts: &[&str]) -> Result<(), OllamaError> {
+    let response = ollama.embed(&EmbedRequest::new(model, texts.iter().copied()))?;
+    for embedding in response.embeddings {
+        println!("{}", serde_json::to_string(&embedding)?);
+    }
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1000.0 {
+            break;
+        }
+        size /= 1000.0;
+        unit = next;
+    }
+    if unit == "B" {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, unit)
+    }
+}
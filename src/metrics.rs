@@ -0,0 +1,262 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+// Upper bounds of the latency histogram buckets, in seconds, as Prometheus buckets
+const BUCKETS: [f64; 11] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+// Counts the calls made through a client. Clones share the counts, like clones of a client do,
+// so one recorder can cover every client in a service.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    snapshot: Arc<Mutex<MetricsSnapshot>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    pub requests: u64,
+    // Calls that got no successful response, cancelled ones included
+    pub errors: u64,
+    // Time until the response status arrived, streamed bodies not included
+    pub latency: Histogram,
+    // Token usage by the model named in the replies
+    pub models: BTreeMap<String, ModelUsage>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModelUsage {
+    // Replies that reported token counts, one per finished generation or embedding
+    pub replies: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    // Observations per bucket of BUCKETS, the last entry counts those over the largest bound
+    pub counts: [u64; BUCKETS.len() + 1],
+    pub sum: Duration,
+}
+
+impl Default for Histogram {
+    fn default() -> Histogram {
+        Histogram {
+            counts: [0; BUCKETS.len() + 1],
+            sum: Duration::ZERO,
+        }
+    }
+}
+
+impl Histogram {
+    // Upper bounds of `counts` in seconds
+    pub const BOUNDS: [f64; BUCKETS.len()] = BUCKETS;
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => Some(self.sum / count as u32),
+        }
+    }
+
+    // The bucket bound below which at least `quantile` (0.0 to 1.0) of the observations fall,
+    // None when empty or when it lies past the largest bound
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let target = (quantile.clamp(0.0, 1.0) * self.count() as f64)
+            .ceil()
+            .max(1.0) as u64;
+        let mut seen = 0;
+        for (count, bound) in self.counts.iter().zip(BUCKETS) {
+            seen += count;
+            if seen >= target {
+                return Some(Duration::from_secs_f64(bound));
+            }
+        }
+        None
+    }
+
+    fn observe(&mut self, duration: Duration) {
+        let bucket = BUCKETS
+            .iter()
+            .position(|&bound| duration.as_secs_f64() <= bound)
+            .unwrap_or(BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += duration;
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        *self.snapshot.lock().unwrap() = MetricsSnapshot::default();
+    }
+
+    pub(crate) fn request(&self, latency: Duration, failed: bool) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.requests += 1;
+        if failed {
+            snapshot.errors += 1;
+        }
+        snapshot.latency.observe(latency);
+    }
+
+    pub(crate) fn tokens(&self, model: &str, prompt_tokens: u64, completion_tokens: u64) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        let usage = snapshot.models.entry(model.to_string()).or_default();
+        usage.replies += 1;
+        usage.prompt_tokens += prompt_tokens;
+        usage.completion_tokens += completion_tokens;
+    }
+}
+
+// Two recorders are equal when they are clones of each other
+impl PartialEq for Metrics {
+    fn eq(&self, other: &Metrics) -> bool {
+        Arc::ptr_eq(&self.snapshot, &other.snapshot)
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let snapshot = self.snapshot();
+        f.debug_struct("Metrics")
+            .field("requests", &snapshot.requests)
+            .field("errors", &snapshot.errors)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MetricsSnapshot {
+    // The Prometheus text exposition format, to serve from a /metrics endpoint
+    pub fn prometheus(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE ollama_requests_total counter");
+        let _ = writeln!(out, "ollama_requests_total {}", self.requests);
+        let _ = writeln!(out, "# TYPE ollama_errors_total counter");
+        let _ = writeln!(out, "ollama_errors_total {}", self.errors);
+
+        let _ = writeln!(out, "# TYPE ollama_request_duration_seconds histogram");
+        let mut cumulative = 0;
+        for (count, bound) in self.latency.counts.iter().zip(BUCKETS) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "ollama_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "ollama_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            self.latency.count()
+        );
+        let _ = writeln!(
+            out,
+            "ollama_request_duration_seconds_sum {}",
+            self.latency.sum.as_secs_f64()
+        );
+        let _ = writeln!(
+            out,
+            "ollama_request_duration_seconds_count {}",
+            self.latency.count()
+        );
+
+        let _ = writeln!(out, "# TYPE ollama_tokens_total counter");
+        for (model, usage) in &self.models {
+            // Label values escape backslashes and quotes
+            let model = model.replace('\\', "\\\\").replace('"', "\\\"");
+            let _ = writeln!(
+                out,
+                "ollama_tokens_total{{model=\"{}\",kind=\"prompt\"}} {}",
+                model, usage.prompt_tokens
+            );
+            let _ = writeln!(
+                out,
+                "ollama_tokens_total{{model=\"{}\",kind=\"completion\"}} {}",
+                model, usage.completion_tokens
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GenerateRequest, MockOllama};
+    use serde_json::json;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.mean(), None);
+        for ms in [20, 80, 300, 700, 400_000] {
+            histogram.observe(Duration::from_millis(ms));
+        }
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.counts[0], 1);
+        assert_eq!(histogram.counts[BUCKETS.len()], 1);
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_millis(500)));
+        assert_eq!(histogram.quantile(0.8), Some(Duration::from_millis(1000)));
+        assert_eq!(histogram.quantile(1.0), None);
+    }
+
+    #[test]
+    fn test_client_metrics() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({
+                    "model": "llama3:8b",
+                    "response": "Hi",
+                    "done": true,
+                    "prompt_eval_count": 5,
+                    "eval_count": 2
+                }),
+            )
+            .error("POST", "/api/generate", 404, "model not found");
+        let metrics = Metrics::new();
+        let ollama = mock.client().with_metrics(metrics.clone());
+        assert_eq!(ollama.metrics(), Some(MetricsSnapshot::default()));
+
+        let request = GenerateRequest::new("llama3:8b", "Hello");
+        ollama.generate(&request).unwrap();
+        assert!(ollama.generate(&request).is_err());
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.requests, 2);
+        assert_eq!(snapshot.errors, 1);
+        assert_eq!(snapshot.latency.count(), 2);
+        assert_eq!(
+            snapshot.models["llama3:8b"],
+            ModelUsage {
+                replies: 1,
+                prompt_tokens: 5,
+                completion_tokens: 2
+            }
+        );
+
+        let text = snapshot.prometheus();
+        assert!(text.contains("ollama_requests_total 2\n"));
+        assert!(text.contains("ollama_request_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("ollama_tokens_total{model=\"llama3:8b\",kind=\"completion\"} 2\n"));
+
+        metrics.reset();
+        assert_eq!(ollama.metrics(), Some(MetricsSnapshot::default()));
+    }
+}
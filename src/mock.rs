@@ -0,0 +1,411 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Error, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use serde_json::{Value, json};
+
+use crate::{Body, Ollama, OllamaError, Request, Response, Transport, record::Entry};
+
+// A stand-in for the Ollama server with canned responses, for tests that should not need a
+// running daemon or a pulled model. Use it as a transport with `client()` or over a real
+// local port with `listen()`.
+#[derive(Debug, Clone)]
+pub struct MockOllama {
+    routes: Arc<Mutex<Vec<Route>>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    method: String,
+    path: String,
+    status: u16,
+    // One entry per NDJSON line for streams, a single entry otherwise
+    chunks: Vec<String>,
+}
+
+impl Default for MockOllama {
+    fn default() -> MockOllama {
+        MockOllama::new()
+    }
+}
+
+impl MockOllama {
+    // Answers /api/version out of the box so connecting works
+    pub fn new() -> MockOllama {
+        let mock = MockOllama {
+            routes: Arc::new(Mutex::new(Vec::new())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        mock.respond("GET", "/api/version", 200, json!({ "version": "0.5.1" }))
+    }
+
+    // Queues a response for method + path. Responses for the same route are served in order,
+    // the last one keeps being served once the others are used up.
+    pub fn respond(self, method: &str, path: &str, status: u16, body: Value) -> MockOllama {
+        self.route(method, path, status, vec![body.to_string()])
+    }
+
+    // A successful streamed reply, one JSON object per line
+    pub fn stream(self, method: &str, path: &str, lines: Vec<Value>) -> MockOllama {
+        let chunks = lines.iter().map(|line| format!("{}\n", line)).collect();
+        self.route(method, path, 200, chunks)
+    }
+
+    // An error status with Ollama's {"error": "..."} body
+    pub fn error(self, method: &str, path: &str, status: u16, message: &str) -> MockOllama {
+        self.respond(method, path, status, json!({ "error": message }))
+    }
+
+    pub fn version(self, version: &str) -> MockOllama {
+        self.routes
+            .lock()
+            .unwrap()
+            .retain(|r| r.path != "/api/version");
+        self.respond("GET", "/api/version", 200, json!({ "version": version }))
+    }
+
+    // Serves the responses of a recording made with Recorder, in the order they were recorded.
+    // Calls that failed without a response are left out.
+    pub fn replay(path: impl AsRef<Path>) -> Result<MockOllama, OllamaError> {
+        let text = std::fs::read_to_string(path).map_err(OllamaError::Io)?;
+        let mut order = Vec::new();
+        let mut exchanges: HashMap<u64, (String, String, Option<u16>, Vec<String>)> =
+            HashMap::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line)? {
+                Entry::Request {
+                    id, method, path, ..
+                } => {
+                    if exchanges.contains_key(&id) {
+                        return Err(OllamaError::InvalidResponse(format!(
+                            "the recording has two requests with id {}",
+                            id
+                        )));
+                    }
+                    order.push(id);
+                    exchanges.insert(id, (method, path, None, Vec::new()));
+                }
+                Entry::Response { id, status, .. } => {
+                    if let Some(exchange) = exchanges.get_mut(&id) {
+                        exchange.2 = Some(status);
+                    }
+                }
+                Entry::Chunk { id, data, .. } => {
+                    if let Some(exchange) = exchanges.get_mut(&id) {
+                        exchange.3.push(data);
+                    }
+                }
+                Entry::Error { .. } => {}
+            }
+        }
+
+        let mut mock = MockOllama::new();
+        let recorded_version = order.iter().any(|id| exchanges[id].1 == "/api/version");
+        if recorded_version {
+            mock.routes.lock().unwrap().clear();
+        }
+        for id in order {
+            let (method, path, status, chunks) = exchanges.remove(&id).unwrap();
+            if let Some(status) = status {
+                mock = mock.route(&method, &path, status, chunks);
+            }
+        }
+        Ok(mock)
+    }
+
+    fn route(self, method: &str, path: &str, status: u16, chunks: Vec<String>) -> MockOllama {
+        self.routes.lock().unwrap().push(Route {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            chunks,
+        });
+        self
+    }
+
+    // Every request received so far, including the version check made when connecting
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // The JSON body of request number `index` in requests(), panics when it has none
+    pub fn request_json(&self, index: usize) -> Value {
+        match &self.requests.lock().unwrap()[index].body {
+            Some(Body::Json(body)) => serde_json::from_slice(body).expect("a JSON request body"),
+            other => panic!("request {} has no JSON body: {:?}", index, other),
+        }
+    }
+
+    // A client that talks to this mock without any networking
+    pub fn client(&self) -> Ollama {
+        Ollama::connect_transport(self.clone()).expect("the mock always answers /api/version")
+    }
+
+    // Serves the mock over HTTP on a free local port, until the returned server is dropped
+    pub fn listen(&self) -> Result<MockServer, Error> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let mock = self.clone();
+        let stop = stopped.clone();
+        let thread = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    // A client that hangs up early is its own problem
+                    let _ = mock.answer(stream);
+                }
+            }
+        });
+
+        Ok(MockServer {
+            port,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    fn next_response(&self, request: &Request) -> Route {
+        let mut routes = self.routes.lock().unwrap();
+        let matching: Vec<usize> = routes
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.method == request.method && r.path == request.path)
+            .map(|(i, _)| i)
+            .collect();
+
+        match matching.as_slice() {
+            [] => Route {
+                method: request.method.clone(),
+                path: request.path.clone(),
+                status: 404,
+                chunks: vec![
+                    json!({
+                        "error": format!("no mock response for {} {}", request.method, request.path)
+                    })
+                    .to_string(),
+                ],
+            },
+            [last] => routes[*last].clone(),
+            [first, ..] => routes.remove(*first),
+        }
+    }
+
+    fn answer(&self, stream: TcpStream) -> Result<(), Error> {
+        let mut reader = BufReader::new(stream);
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+
+        // Framing headers are left out, the same as a Request handed to a transport
+        let mut headers = Vec::new();
+        let mut length = 0;
+        let mut json = false;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.trim_end().split_once(':') {
+                let (name, value) = (name.trim(), value.trim());
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => length = value.parse().unwrap_or(0),
+                    "content-type" => json = value.starts_with("application/json"),
+                    "host" | "accept" | "connection" => {}
+                    _ => headers.push((name.to_string(), value.to_string())),
+                }
+            }
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+
+        let request = Request {
+            method,
+            path,
+            headers,
+            // Uploaded files can't be recorded as the path they came from, they are dropped
+            body: (length > 0 && json).then_some(Body::Json(body)),
+            cancel: None,
+        };
+        self.requests.lock().unwrap().push(request.clone());
+        let route = self.next_response(&request);
+
+        // Streams go out chunked so clients see the lines arrive one by one
+        let mut stream = reader.into_inner();
+        if route.chunks.len() > 1 {
+            write!(
+                stream,
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/x-ndjson\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                route.status
+            )?;
+            for chunk in &route.chunks {
+                write!(stream, "{:x}\r\n{}\r\n", chunk.len(), chunk)?;
+                stream.flush()?;
+            }
+            write!(stream, "0\r\n\r\n")?;
+        } else {
+            let body = if request.method == "HEAD" {
+                String::new()
+            } else {
+                route.chunks.concat()
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                route.status,
+                route.chunks.concat().len(),
+                body
+            )?;
+        }
+        stream.flush()
+    }
+}
+
+impl Transport for MockOllama {
+    fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+        self.requests.lock().unwrap().push(request.clone());
+        let route = self.next_response(request);
+        Ok(Response::new(
+            route.status,
+            std::io::Cursor::new(route.chunks.concat()),
+        ))
+    }
+}
+
+// A MockOllama listening on a local port, stops when dropped
+#[derive(Debug)]
+pub struct MockServer {
+    pub port: u16,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn client(&self) -> Ollama {
+        Ollama::connect("127.0.0.1", self.port).expect("the mock always answers /api/version")
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes up the blocking accept so the thread sees the flag
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GenerateRequest, Progress, Version};
+
+    #[test]
+    fn test_transport_stream() {
+        let mock = MockOllama::new().stream(
+            "POST",
+            "/api/push",
+            vec![
+                json!({ "status": "retrieving manifest" }),
+                json!({ "status": "pushing manifest" }),
+                json!({ "status": "success" }),
+            ],
+        );
+
+        let mut statuses = Vec::new();
+        mock.client()
+            .push_model("me/llama3".to_string(), false, |p: &Progress| {
+                statuses.push(p.status.clone())
+            })
+            .unwrap();
+        assert_eq!(
+            statuses,
+            ["retrieving manifest", "pushing manifest", "success"]
+        );
+    }
+
+    #[test]
+    fn test_responses_in_order() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "first", "done": true }),
+            )
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "again", "done": true }),
+            );
+        let ollama = mock.client();
+
+        let request = GenerateRequest::new("llama3", "Hi");
+        let replies: Vec<String> = (0..3)
+            .map(|_| ollama.generate(&request).unwrap().response)
+            .collect();
+        assert_eq!(replies, ["first", "again", "again"]);
+
+        let error = ollama.show_model("llama3".to_string()).unwrap_err();
+        assert!(
+            matches!(error, OllamaError::Http { status: 404, .. }),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_listen() {
+        let mock = MockOllama::new()
+            .version("0.6.0")
+            .error("POST", "/api/show", 404, "model 'nope' not found")
+            .stream(
+                "POST",
+                "/api/push",
+                vec![
+                    json!({ "status": "pushing manifest" }),
+                    json!({ "status": "success" }),
+                ],
+            );
+        let server = mock.listen().unwrap();
+        let ollama = server.client();
+        assert_eq!(ollama.version, Version::new(0, 6, 0));
+
+        let error = ollama.show_model("nope".to_string()).unwrap_err();
+        assert!(matches!(error, OllamaError::ModelNotFound(ref name) if name == "nope"));
+
+        let mut count = 0;
+        ollama
+            .push_model("me/llama3".to_string(), false, |_| count += 1)
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let requests = mock.requests();
+        assert_eq!(requests[1].path, "/api/show");
+        assert_eq!(
+            requests[1].body,
+            Some(Body::Json(br#"{"model":"nope:latest"}"#.to_vec()))
+        );
+        assert_eq!(mock.request_json(1), json!({ "model": "nope:latest" }));
+        drop(server);
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::OllamaError;
+
+// Models pulled from the default registry are listed without this prefix
+const DEFAULT_PREFIX: &str = "registry.ollama.ai/library/";
+
+// A model reference, `[host/][namespace/]model[:tag][@digest]`, in the form the server lists
+// it: with the implicit ":latest" spelled out and the default registry left off. The case is
+// kept as given, registry and Hugging Face paths are case-sensitive, but names compare
+// case-insensitively. "llama3", "Llama3:latest" and "registry.ollama.ai/library/llama3" are the
+// same ModelName.
+#[derive(Debug, Clone)]
+pub struct ModelName {
+    name: String,
+    tag: String,
+    digest: Option<String>,
+}
+
+impl ModelName {
+    pub fn parse(value: &str) -> Result<ModelName, OllamaError> {
+        let invalid =
+            |reason: &str| OllamaError::InvalidModelName(format!("{:?}: {}", value, reason));
+        let value = value.trim();
+
+        let (rest, digest) = match value.split_once('@') {
+            Some((rest, digest)) => {
+                let digest = digest.to_lowercase();
+                let hex = digest
+                    .strip_prefix("sha256:")
+                    .ok_or_else(|| invalid("the digest must start with sha256:"))?;
+                if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(invalid("the digest must be 64 hex digits"));
+                }
+                (rest, Some(digest))
+            }
+            None => (value, None),
+        };
+
+        // A colon after the last slash starts the tag, one before it is a registry port
+        let slash = rest.rfind('/').map_or(0, |i| i + 1);
+        let (name, tag) = match rest[slash..].rfind(':') {
+            Some(colon) => (&rest[..slash + colon], &rest[slash + colon + 1..]),
+            None => (rest, "latest"),
+        };
+
+        if !valid_part(tag, 128) {
+            return Err(invalid(
+                "the tag may only use letters, digits, '_', '-' and '.'",
+            ));
+        }
+        let name = match name.get(..DEFAULT_PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(DEFAULT_PREFIX) => {
+                &name[DEFAULT_PREFIX.len()..]
+            }
+            _ => name,
+        };
+        let parts: Vec<&str> = name.split('/').collect();
+        if parts.len() > 3 {
+            return Err(invalid("expected at most host/namespace/model"));
+        }
+        for (i, part) in parts.iter().enumerate() {
+            // Only the host may carry a port
+            let part = match (i, parts.len()) {
+                (0, 3) => part.split_once(':').map_or(*part, |(host, _)| host),
+                _ => part,
+            };
+            if !valid_part(part, 80) {
+                return Err(invalid(
+                    "names may only use letters, digits, '_', '-' and '.'",
+                ));
+            }
+        }
+
+        Ok(ModelName {
+            name: name.to_string(),
+            tag: tag.to_string(),
+            digest,
+        })
+    }
+
+    // Everything before the tag, e.g. "llama3" or "hf.co/user/model"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    // The same model, pinned to `digest` or unpinned with None
+    pub fn with_digest(&self, digest: Option<String>) -> ModelName {
+        ModelName {
+            digest,
+            ..self.clone()
+        }
+    }
+
+    // Whether `name`, as the server lists it, is this model. A pinned digest isn't compared,
+    // the server lists names without one.
+    pub fn matches(&self, name: &str) -> bool {
+        ModelName::parse(name).is_ok_and(|other| self.same_model(&other))
+    }
+
+    fn same_model(&self, other: &ModelName) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name) && self.tag.eq_ignore_ascii_case(&other.tag)
+    }
+}
+
+impl PartialEq for ModelName {
+    fn eq(&self, other: &ModelName) -> bool {
+        self.same_model(other) && self.digest == other.digest
+    }
+}
+
+impl Eq for ModelName {}
+
+// Consistent with PartialEq, names that differ only in case hash the same
+impl Hash for ModelName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.to_ascii_lowercase().hash(state);
+        self.tag.to_ascii_lowercase().hash(state);
+        self.digest.hash(state);
+    }
+}
+
+// Starts with a letter, digit or '_', as the server requires
+fn valid_part(part: &str, max_len: usize) -> bool {
+    !part.is_empty()
+        && part.len() <= max_len
+        && part.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        && part
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+impl fmt::Display for ModelName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name, self.tag)?;
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ModelName {
+    type Err = OllamaError;
+
+    fn from_str(value: &str) -> Result<ModelName, OllamaError> {
+        ModelName::parse(value)
+    }
+}
+
+impl Serialize for ModelName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ModelName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ModelName, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        ModelName::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+// What the model management calls take: a ModelName, or a string that is parsed into one
+pub trait IntoModelName {
+    fn into_model_name(self) -> Result<ModelName, OllamaError>;
+}
+
+impl IntoModelName for ModelName {
+    fn into_model_name(self) -> Result<ModelName, OllamaError> {
+        Ok(self)
+    }
+}
+
+impl IntoModelName for &ModelName {
+    fn into_model_name(self) -> Result<ModelName, OllamaError> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoModelName for &str {
+    fn into_model_name(self) -> Result<ModelName, OllamaError> {
+        ModelName::parse(self)
+    }
+}
+
+impl IntoModelName for String {
+    fn into_model_name(self) -> Result<ModelName, OllamaError> {
+        ModelName::parse(&self)
+    }
+}
+
+impl IntoModelName for &String {
+    fn into_model_name(self) -> Result<ModelName, OllamaError> {
+        ModelName::parse(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let name = ModelName::parse("llama3").unwrap();
+        assert_eq!(
+            (name.name(), name.tag(), name.digest()),
+            ("llama3", "latest", None)
+        );
+        assert_eq!(name.to_string(), "llama3:latest");
+        assert_eq!(name, ModelName::parse("llama3:latest").unwrap());
+        assert_eq!(
+            name,
+            ModelName::parse("registry.ollama.ai/library/Llama3").unwrap()
+        );
+
+        let name = ModelName::parse("localhost:5000/team/coder:7b-q4_K_M").unwrap();
+        assert_eq!(name.name(), "localhost:5000/team/coder");
+        assert_eq!(name.tag(), "7b-q4_K_M");
+
+        // The case is sent as given, but doesn't tell models apart
+        let name = ModelName::parse("hf.co/TheBloke/Foo-GGUF:Q4_K_M").unwrap();
+        assert_eq!(name.to_string(), "hf.co/TheBloke/Foo-GGUF:Q4_K_M");
+        assert!(name.matches("hf.co/thebloke/foo-gguf:q4_k_m"));
+        let lower = ModelName::parse("hf.co/thebloke/foo-gguf:q4_k_m").unwrap();
+        assert_eq!(name, lower);
+        let set: std::collections::HashSet<_> = [name, lower].into_iter().collect();
+        assert_eq!(set.len(), 1);
+
+        let digest = format!("sha256:{}", "ab".repeat(32));
+        let name: ModelName = format!("mistral:7b@{}", digest).parse().unwrap();
+        assert_eq!(name.digest(), Some(digest.as_str()));
+        assert!(name.matches("mistral:7b"));
+        assert!(!name.matches("mistral"));
+
+        for invalid in [
+            "",
+            "llama3:",
+            "llama 3",
+            "-llama3",
+            "a/b/c/d",
+            "llama3@sha256:abc",
+            "llama3@md5:00",
+        ] {
+            assert!(
+                matches!(
+                    ModelName::parse(invalid),
+                    Err(OllamaError::InvalidModelName(_))
+                ),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let name: ModelName = serde_json::from_str("\"phi3\"").unwrap();
+        assert_eq!(serde_json::to_string(&name).unwrap(), "\"phi3:latest\"");
+        assert!(serde_json::from_str::<ModelName>("\"no good\"").is_err());
+    }
+}
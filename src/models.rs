@@ -0,0 +1,622 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::collections::HashMap;
+
+use crate::{IntoModelName, ModelName, Ollama, OllamaError};
+
+// Status update streamed back while a model is being transferred
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Progress {
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+}
+
+// Metadata about a local model as reported by /api/show
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelDetails {
+    pub modelfile: String,
+    pub parameters: String,
+    pub template: String,
+    pub license: String,
+    pub family: Option<String>,
+    pub format: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+    pub context_length: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ShowResponse {
+    #[serde(default)]
+    modelfile: String,
+    #[serde(default)]
+    parameters: String,
+    #[serde(default)]
+    template: String,
+    #[serde(default)]
+    license: String,
+    #[serde(default)]
+    details: ShowDetails,
+    #[serde(default)]
+    model_info: HashMap<String, Value>,
+}
+
+#[derive(Default, Deserialize)]
+struct ShowDetails {
+    family: Option<String>,
+    format: Option<String>,
+    parameter_size: Option<String>,
+    quantization_level: Option<String>,
+}
+
+impl From<ShowResponse> for ModelDetails {
+    fn from(show: ShowResponse) -> ModelDetails {
+        // The context length key is prefixed with the model architecture, e.g. "llama.context_length"
+        let context_length = show
+            .model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, v)| v.as_u64());
+
+        ModelDetails {
+            modelfile: show.modelfile,
+            parameters: show.parameters,
+            template: show.template,
+            license: show.license,
+            family: show.details.family,
+            format: show.details.format,
+            parameter_size: show.details.parameter_size,
+            quantization_level: show.details.quantization_level,
+            context_length,
+        }
+    }
+}
+
+// A local model as listed by /api/tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    pub name: String,
+    pub size: u64,
+    pub digest: String,
+    pub modified_at: String,
+    pub family: Option<String>,
+    pub format: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+}
+
+// A model currently loaded into memory as reported by /api/ps
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RunningModel {
+    pub name: String,
+    #[serde(default)]
+    pub digest: String,
+    // Bytes of memory the loaded model takes, weights and context together
+    #[serde(default)]
+    pub size: u64,
+    // The part of `size` in GPU memory, the rest is in system RAM
+    #[serde(default)]
+    pub size_vram: u64,
+    #[serde(default)]
+    pub expires_at: String,
+    // The context window it was loaded with, from servers that report it
+    #[serde(default)]
+    pub context_length: Option<u64>,
+}
+
+impl RunningModel {
+    // Bytes in system RAM, for models that didn't fit on the GPU
+    pub fn size_ram(&self) -> u64 {
+        self.size.saturating_sub(self.size_vram)
+    }
+
+    // Runs at full speed, with nothing offloaded to the CPU
+    pub fn fully_on_gpu(&self) -> bool {
+        self.size > 0 && self.size_vram >= self.size
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelList<T> {
+    models: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    digest: String,
+    #[serde(default)]
+    modified_at: String,
+    #[serde(default)]
+    details: ShowDetails,
+}
+
+impl From<ModelEntry> for ModelInfo {
+    fn from(entry: ModelEntry) -> ModelInfo {
+        ModelInfo {
+            name: entry.name,
+            size: entry.size,
+            digest: entry.digest,
+            modified_at: entry.modified_at,
+            family: entry.details.family,
+            format: entry.details.format,
+            parameter_size: entry.details.parameter_size,
+            quantization_level: entry.details.quantization_level,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ShowRequest<'a> {
+    model: &'a ModelName,
+}
+
+// A model to build on the server with /api/create, from an existing model or uploaded blobs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreateRequest {
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    // File name in the model -> blob digest, see create_blob
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<HashMap<String, String>>,
+    // e.g. "q4_K_M" or "q8_0", only applies to fp16/fp32 models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantize: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    stream: bool,
+}
+
+impl CreateRequest {
+    pub fn new(model: impl Into<String>) -> CreateRequest {
+        CreateRequest {
+            model: model.into(),
+            from: None,
+            files: None,
+            quantize: None,
+            system: None,
+            template: None,
+            license: None,
+            stream: true,
+        }
+    }
+
+    pub fn from(mut self, model: impl Into<String>) -> CreateRequest {
+        self.from = Some(model.into());
+        self
+    }
+
+    pub fn file(mut self, name: impl Into<String>, digest: impl Into<String>) -> CreateRequest {
+        self.files
+            .get_or_insert_with(HashMap::new)
+            .insert(name.into(), digest.into());
+        self
+    }
+
+    pub fn quantize(mut self, quantization: impl Into<String>) -> CreateRequest {
+        self.quantize = Some(quantization.into());
+        self
+    }
+
+    pub fn system(mut self, system: impl Into<String>) -> CreateRequest {
+        self.system = Some(system.into());
+        self
+    }
+
+    pub fn template(mut self, template: impl Into<String>) -> CreateRequest {
+        self.template = Some(template.into());
+        self
+    }
+
+    pub fn license(mut self, license: impl Into<String>) -> CreateRequest {
+        self.license = Some(license.into());
+        self
+    }
+}
+
+// What ensure_model does about a model that isn't installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullPolicy {
+    // Fail with OllamaError::ModelNotFound
+    Never,
+    IfMissing,
+}
+
+// The body of both /api/pull and /api/push
+#[derive(Serialize)]
+struct RegistryRequest<'a> {
+    model: &'a ModelName,
+    insecure: bool,
+    stream: bool,
+}
+
+impl Ollama {
+    pub fn available_models(&self) -> Result<Vec<String>, OllamaError> {
+        Ok(self.list_models()?.into_iter().map(|m| m.name).collect())
+    }
+
+    pub fn list_models(&self) -> Result<Vec<ModelInfo>, OllamaError> {
+        let list: ModelList<ModelEntry> = self.get_json("/api/tags")?;
+        Ok(list.models.into_iter().map(ModelInfo::from).collect())
+    }
+
+    pub fn running_models(&self) -> Result<Vec<RunningModel>, OllamaError> {
+        let list: ModelList<RunningModel> = self.get_json("/api/ps")?;
+        Ok(list.models)
+    }
+
+    // Bytes of GPU memory the loaded models take together. The server doesn't report how much
+    // the GPU has, compare against what you know of the machine before loading another model.
+    pub fn gpu_memory_in_use(&self) -> Result<u64, OllamaError> {
+        Ok(self.running_models()?.iter().map(|m| m.size_vram).sum())
+    }
+
+    // Bytes of GPU and system memory the loaded models take together
+    pub fn memory_in_use(&self) -> Result<u64, OllamaError> {
+        Ok(self.running_models()?.iter().map(|m| m.size).sum())
+    }
+
+    // Downloads `name` from the registry, or updates it when it's already here
+    pub fn pull_model(
+        &self,
+        name: impl IntoModelName,
+        insecure: bool,
+        mut on_progress: impl FnMut(&Progress),
+    ) -> Result<(), OllamaError> {
+        let request = RegistryRequest {
+            model: &name.into_model_name()?,
+            insecure,
+            stream: true,
+        };
+
+        self.follow_progress("/api/pull", &request, "Pull", &mut on_progress)
+    }
+
+    pub fn push_model(
+        &self,
+        name: impl IntoModelName,
+        insecure: bool,
+        mut on_progress: impl FnMut(&Progress),
+    ) -> Result<(), OllamaError> {
+        let request = RegistryRequest {
+            model: &name.into_model_name()?,
+            insecure,
+            stream: true,
+        };
+
+        self.follow_progress("/api/push", &request, "Push", &mut on_progress)
+    }
+
+    // Progress reports e.g. "quantizing F16 model to Q4_K_M" before the final "success"
+    pub fn create_model(
+        &self,
+        request: &CreateRequest,
+        mut on_progress: impl FnMut(&Progress),
+    ) -> Result<(), OllamaError> {
+        // Created under the same name list_models and has_model will report it by
+        let request = CreateRequest {
+            model: request.model.as_str().into_model_name()?.to_string(),
+            ..request.clone()
+        };
+        self.follow_progress("/api/create", &request, "Create", &mut on_progress)
+    }
+
+    // Whether the model is installed. With a pinned digest, only that version counts.
+    pub fn has_model(&self, name: impl IntoModelName) -> Result<bool, OllamaError> {
+        let name = name.into_model_name()?;
+        let digest = name.digest().map(|d| d.trim_start_matches("sha256:"));
+        Ok(self.list_models()?.iter().any(|model| {
+            name.matches(&model.name)
+                && digest.is_none_or(|d| model.digest.trim_start_matches("sha256:") == d)
+        }))
+    }
+
+    // Makes sure the model is installed before it's used, pulling it when `policy` allows.
+    // `on_progress` only hears from a pull.
+    pub fn ensure_model(
+        &self,
+        name: impl IntoModelName,
+        policy: PullPolicy,
+        on_progress: impl FnMut(&Progress),
+    ) -> Result<(), OllamaError> {
+        let name = name.into_model_name()?;
+        if self.has_model(&name)? {
+            return Ok(());
+        }
+        match policy {
+            PullPolicy::Never => Err(OllamaError::ModelNotFound(name.to_string())),
+            PullPolicy::IfMissing => self.pull_model(name, false, on_progress),
+        }
+    }
+
+    fn follow_progress<B: Serialize>(
+        &self,
+        path: &str,
+        request: &B,
+        action: &str,
+        on_progress: &mut impl FnMut(&Progress),
+    ) -> Result<(), OllamaError> {
+        let mut succeeded = false;
+        for progress in self.post_stream::<_, Progress>(path, request)? {
+            let progress = progress?;
+            succeeded = progress.status == "success";
+            on_progress(&progress);
+        }
+
+        if succeeded {
+            Ok(())
+        } else {
+            Err(OllamaError::InvalidResponse(format!(
+                "{} ended without a success status",
+                action
+            )))
+        }
+    }
+
+    pub fn show_model(&self, name: impl IntoModelName) -> Result<ModelDetails, OllamaError> {
+        let name = name.into_model_name()?;
+        let show: ShowResponse = self.post_json("/api/show", &ShowRequest { model: &name })?;
+        Ok(show.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_from_json() {
+        let value = serde_json::json!({
+            "status": "pushing abc123",
+            "digest": "sha256:abc123",
+            "total": 2048,
+            "completed": 1024
+        });
+        let progress: Progress = serde_json::from_value(value).unwrap();
+        assert_eq!(progress.status, "pushing abc123");
+        assert_eq!(progress.digest.as_deref(), Some("sha256:abc123"));
+        assert_eq!(progress.total, Some(2048));
+        assert_eq!(progress.completed, Some(1024));
+
+        assert!(
+            serde_json::from_value::<Progress>(serde_json::json!({ "error": "nope" })).is_err()
+        );
+    }
+
+    #[test]
+    fn test_ensure_model() {
+        let tags = serde_json::json!({ "models": [
+            { "name": "llama3:latest", "digest": "a".repeat(64) }
+        ] });
+        let mock = crate::MockOllama::new()
+            .respond("GET", "/api/tags", 200, tags)
+            .stream(
+                "POST",
+                "/api/pull",
+                vec![serde_json::json!({ "status": "success" })],
+            );
+        let ollama = mock.client();
+
+        ollama
+            .ensure_model("llama3", PullPolicy::Never, |_| {})
+            .unwrap();
+        let pinned = format!("llama3@sha256:{}", "b".repeat(64));
+        assert!(!ollama.has_model(pinned.as_str()).unwrap());
+
+        let error = ollama
+            .ensure_model("phi3", PullPolicy::Never, |_| {})
+            .unwrap_err();
+        assert!(matches!(&error, OllamaError::ModelNotFound(name) if name == "phi3:latest"));
+
+        let mut pulled = 0;
+        ollama
+            .ensure_model("phi3", PullPolicy::IfMissing, |_| pulled += 1)
+            .unwrap();
+        assert_eq!(pulled, 1);
+        let paths: Vec<String> = mock.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths.last().unwrap(), "/api/pull");
+        assert_eq!(paths.iter().filter(|p| *p == "/api/pull").count(), 1);
+    }
+
+    #[test]
+    fn test_pull_model() {
+        let mock = crate::MockOllama::new().stream(
+            "POST",
+            "/api/pull",
+            vec![
+                serde_json::json!({ "status": "pulling manifest" }),
+                serde_json::json!({ "status": "pulling abc", "total": 10, "completed": 10 }),
+                serde_json::json!({ "status": "success" }),
+            ],
+        );
+        let mut statuses = Vec::new();
+        mock.client()
+            .pull_model("llama3".to_string(), false, |p| {
+                statuses.push(p.status.clone())
+            })
+            .unwrap();
+        assert_eq!(statuses, ["pulling manifest", "pulling abc", "success"]);
+
+        let failed = crate::MockOllama::new().stream(
+            "POST",
+            "/api/pull",
+            vec![serde_json::json!({ "status": "pulling manifest" })],
+        );
+        let error = failed
+            .client()
+            .pull_model("llama3".to_string(), false, |_| {})
+            .unwrap_err();
+        assert!(
+            matches!(error, OllamaError::InvalidResponse(_)),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_model_details_from_json() {
+        let value = serde_json::json!({
+            "modelfile": "FROM llama3",
+            "parameters": "stop \"<|eot_id|>\"",
+            "template": "{{ .Prompt }}",
+            "license": "MIT",
+            "details": {
+                "format": "gguf",
+                "family": "llama",
+                "parameter_size": "8.0B",
+                "quantization_level": "Q4_0"
+            },
+            "model_info": {
+                "general.architecture": "llama",
+                "llama.context_length": 8192
+            }
+        });
+        let details: ModelDetails = serde_json::from_value::<ShowResponse>(value)
+            .unwrap()
+            .into();
+        assert_eq!(details.modelfile, "FROM llama3");
+        assert_eq!(details.license, "MIT");
+        assert_eq!(details.family.as_deref(), Some("llama"));
+        assert_eq!(details.quantization_level.as_deref(), Some("Q4_0"));
+        assert_eq!(details.context_length, Some(8192));
+    }
+
+    #[test]
+    fn test_model_info_from_json() {
+        let value = serde_json::json!({
+            "name": "llama3:latest",
+            "model": "llama3:latest",
+            "modified_at": "2024-05-20T10:00:00.123456-07:00",
+            "size": 4661224676u64,
+            "digest": "365c0bd3c000a25d28ddbf732fe1c6add414de7275464c4e4d1c3b5fcb5d8ad1",
+            "details": {
+                "parent_model": "",
+                "format": "gguf",
+                "family": "llama",
+                "families": ["llama"],
+                "parameter_size": "8.0B",
+                "quantization_level": "Q4_0"
+            }
+        });
+        let info: ModelInfo = serde_json::from_value::<ModelEntry>(value).unwrap().into();
+        assert_eq!(info.name, "llama3:latest");
+        assert_eq!(info.size, 4661224676);
+        assert_eq!(info.modified_at, "2024-05-20T10:00:00.123456-07:00");
+        assert_eq!(info.parameter_size.as_deref(), Some("8.0B"));
+        assert_eq!(info.quantization_level.as_deref(), Some("Q4_0"));
+
+        let bare: ModelInfo =
+            serde_json::from_value::<ModelEntry>(serde_json::json!({ "name": "x" }))
+                .unwrap()
+                .into();
+        assert_eq!(bare.family, None);
+    }
+
+    #[test]
+    fn test_create_quantized() {
+        let mock = crate::MockOllama::new().stream(
+            "POST",
+            "/api/create",
+            vec![
+                serde_json::json!({ "status": "quantizing F16 model to Q4_K_M" }),
+                serde_json::json!({ "status": "creating new layer sha256:abc" }),
+                serde_json::json!({ "status": "writing manifest" }),
+                serde_json::json!({ "status": "success" }),
+            ],
+        );
+
+        let request = CreateRequest::new("llama3:q4")
+            .from("llama3:fp16")
+            .quantize("q4_K_M");
+        let mut statuses = Vec::new();
+        mock.client()
+            .create_model(&request, |p| statuses.push(p.status.clone()))
+            .unwrap();
+        assert_eq!(statuses.len(), 4);
+        assert_eq!(statuses[0], "quantizing F16 model to Q4_K_M");
+
+        mock.client()
+            .create_model(&CreateRequest::new("Team/Assistant"), |_| {})
+            .unwrap();
+        let body = mock.request_json(3);
+        assert_eq!(body["model"], "Team/Assistant:latest");
+        assert!(matches!(
+            mock.client()
+                .create_model(&CreateRequest::new("bad name"), |_| {}),
+            Err(OllamaError::InvalidModelName(_))
+        ));
+        // Three version checks and two creates, the bad name never went out
+        assert_eq!(mock.requests().len(), 5);
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "model": "llama3:q4",
+                "from": "llama3:fp16",
+                "quantize": "q4_K_M",
+                "stream": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_running_model_from_json() {
+        let value = serde_json::json!({
+            "name": "mistral:latest",
+            "model": "mistral:latest",
+            "size": 5137025024u64,
+            "digest": "2ae6f6dd7a3dd734790bbbf58b8909a606e0e7e97e94b7604e0aa7ae4490e6d8",
+            "expires_at": "2024-06-04T14:38:31.83753-07:00",
+            "size_vram": 5137025024u64
+        });
+        let model: RunningModel = serde_json::from_value(value).unwrap();
+        assert_eq!(model.name, "mistral:latest");
+        assert_eq!(model.size, 5137025024);
+        assert_eq!(model.size_vram, 5137025024);
+        assert_eq!(model.expires_at, "2024-06-04T14:38:31.83753-07:00");
+        assert_eq!(model.context_length, None);
+        assert!(model.fully_on_gpu());
+        assert_eq!(model.size_ram(), 0);
+    }
+
+    #[test]
+    fn test_memory_in_use() {
+        let mock = crate::MockOllama::new().respond(
+            "GET",
+            "/api/ps",
+            200,
+            serde_json::json!({ "models": [
+                { "name": "llama3:latest", "size": 6_000, "size_vram": 6_000, "context_length": 8192 },
+                { "name": "mixtral:latest", "size": 30_000, "size_vram": 20_000 }
+            ] }),
+        );
+        let ollama = mock.client();
+        assert_eq!(ollama.gpu_memory_in_use().unwrap(), 26_000);
+        assert_eq!(ollama.memory_in_use().unwrap(), 36_000);
+
+        let models = ollama.running_models().unwrap();
+        assert_eq!(models[0].context_length, Some(8192));
+        assert!(!models[1].fully_on_gpu());
+        assert_eq!(models[1].size_ram(), 10_000);
+
+        let idle = crate::MockOllama::new().respond(
+            "GET",
+            "/api/ps",
+            200,
+            serde_json::json!({ "models": [] }),
+        );
+        assert_eq!(idle.client().gpu_memory_in_use().unwrap(), 0);
+    }
+}
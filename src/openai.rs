@@ -0,0 +1,395 @@
+// Ollama's OpenAI-compatible /v1/chat/completions endpoint, with request and response types
+// shaped like OpenAI's so code written for it ports over by renaming types. Everything else
+// about the client (auth, retries, timeouts, cancellation) works as for the native API.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Ollama, OllamaError, Role, Tool};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    // e.g. {"type": "json_object"}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    // Set by chat_completion_stream
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+impl ChatCompletionRequest {
+    pub fn new(model: impl Into<String>, messages: Vec<Message>) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: model.into(),
+            messages,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stop: None,
+            seed: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            response_format: None,
+            tools: None,
+            stream: false,
+            stream_options: None,
+        }
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> ChatCompletionRequest {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> ChatCompletionRequest {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> ChatCompletionRequest {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn stop(mut self, stop: Vec<String>) -> ChatCompletionRequest {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn seed(mut self, seed: i64) -> ChatCompletionRequest {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn response_format(mut self, format: Value) -> ChatCompletionRequest {
+        self.response_format = Some(format);
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<Tool>) -> ChatCompletionRequest {
+        self.tools = Some(tools);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    // None on assistant messages that only call tools
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    // On tool messages, the id of the call this is the result of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl Message {
+    fn new(role: Role, content: impl Into<String>) -> Message {
+        Message {
+            role,
+            content: Some(content.into()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Message {
+        Message::new(Role::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Message {
+        Message::new(Role::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Message {
+        Message::new(Role::Assistant, content)
+    }
+
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Message {
+        Message {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Message::new(Role::Tool, content)
+        }
+    }
+}
+
+// Unlike the native API's ToolCall, the arguments arrive as a JSON string
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "type", default = "function_type")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+fn function_type() -> String {
+    "function".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChatCompletion {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+impl ChatCompletion {
+    // The text of the first choice, which is the only one Ollama returns
+    pub fn content(&self) -> Option<&str> {
+        self.choices.first()?.message.content.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Choice {
+    pub index: u32,
+    pub message: Message,
+    // "stop", "length" or "tool_calls"
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<ChunkChoice>,
+    // Only on the last chunk
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChunkChoice {
+    pub index: u32,
+    pub delta: Delta,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+// The part of the assistant message that arrived with this chunk
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Delta {
+    #[serde(default)]
+    pub role: Option<Role>,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Option<Vec<ToolCall>>,
+}
+
+impl Ollama {
+    pub fn chat_completion(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletion, OllamaError> {
+        self.post_json("/v1/chat/completions", request)
+    }
+
+    // The reply as server-sent events, one chunk per event until the closing [DONE]. The
+    // last chunk carries the token usage.
+    pub fn chat_completion_stream(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<
+        impl Iterator<Item = Result<ChatCompletionChunk, OllamaError>> + Send + use<>,
+        OllamaError,
+    > {
+        let request = ChatCompletionRequest {
+            stream: true,
+            stream_options: Some(serde_json::json!({ "include_usage": true })),
+            ..request.clone()
+        };
+        let (span, lines) = self.post_lines("/v1/chat/completions", &request)?;
+        let interceptors = self.interceptors.clone();
+
+        Ok(lines
+            .map_while(move |line| match line {
+                Ok(line) => match line.strip_prefix("data:").map(str::trim) {
+                    Some("[DONE]") => None,
+                    Some(data) => Some(Some(Ollama::parse_chunk(&span, &interceptors, data))),
+                    // Comments and other SSE fields carry nothing we need
+                    None => Some(None),
+                },
+                Err(error) => Some(Some(Err(error))),
+            })
+            .flatten())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MockOllama,
+        tests::{json_response, serve},
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_chat_completion() {
+        let mock = MockOllama::new().respond(
+            "POST",
+            "/v1/chat/completions",
+            200,
+            json!({
+                "id": "chatcmpl-7",
+                "object": "chat.completion",
+                "created": 1718000000,
+                "model": "llama3",
+                "system_fingerprint": "fp_ollama",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }],
+                "usage": { "prompt_tokens": 12, "completion_tokens": 9, "total_tokens": 21 }
+            }),
+        );
+
+        let request = ChatCompletionRequest::new(
+            "llama3",
+            vec![
+                Message::system("Use the tools."),
+                Message::user("Weather in Paris?"),
+            ],
+        )
+        .temperature(0.0)
+        .max_tokens(64);
+        let completion = mock.client().chat_completion(&request).unwrap();
+        assert_eq!(completion.content(), None);
+        let calls = completion.choices[0].message.tool_calls.as_ref().unwrap();
+        assert_eq!(calls[0].function.arguments, "{\"city\":\"Paris\"}");
+        assert_eq!(completion.usage.unwrap().total_tokens, 21);
+
+        let body = mock.request_json(1);
+        assert_eq!(
+            body,
+            json!({
+                "model": "llama3",
+                "messages": [
+                    { "role": "system", "content": "Use the tools." },
+                    { "role": "user", "content": "Weather in Paris?" }
+                ],
+                "temperature": 0.0,
+                "max_tokens": 64
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_completion_stream() {
+        let chunk = |delta: Value, finish: Value| {
+            json!({
+                "id": "chatcmpl-7",
+                "object": "chat.completion.chunk",
+                "created": 1718000000,
+                "model": "llama3",
+                "choices": [{ "index": 0, "delta": delta, "finish_reason": finish }]
+            })
+        };
+        let events = format!(
+            "data: {}\n\n: keep-alive\n\ndata: {}\n\ndata: [DONE]\n\n",
+            chunk(
+                json!({ "role": "assistant", "content": "Hel" }),
+                Value::Null
+            ),
+            chunk(json!({ "content": "lo" }), json!("stop")),
+        );
+        let (ollama, server) = serve(vec![
+            json_response("200 OK", r#"{"version":"0.5.1"}"#),
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\n\r\n{}",
+                events.len(),
+                events
+            ),
+        ]);
+
+        let request = ChatCompletionRequest::new("llama3", vec![Message::user("Hi")]);
+        let chunks: Vec<ChatCompletionChunk> = ollama
+            .chat_completion_stream(&request)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| c.choices[0].delta.content.as_deref())
+            .collect();
+        assert_eq!(text, "Hello");
+        assert_eq!(chunks[1].choices[0].finish_reason.as_deref(), Some("stop"));
+
+        let captured = server.join().unwrap();
+        let body: Value = serde_json::from_str(&captured[1].body).unwrap();
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["stream_options"]["include_usage"], true);
+    }
+
+    #[test]
+    fn test_openai_error_body() {
+        let mock = MockOllama::new().respond(
+            "POST",
+            "/v1/chat/completions",
+            404,
+            json!({ "error": { "message": "model \"gpt-4\" not found", "type": "api_error" } }),
+        );
+        let request = ChatCompletionRequest::new("gpt-4", vec![Message::user("Hi")]);
+        let error = mock.client().chat_completion(&request).unwrap_err();
+        assert!(
+            matches!(&error, OllamaError::ModelNotFound(name) if name == "gpt-4"),
+            "{}",
+            error
+        );
+    }
+}
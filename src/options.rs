@@ -0,0 +1,237 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::time::Duration;
+
+// Parameters sent in the `options` field of generate and chat requests: how to sample, and
+// how to load the model. Anything left as None falls back to the model's Modelfile defaults,
+// then the server's.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Options {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+
+    // Loading options. The server reloads the model when a request asks for different ones
+    // than it's loaded with, so keep them the same across requests to a model.
+    //
+    // The context window in tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    // Layers offloaded to the GPU, 0 runs on the CPU only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_gpu: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_gpu: Option<u32>,
+    // CPU threads, the server picks by core count when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_thread: Option<u32>,
+    // Tokens evaluated per batch of the prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_batch: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_vram: Option<bool>,
+    // Maps the weights from disk instead of reading them in, false can help on network storage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_mmap: Option<bool>,
+    // Locks the weights in RAM so they are never swapped out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_mlock: Option<bool>,
+}
+
+impl Options {
+    // Greedy decoding with a fixed seed: the same request gets the same reply every time, as
+    // long as the model, the server version and the hardware it runs on stay the same. Good
+    // for snapshot tests of model output.
+    pub fn deterministic(seed: i64) -> Options {
+        Options::default().temperature(0.0).top_k(1).seed(seed)
+    }
+
+    // Whether repeating a request gives the same reply: it decodes greedily, with a
+    // temperature of 0 or top_k of 1, or samples with a fixed seed
+    pub fn is_deterministic(&self) -> bool {
+        self.temperature == Some(0.0) || self.top_k == Some(1) || self.seed.is_some()
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Options {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> Options {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn top_k(mut self, top_k: u32) -> Options {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    pub fn seed(mut self, seed: i64) -> Options {
+        self.seed = Some(seed);
+        self
+    }
+
+    // -1 generates until the model stops on its own
+    pub fn num_predict(mut self, num_predict: i32) -> Options {
+        self.num_predict = Some(num_predict);
+        self
+    }
+
+    pub fn stop(mut self, stop: impl IntoIterator<Item = impl Into<String>>) -> Options {
+        self.stop = Some(stop.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn num_ctx(mut self, num_ctx: u32) -> Options {
+        self.num_ctx = Some(num_ctx);
+        self
+    }
+
+    pub fn num_gpu(mut self, layers: i32) -> Options {
+        self.num_gpu = Some(layers);
+        self
+    }
+
+    pub fn main_gpu(mut self, gpu: u32) -> Options {
+        self.main_gpu = Some(gpu);
+        self
+    }
+
+    pub fn num_thread(mut self, threads: u32) -> Options {
+        self.num_thread = Some(threads);
+        self
+    }
+
+    pub fn num_batch(mut self, num_batch: u32) -> Options {
+        self.num_batch = Some(num_batch);
+        self
+    }
+
+    pub fn low_vram(mut self, low_vram: bool) -> Options {
+        self.low_vram = Some(low_vram);
+        self
+    }
+
+    pub fn use_mmap(mut self, use_mmap: bool) -> Options {
+        self.use_mmap = Some(use_mmap);
+        self
+    }
+
+    pub fn use_mlock(mut self, use_mlock: bool) -> Options {
+        self.use_mlock = Some(use_mlock);
+        self
+    }
+}
+
+// How long the server keeps a model in memory after a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAlive {
+    For(Duration),
+    Forever,
+    UnloadNow,
+}
+
+impl Serialize for KeepAlive {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            // Go duration syntax, which is what the server parses strings with
+            KeepAlive::For(duration) => {
+                serializer.serialize_str(&format!("{}ms", duration.as_millis()))
+            }
+            KeepAlive::Forever => serializer.serialize_i64(-1),
+            KeepAlive::UnloadNow => serializer.serialize_i64(0),
+        }
+    }
+}
+
+// Seconds or a duration string, as the server accepts them
+impl<'de> Deserialize<'de> for KeepAlive {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<KeepAlive, D::Error> {
+        crate::config::keep_alive(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_alive_serialization() {
+        let to_json = |k: KeepAlive| serde_json::to_value(k).unwrap();
+        assert_eq!(
+            to_json(KeepAlive::For(Duration::from_secs(300))),
+            "300000ms"
+        );
+        assert_eq!(to_json(KeepAlive::Forever), -1);
+        assert_eq!(to_json(KeepAlive::UnloadNow), 0);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let options = Options::deterministic(42);
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            serde_json::json!({ "temperature": 0.0, "top_k": 1, "seed": 42 })
+        );
+        assert!(options.is_deterministic());
+        assert!(Options::default().seed(1).is_deterministic());
+        assert!(!Options::default().is_deterministic());
+        assert!(!Options::default().temperature(0.7).is_deterministic());
+    }
+
+    #[test]
+    fn test_only_set_options_are_serialized() {
+        let options = Options::default()
+            .temperature(0.5)
+            .seed(42)
+            .stop(["\n\n", "User:"]);
+        let value = serde_json::to_value(&options).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "temperature": 0.5,
+                "seed": 42,
+                "stop": ["\n\n", "User:"]
+            })
+        );
+
+        assert_eq!(
+            serde_json::to_value(Options::default()).unwrap(),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_loading_options() {
+        let options = Options::default()
+            .temperature(0.2)
+            .num_ctx(8192)
+            .num_gpu(0)
+            .num_thread(4)
+            .low_vram(true)
+            .use_mmap(false);
+        let value = serde_json::to_value(&options).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "temperature": 0.2f32,
+                "num_ctx": 8192,
+                "num_gpu": 0,
+                "num_thread": 4,
+                "low_vram": true,
+                "use_mmap": false
+            })
+        );
+        assert_eq!(serde_json::from_value::<Options>(value).unwrap(), options);
+    }
+}
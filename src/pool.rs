@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{Instant, connection::Connection};
+
+// Idle connections are dropped after this, the server has likely closed them by then
+const IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+// Per server, with the time each connection went idle
+type Idle = HashMap<String, Vec<(Instant, Connection)>>;
+
+// Keeps finished connections open so the next request to the same server skips the TCP/TLS
+// handshake. Clones share their idle connections, like clones of a client do.
+#[derive(Clone)]
+pub struct ConnectionPool {
+    idle: Arc<Mutex<Idle>>,
+    max_idle_per_host: usize,
+}
+
+impl ConnectionPool {
+    // Keeps at most `max_idle_per_host` idle connections per server, 0 disables reuse
+    pub fn new(max_idle_per_host: usize) -> ConnectionPool {
+        ConnectionPool {
+            idle: Arc::default(),
+            max_idle_per_host,
+        }
+    }
+
+    // Every request gets a fresh connection that is closed afterwards
+    pub fn disabled() -> ConnectionPool {
+        ConnectionPool::new(0)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_idle_per_host > 0
+    }
+
+    // Drops all idle connections
+    pub fn clear(&self) {
+        self.idle.lock().unwrap().clear();
+    }
+
+    pub fn idle_connections(&self) -> usize {
+        self.idle.lock().unwrap().values().map(Vec::len).sum()
+    }
+
+    // The most recently used connection is handed out first, it is the least likely to be stale
+    pub(crate) fn take(&self, key: &str) -> Option<Connection> {
+        let mut idle = self.idle.lock().unwrap();
+        let connections = idle.get_mut(key)?;
+        connections.retain(|(since, _)| since.elapsed() < IDLE_TIMEOUT);
+        connections.pop().map(|(_, connection)| connection)
+    }
+
+    pub(crate) fn put(&self, key: &str, connection: Connection) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut idle = self.idle.lock().unwrap();
+        let connections = idle.entry(key.to_string()).or_default();
+        if connections.len() >= self.max_idle_per_host {
+            connections.remove(0);
+        }
+        connections.push((Instant::now(), connection));
+    }
+}
+
+impl Default for ConnectionPool {
+    fn default() -> ConnectionPool {
+        ConnectionPool::new(4)
+    }
+}
+
+// Two pools are equal when they are clones of each other
+impl PartialEq for ConnectionPool {
+    fn eq(&self, other: &ConnectionPool) -> bool {
+        Arc::ptr_eq(&self.idle, &other.idle) && self.max_idle_per_host == other.max_idle_per_host
+    }
+}
+
+impl Eq for ConnectionPool {}
+
+impl fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("max_idle_per_host", &self.max_idle_per_host)
+            .field("idle", &self.idle_connections())
+            .finish()
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use crate::{ChatMessage, ChatRequest, EmbedRequest, Ollama, OllamaError};
+
+// The calls most LLM pipelines are built from, for code that shouldn't care which backend
+// answers them. Take a `&dyn Provider` or `Arc<dyn Provider>` and pass an OllamaProvider, a
+// test double, or an adapter around another crate's client.
+pub trait Provider: Send + Sync {
+    // A single completion of `prompt`
+    fn generate(&self, prompt: &str) -> Result<String, OllamaError>;
+
+    // The assistant's reply to the conversation
+    fn chat(&self, messages: &[ChatMessage]) -> Result<ChatMessage, OllamaError>;
+
+    // One embedding per text, in the same order
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, OllamaError>;
+}
+
+impl<P: Provider + ?Sized> Provider for &P {
+    fn generate(&self, prompt: &str) -> Result<String, OllamaError> {
+        (**self).generate(prompt)
+    }
+
+    fn chat(&self, messages: &[ChatMessage]) -> Result<ChatMessage, OllamaError> {
+        (**self).chat(messages)
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, OllamaError> {
+        (**self).embed(texts)
+    }
+}
+
+impl<P: Provider + ?Sized> Provider for Box<P> {
+    fn generate(&self, prompt: &str) -> Result<String, OllamaError> {
+        (**self).generate(prompt)
+    }
+
+    fn chat(&self, messages: &[ChatMessage]) -> Result<ChatMessage, OllamaError> {
+        (**self).chat(messages)
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, OllamaError> {
+        (**self).embed(texts)
+    }
+}
+
+impl<P: Provider + ?Sized> Provider for Arc<P> {
+    fn generate(&self, prompt: &str) -> Result<String, OllamaError> {
+        (**self).generate(prompt)
+    }
+
+    fn chat(&self, messages: &[ChatMessage]) -> Result<ChatMessage, OllamaError> {
+        (**self).chat(messages)
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, OllamaError> {
+        (**self).embed(texts)
+    }
+}
+
+// A client with the models a Provider's calls go to, chat models rarely embed well so
+// embeddings can have their own. With the `llm` feature it's also a backend of the `llm`
+// crate's ChatProvider, CompletionProvider and EmbeddingProvider.
+#[derive(Debug, Clone)]
+pub struct OllamaProvider {
+    pub ollama: Ollama,
+    // Empty for the client's default model
+    pub model: String,
+    // None to embed with `model`
+    pub embedding_model: Option<String>,
+}
+
+impl OllamaProvider {
+    pub fn new(ollama: Ollama, model: impl Into<String>) -> OllamaProvider {
+        OllamaProvider {
+            ollama,
+            model: model.into(),
+            embedding_model: None,
+        }
+    }
+
+    pub fn embedding_model(mut self, model: impl Into<String>) -> OllamaProvider {
+        self.embedding_model = Some(model.into());
+        self
+    }
+}
+
+impl Ollama {
+    // This client as a Provider using its default model, see with_default_model
+    pub fn provider(&self) -> OllamaProvider {
+        OllamaProvider::new(self.clone(), "")
+    }
+}
+
+impl Provider for OllamaProvider {
+    fn generate(&self, prompt: &str) -> Result<String, OllamaError> {
+        self.ollama.prompt_with(self.model.as_str(), prompt)
+    }
+
+    fn chat(&self, messages: &[ChatMessage]) -> Result<ChatMessage, OllamaError> {
+        let request = ChatRequest::new(self.model.as_str(), messages.to_vec());
+        Ok(self.ollama.chat(&request)?.message)
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, OllamaError> {
+        let model = self.embedding_model.as_ref().unwrap_or(&self.model);
+        Ok(self
+            .ollama
+            .embed(&EmbedRequest::new(model.as_str(), texts))?
+            .embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockOllama, Role};
+    use serde_json::{Value, json};
+
+    // What a pipeline looks like when written against the trait
+    fn summarize(provider: &dyn Provider, text: &str) -> Result<String, OllamaError> {
+        provider.generate(&format!("Summarize: {}", text))
+    }
+
+    struct Canned;
+
+    impl Provider for Canned {
+        fn generate(&self, _prompt: &str) -> Result<String, OllamaError> {
+            Ok("canned".to_string())
+        }
+
+        fn chat(&self, _messages: &[ChatMessage]) -> Result<ChatMessage, OllamaError> {
+            Ok(ChatMessage::assistant("canned"))
+        }
+
+        fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, OllamaError> {
+            Ok(texts.iter().map(|_| vec![0.0]).collect())
+        }
+    }
+
+    #[test]
+    fn test_ollama_provider() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "Short.", "done": true }),
+            )
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({ "model": "llama3", "message": { "role": "assistant", "content": "Hi!" }, "done": true }),
+            )
+            .respond(
+                "POST",
+                "/api/embed",
+                200,
+                json!({ "model": "nomic-embed-text", "embeddings": [[0.1, 0.2]] }),
+            );
+        let provider: Arc<dyn Provider> = Arc::new(
+            OllamaProvider::new(mock.client(), "llama3").embedding_model("nomic-embed-text"),
+        );
+
+        assert_eq!(summarize(&provider, "A long text").unwrap(), "Short.");
+        let reply = provider.chat(&[ChatMessage::user("Hello")]).unwrap();
+        assert_eq!(reply.role, Role::Assistant);
+        assert_eq!(reply.content, "Hi!");
+        let embeddings = provider.embed(&["Hello".to_string()]).unwrap();
+        assert_eq!(embeddings, [[0.1, 0.2]]);
+
+        let models: Vec<Value> = (1..mock.requests().len())
+            .map(|i| mock.request_json(i)["model"].clone())
+            .collect();
+        assert_eq!(models, ["llama3", "llama3", "nomic-embed-text"]);
+
+        assert_eq!(summarize(&Canned, "A long text").unwrap(), "canned");
+    }
+}
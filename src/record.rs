@@ -0,0 +1,340 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{Error, Read, Write},
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{Body, Instant, Ollama, OllamaError, Request, Response, Transport};
+
+// One line of a recording. The lines of an exchange share an id, and those of concurrent
+// calls can interleave.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum Entry {
+    Request {
+        id: u64,
+        at_ms: u64,
+        method: String,
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body: Option<Value>,
+    },
+    Response {
+        id: u64,
+        at_ms: u64,
+        status: u16,
+    },
+    // A line of the response body, as it arrived
+    Chunk {
+        id: u64,
+        at_ms: u64,
+        data: String,
+    },
+    // The transport failed before there was a response
+    Error {
+        id: u64,
+        at_ms: u64,
+        message: String,
+    },
+}
+
+// Wraps a transport and writes every call to a JSON Lines file: the request, the response
+// status and each line of the body with the milliseconds since recording started. Headers are
+// left out so credentials don't end up in the file. Feed the file to MockOllama::replay to
+// reproduce a session without a server.
+#[derive(Clone)]
+pub struct Recorder {
+    // None when it records for a client, see Ollama::with_recorder
+    inner: Option<Arc<dyn Transport>>,
+    log: Log,
+}
+
+#[derive(Clone)]
+struct Log {
+    file: Arc<Mutex<File>>,
+    start: Instant,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Log {
+    fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    fn write(&self, entry: &Entry) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.lock().unwrap().write_all(&line)
+    }
+}
+
+impl Recorder {
+    // Starts a new recording at `path`, replacing any file there
+    pub fn new(
+        transport: impl Transport + 'static,
+        path: impl AsRef<Path>,
+    ) -> Result<Recorder, OllamaError> {
+        Recorder::wrap(Some(Arc::new(transport)), path)
+    }
+
+    fn wrap(
+        inner: Option<Arc<dyn Transport>>,
+        path: impl AsRef<Path>,
+    ) -> Result<Recorder, OllamaError> {
+        let file = File::create(path).map_err(OllamaError::Io)?;
+        Ok(Recorder {
+            inner,
+            log: Log {
+                file: Arc::new(Mutex::new(file)),
+                start: Instant::now(),
+                next_id: Arc::new(AtomicU64::new(1)),
+            },
+        })
+    }
+}
+
+impl Transport for Recorder {
+    fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+        match &self.inner {
+            Some(inner) => self.record(&**inner, request),
+            None => Err(OllamaError::InvalidRequest(
+                "this recorder belongs to a client and has no transport of its own".to_string(),
+            )),
+        }
+    }
+}
+
+impl Recorder {
+    // Sends `request` through `transport` and records the exchange
+    pub(crate) fn record(
+        &self,
+        transport: &dyn Transport,
+        request: &Request,
+    ) -> Result<Response, OllamaError> {
+        let id = self.log.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = request.body.as_ref().map(|body| match body {
+            Body::Json(bytes) => serde_json::from_slice(bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned())),
+            Body::File(path) => json!({ "file": path }),
+        });
+        self.log
+            .write(&Entry::Request {
+                id,
+                at_ms: self.log.elapsed_ms(),
+                method: request.method.clone(),
+                path: request.path.clone(),
+                body,
+            })
+            .map_err(OllamaError::Io)?;
+
+        let response = match transport.send(request) {
+            Ok(response) => response,
+            Err(error) => {
+                self.log
+                    .write(&Entry::Error {
+                        id,
+                        at_ms: self.log.elapsed_ms(),
+                        message: error.to_string(),
+                    })
+                    .map_err(OllamaError::Io)?;
+                return Err(error);
+            }
+        };
+        self.log
+            .write(&Entry::Response {
+                id,
+                at_ms: self.log.elapsed_ms(),
+                status: response.status,
+            })
+            .map_err(OllamaError::Io)?;
+        Ok(Response::new(
+            response.status,
+            Tap {
+                body: response.into_body(),
+                pending: Vec::new(),
+                id,
+                log: self.log.clone(),
+            },
+        ))
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+// Copies a response body into the recording line by line as the client reads it
+struct Tap {
+    body: Box<dyn Read + Send>,
+    // Read but not yet recorded, short of a newline
+    pending: Vec<u8>,
+    id: u64,
+    log: Log,
+}
+
+impl Tap {
+    // A recording that can't be written doesn't fail the call reading the body
+    fn record(&mut self, line: Vec<u8>) {
+        let _ = self.log.write(&Entry::Chunk {
+            id: self.id,
+            at_ms: self.log.elapsed_ms(),
+            data: String::from_utf8_lossy(&line).into_owned(),
+        });
+    }
+}
+
+impl Read for Tap {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let n = self.body.read(buf)?;
+        self.pending.extend_from_slice(&buf[..n]);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.record(line);
+        }
+        if n == 0 && !self.pending.is_empty() {
+            let rest = std::mem::take(&mut self.pending);
+            self.record(rest);
+        }
+        Ok(n)
+    }
+}
+
+// A body dropped halfway still has what was read of it recorded
+impl Drop for Tap {
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            let rest = std::mem::take(&mut self.pending);
+            self.record(rest);
+        }
+    }
+}
+
+impl Ollama {
+    // A copy of this client that records its calls to `path`, see Recorder. Whatever the copy
+    // is configured with later, e.g. with_timeouts, still applies to the calls it records.
+    pub fn with_recorder(&self, path: impl AsRef<Path>) -> Result<Ollama, OllamaError> {
+        Ok(Ollama {
+            recorder: Some(Recorder::wrap(None, path)?),
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ChatMessage, ChatRequest, GenerateRequest, MockOllama,
+        tests::{json_response, serve},
+    };
+    use std::fs;
+
+    fn recording_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ollama-rs-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = recording_path("recording");
+        let server = MockOllama::new()
+            .version("0.9.0")
+            .respond(
+                "POST",
+                "/api/generate",
+                200,
+                json!({ "model": "llama3", "response": "Hi!", "done": true }),
+            )
+            .stream(
+                "POST",
+                "/api/chat",
+                vec![
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": "Hel" }, "done": false }),
+                    json!({ "model": "llama3", "message": { "role": "assistant", "content": "lo" }, "done": true }),
+                ],
+            )
+            .error("POST", "/api/show", 404, "model 'nope' not found");
+
+        let chat = ChatRequest::new("llama3", vec![ChatMessage::user("Hello")]);
+        let session = |ollama: &Ollama| {
+            let reply = ollama
+                .generate(&GenerateRequest::new("llama3", "Hi"))
+                .unwrap();
+            let chunks: Vec<String> = ollama
+                .chat_stream(&chat)
+                .unwrap()
+                .map(|chunk| chunk.unwrap().message.content)
+                .collect();
+            let error = ollama.show_model("nope").unwrap_err().to_string();
+            (reply.response, chunks, error)
+        };
+
+        let recording = server.client().with_recorder(&path).unwrap();
+        let live = session(&recording);
+        assert_eq!(live.1, ["Hel", "lo"]);
+
+        let entries: Vec<Entry> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(matches!(
+            &entries[0],
+            Entry::Request { id: 1, method, body: Some(body), .. }
+                if method == "POST" && body["prompt"] == "Hi"
+        ));
+        let chunks = entries
+            .iter()
+            .filter(|entry| matches!(entry, Entry::Chunk { id: 2, .. }))
+            .count();
+        assert_eq!(chunks, 2);
+
+        let replayed = MockOllama::replay(&path).unwrap();
+        assert_eq!(session(&replayed.client()), live);
+        // The recording had no version check, so the mock's default answers it
+        assert_eq!(replayed.client().version.to_string(), "0.5.1");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_follows_later_settings() {
+        let path = recording_path("later-settings");
+        let version = json_response("200 OK", r#"{"version":"0.5.1"}"#);
+        let (ollama, server) = serve(vec![version.clone(), version]);
+
+        let mut recording = ollama.with_recorder(&path).unwrap();
+        recording.base_path = "/ollama".to_string();
+        recording.version().unwrap();
+        let requests = server.join().unwrap();
+        assert!(requests[1].head.starts_with("GET /ollama/api/version "));
+        let recorded: Vec<Entry> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(matches!(
+            &recorded[2],
+            Entry::Chunk { data, .. } if data == r#"{"version":"0.5.1"}"#
+        ));
+        fs::remove_file(&path).unwrap();
+
+        let path = recording_path("repeated-id");
+        let request =
+            json!({ "type": "request", "id": 1, "at_ms": 0, "method": "GET", "path": "/api/ps" });
+        fs::write(&path, format!("{}\n{}\n", request, request)).unwrap();
+        assert!(matches!(
+            MockOllama::replay(&path),
+            Err(OllamaError::InvalidResponse(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use crate::{CancellationToken, Instant, OllamaError, limit::CANCEL_POLL};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    // Total attempts including the first one
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    // Randomizes each delay between half and the full backoff so clients don't retry in lockstep
+    pub jitter: bool,
+    pub retry_on: RetryOn,
+}
+
+// Which classes of failures are worth another attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryOn {
+    pub connection: bool,
+    // Off by default: a request that timed out may still be running on the server, and
+    // generate, create or pull would run twice
+    pub timeout: bool,
+    // 5xx responses and 429, e.g. while the server is still loading a model
+    pub server_errors: bool,
+}
+
+impl Default for RetryOn {
+    fn default() -> RetryOn {
+        RetryOn {
+            connection: true,
+            timeout: false,
+            server_errors: true,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: true,
+            retry_on: RetryOn::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn max_attempts(mut self, max_attempts: u32) -> RetryPolicy {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> RetryPolicy {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> RetryPolicy {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retry_on(mut self, retry_on: RetryOn) -> RetryPolicy {
+        self.retry_on = retry_on;
+        self
+    }
+
+    pub fn should_retry(&self, error: &OllamaError) -> bool {
+        match error {
+            OllamaError::Connection(_) => self.retry_on.connection,
+            OllamaError::Timeout => self.retry_on.timeout,
+            _ => {
+                self.retry_on.server_errors
+                    && error
+                        .status()
+                        .is_some_and(|status| status >= 500 || status == 429)
+            }
+        }
+    }
+
+    // Delay before retry number `retry` (starting at 1)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.saturating_sub(1) as i32);
+        let backoff = self.initial_backoff.mul_f64(factor).min(self.max_backoff);
+
+        if self.jitter {
+            let random = RandomState::new().build_hasher().finish();
+            let fraction = 0.5 + (random % 1000) as f64 / 2000.0;
+            backoff.mul_f64(fraction)
+        } else {
+            backoff
+        }
+    }
+
+    // The backoff ends early with Cancelled once `cancel` fires
+    pub(crate) fn run<T>(
+        &self,
+        cancel: Option<&CancellationToken>,
+        mut attempt: impl FnMut() -> Result<T, OllamaError>,
+    ) -> Result<T, OllamaError> {
+        let mut retry = 0;
+        loop {
+            match attempt() {
+                Err(error) if retry + 1 < self.max_attempts && self.should_retry(&error) => {
+                    retry += 1;
+                    // Without it the retry goes out right away
+                    if crate::CAN_WAIT {
+                        let until = Instant::now() + self.delay(retry);
+                        while let Some(wait) = until.checked_duration_since(Instant::now()) {
+                            if wait.is_zero() {
+                                break;
+                            }
+                            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                                return Err(OllamaError::Cancelled);
+                            }
+                            std::thread::sleep(wait.min(CANCEL_POLL));
+                        }
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_delay() {
+        let policy = RetryPolicy::default()
+            .jitter(false)
+            .backoff(Duration::from_millis(100), Duration::from_millis(350));
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+
+        let jittered = policy.jitter(true).delay(2);
+        assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_retry_classes() {
+        let policy = RetryPolicy::default();
+        let http = |status| OllamaError::from_status(status, String::new());
+        assert!(!policy.should_retry(&OllamaError::Timeout));
+        assert!(policy.should_retry(&http(503)));
+        assert!(policy.should_retry(&http(500)));
+        assert!(policy.should_retry(&http(429)));
+        assert!(!policy.should_retry(&http(404)));
+        assert!(!policy.should_retry(&http(400)));
+        assert!(!policy.should_retry(&OllamaError::InvalidResponse(String::new())));
+        assert!(!policy.should_retry(&OllamaError::InvalidRequest(String::new())));
+
+        let policy = policy.retry_on(RetryOn {
+            timeout: true,
+            ..RetryOn::default()
+        });
+        assert!(policy.should_retry(&OllamaError::Timeout));
+    }
+
+    #[test]
+    fn test_run_stops_after_max_attempts() {
+        let policy = RetryPolicy::default()
+            .max_attempts(3)
+            .backoff(Duration::ZERO, Duration::ZERO);
+        let mut attempts = 0;
+        let result: Result<(), _> = policy.run(None, || {
+            attempts += 1;
+            Err(OllamaError::from_status(503, String::new()))
+        });
+        assert!(matches!(result, Err(OllamaError::ModelLoading(_))));
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn test_cancel_ends_backoff() {
+        let policy = RetryPolicy::default()
+            .jitter(false)
+            .backoff(Duration::from_secs(10), Duration::from_secs(10));
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            canceller.cancel();
+        });
+
+        let started = Instant::now();
+        let result: Result<(), _> = policy.run(Some(&token), || {
+            Err(OllamaError::from_status(503, String::new()))
+        });
+        assert!(matches!(result, Err(OllamaError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
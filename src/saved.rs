@@ -0,0 +1,113 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::OllamaError;
+
+// The JSON files ChatSession and VectorStore save themselves to: the value's own fields next to
+// a `format` number, bumped when the layout changes in a way older versions can't read
+#[derive(Serialize, Deserialize)]
+struct Saved<T> {
+    format: u32,
+    #[serde(flatten)]
+    value: T,
+}
+
+// Writes through a temporary file next to `path`, so a crash never leaves half of it
+pub(crate) fn save_versioned<T: Serialize>(
+    path: &Path,
+    format: u32,
+    value: &T,
+    pretty: bool,
+) -> Result<(), OllamaError> {
+    let saved = Saved { format, value };
+    let json = if pretty {
+        serde_json::to_vec_pretty(&saved)?
+    } else {
+        serde_json::to_vec(&saved)?
+    };
+
+    let temporary = temporary_path(path)?;
+    let written = fs::write(&temporary, json).and_then(|_| fs::rename(&temporary, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    written.map_err(OllamaError::Io)
+}
+
+// `what` names the file in the error for a format newer than `format`, e.g. "chat session"
+pub(crate) fn load_versioned<T: DeserializeOwned>(
+    path: &Path,
+    format: u32,
+    what: &str,
+) -> Result<T, OllamaError> {
+    let json = fs::read(path).map_err(OllamaError::Io)?;
+    let saved: Saved<T> = serde_json::from_slice(&json)?;
+    if saved.format > format {
+        return Err(OllamaError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} format {} is newer than this version reads",
+                what, saved.format
+            ),
+        )));
+    }
+    Ok(saved.value)
+}
+
+// Unique per save, so concurrent saves and files that only differ in extension don't share one
+fn temporary_path(path: &Path) -> Result<PathBuf, OllamaError> {
+    static SAVES: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().ok_or_else(|| {
+        OllamaError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        ))
+    })?;
+    Ok(path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name.to_string_lossy(),
+        std::process::id(),
+        SAVES.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_save_and_load_versioned() {
+        let dir = std::env::temp_dir().join(format!("ollama-rs-saved-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // A path that already ends in .tmp, and two that only differ in extension
+        let paths = [dir.join("a.tmp"), dir.join("x.json"), dir.join("x.bin")];
+        for (i, path) in paths.iter().enumerate() {
+            save_versioned(path, 1, &json!({ "n": i }), false).unwrap();
+        }
+        for (i, path) in paths.iter().enumerate() {
+            let value: Value = load_versioned(path, 1, "test file").unwrap();
+            assert_eq!(value, json!({ "n": i }));
+        }
+        // Nothing left behind but the files themselves
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        save_versioned(&paths[0], 2, &json!({}), true).unwrap();
+        match load_versioned::<Value>(&paths[0], 1, "test file") {
+            Err(OllamaError::Io(e)) => {
+                assert_eq!(
+                    e.to_string(),
+                    "test file format 2 is newer than this version reads"
+                )
+            }
+            other => panic!("expected a format error, got {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
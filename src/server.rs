@@ -0,0 +1,221 @@
+use std::{path::PathBuf, process::Child, sync::Mutex, time::Duration};
+
+use crate::OllamaError;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::process::{Command, Stdio};
+
+// How `Ollama::start` treats the local `ollama serve` daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerOptions {
+    // Start `ollama serve` when nothing answers on the host, off when the daemon is managed elsewhere
+    pub spawn: bool,
+    // Stop the server we started once the last client using it is dropped
+    pub kill_on_drop: bool,
+    // How long a spawned server gets to start answering
+    pub ready_timeout: Duration,
+    // Looked up on PATH unless it is a path
+    pub program: PathBuf,
+    // Passed after `serve`
+    pub args: Vec<String>,
+    // Set for the server process only, e.g. OLLAMA_MODELS or OLLAMA_NUM_PARALLEL
+    pub envs: Vec<(String, String)>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> ServerOptions {
+        ServerOptions {
+            spawn: true,
+            kill_on_drop: false,
+            ready_timeout: Duration::from_secs(30),
+            program: PathBuf::from("ollama"),
+            args: Vec::new(),
+            envs: Vec::new(),
+        }
+    }
+}
+
+impl ServerOptions {
+    pub fn spawn(mut self, spawn: bool) -> ServerOptions {
+        self.spawn = spawn;
+        self
+    }
+
+    pub fn kill_on_drop(mut self, kill_on_drop: bool) -> ServerOptions {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    pub fn ready_timeout(mut self, timeout: Duration) -> ServerOptions {
+        self.ready_timeout = timeout;
+        self
+    }
+
+    pub fn program(mut self, program: impl Into<PathBuf>) -> ServerOptions {
+        self.program = program.into();
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> ServerOptions {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> ServerOptions {
+        self.envs.push((name.into(), value.into()));
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .arg("serve")
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        command
+    }
+}
+
+// The `ollama serve` process behind a client, or a record that it was already running
+#[derive(Debug)]
+pub struct ServerHandle {
+    child: Mutex<Option<Child>>,
+    kill_on_drop: bool,
+}
+
+impl ServerHandle {
+    // A server someone else started, shutting it down does nothing
+    pub fn external() -> ServerHandle {
+        ServerHandle {
+            child: Mutex::new(None),
+            kill_on_drop: false,
+        }
+    }
+
+    pub fn spawn(options: &ServerOptions) -> Result<ServerHandle, OllamaError> {
+        #[cfg(target_arch = "wasm32")]
+        return Err(OllamaError::Io(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("can't start {} here", options.program.display()),
+        )));
+        #[cfg(not(target_arch = "wasm32"))]
+        Self::spawn_command(options.command(), options.kill_on_drop)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spawn_command(
+        mut command: Command,
+        kill_on_drop: bool,
+    ) -> Result<ServerHandle, OllamaError> {
+        let child = command.spawn().map_err(OllamaError::Io)?;
+        Ok(ServerHandle {
+            child: Mutex::new(Some(child)),
+            kill_on_drop,
+        })
+    }
+
+    // Whether this handle started the server process and has not shut it down yet
+    pub fn spawned(&self) -> bool {
+        self.child.lock().unwrap().is_some()
+    }
+
+    pub fn id(&self) -> Option<u32> {
+        self.child.lock().unwrap().as_ref().map(Child::id)
+    }
+
+    // Stops the server if we started it, waiting for the process to exit
+    pub fn shutdown(&self) -> Result<(), OllamaError> {
+        let Some(mut child) = self.child.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        // The process may have exited on its own, e.g. because the port was taken
+        if child.try_wait().map_err(OllamaError::Io)?.is_none() {
+            child.kill().map_err(OllamaError::Io)?;
+        }
+        child.wait().map_err(OllamaError::Io)?;
+        Ok(())
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        if self.kill_on_drop {
+            let _ = self.shutdown();
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn sleeper() -> Command {
+        let mut command = Command::new("sleep");
+        command.arg("30");
+        command
+    }
+
+    fn running(pid: u32) -> bool {
+        std::path::Path::new(&format!("/proc/{}", pid)).exists()
+    }
+
+    #[test]
+    fn test_command() {
+        let options = ServerOptions::default()
+            .program("/opt/ollama/bin/ollama")
+            .arg("--verbose")
+            .env("OLLAMA_MODELS", "/data/models")
+            .env("OLLAMA_NUM_PARALLEL", "4");
+        let command = options.command();
+        assert_eq!(command.get_program(), "/opt/ollama/bin/ollama");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["serve", "--verbose"]
+        );
+        let envs: Vec<_> = command.get_envs().collect();
+        assert!(envs.contains(&("OLLAMA_NUM_PARALLEL".as_ref(), Some("4".as_ref()))));
+
+        let missing = ServerOptions::default().program("/nonexistent/ollama");
+        assert!(matches!(
+            ServerHandle::spawn(&missing),
+            Err(OllamaError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let handle = ServerHandle::spawn_command(sleeper(), false).unwrap();
+        assert!(handle.spawned());
+        let pid = handle.id().unwrap();
+        assert!(running(pid));
+
+        handle.shutdown().unwrap();
+        assert!(!handle.spawned());
+        assert!(!running(pid));
+        // A second shutdown is a no-op
+        handle.shutdown().unwrap();
+
+        assert!(!ServerHandle::external().spawned());
+        ServerHandle::external().shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_kill_on_drop() {
+        let handle = ServerHandle::spawn_command(sleeper(), true).unwrap();
+        let pid = handle.id().unwrap();
+        drop(handle);
+        assert!(!running(pid));
+
+        let handle = ServerHandle::spawn_command(sleeper(), false).unwrap();
+        let pid = handle.id().unwrap();
+        drop(handle);
+        assert!(running(pid));
+
+        Command::new("kill").arg(pid.to_string()).status().unwrap();
+    }
+}
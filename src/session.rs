@@ -0,0 +1,472 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ChatMessage, ChatRequest, Ollama, OllamaError, Options, Role, Tool, ToolCall,
+    saved::{load_versioned, save_versioned},
+};
+
+// The `format` of saved sessions, see save_versioned
+const FORMAT: u32 = 1;
+
+// What a session does when its history no longer fits the model's context window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextPolicy {
+    // Send everything and let the server cut the transcript
+    #[default]
+    Keep,
+    // Drop the oldest messages, system messages stay
+    Trim,
+    // Have the model summarize the older half of the conversation into a system message,
+    // trimming whatever still doesn't fit
+    Summarize,
+}
+
+// A conversation that keeps its own history: every send adds the message and the reply
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatSession {
+    pub model: String,
+    // Includes images and tool calls, so a saved session resumes exactly where it stopped
+    pub messages: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<Options>,
+    // Offered to the model with every send, see pending_tool_calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(default)]
+    pub context_policy: ContextPolicy,
+    // Read from /api/show on the first send when not set. Set it yourself when the server
+    // runs the model with a smaller num_ctx than the model supports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+}
+
+impl ChatSession {
+    pub fn new(model: impl Into<String>) -> ChatSession {
+        ChatSession {
+            model: model.into(),
+            messages: Vec::new(),
+            options: None,
+            tools: None,
+            context_policy: ContextPolicy::Keep,
+            context_length: None,
+        }
+    }
+
+    pub fn system(mut self, content: impl Into<String>) -> ChatSession {
+        self.messages.push(ChatMessage::system(content));
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> ChatSession {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<Tool>) -> ChatSession {
+        self.tools = Some(tools);
+        self
+    }
+
+    pub fn context_policy(mut self, policy: ContextPolicy) -> ChatSession {
+        self.context_policy = policy;
+        self
+    }
+
+    pub fn context_length(mut self, tokens: u64) -> ChatSession {
+        self.context_length = Some(tokens);
+        self
+    }
+
+    // A rough count of the tokens the history takes up, about four characters per token
+    pub fn estimated_tokens(&self) -> u64 {
+        self.messages.iter().map(estimate_tokens).sum()
+    }
+
+    // The history is left as it was when the call fails
+    pub fn send(
+        &mut self,
+        ollama: &Ollama,
+        message: ChatMessage,
+    ) -> Result<&ChatMessage, OllamaError> {
+        self.send_all(ollama, vec![message])
+    }
+
+    // The calls the model asked for in its last reply, still waiting for their results
+    pub fn pending_tool_calls(&self) -> &[ToolCall] {
+        match self.messages.last() {
+            Some(message) if message.role == Role::Assistant => {
+                message.tool_calls.as_deref().unwrap_or_default()
+            }
+            _ => &[],
+        }
+    }
+
+    // Answers the pending tool calls, see ChatMessage::tool_result, all in one request so the
+    // model sees every result before it replies
+    pub fn send_tool_results(
+        &mut self,
+        ollama: &Ollama,
+        results: impl IntoIterator<Item = ChatMessage>,
+    ) -> Result<&ChatMessage, OllamaError> {
+        self.send_all(ollama, results.into_iter().collect())
+    }
+
+    fn send_all(
+        &mut self,
+        ollama: &Ollama,
+        messages: Vec<ChatMessage>,
+    ) -> Result<&ChatMessage, OllamaError> {
+        // Trimming or summarizing can change older messages too, so a failure restores all of it
+        let before = self.messages.clone();
+        self.messages.extend(messages);
+        let result = self.fit_context(ollama).and_then(|()| {
+            let mut request = ChatRequest::new(self.model.clone(), self.messages.clone());
+            request.options = self.options.clone();
+            request.tools = self.tools.clone();
+            ollama.chat(&request)
+        });
+
+        match result {
+            Ok(response) => {
+                self.messages.push(response.message);
+                Ok(self.messages.last().unwrap())
+            }
+            Err(error) => {
+                self.messages = before;
+                Err(error)
+            }
+        }
+    }
+
+    // Makes room according to the policy, keeping a quarter of the window for the reply
+    fn fit_context(&mut self, ollama: &Ollama) -> Result<(), OllamaError> {
+        if self.context_policy == ContextPolicy::Keep {
+            return Ok(());
+        }
+        if self.context_length.is_none() {
+            self.context_length = ollama.show_model(self.model.clone())?.context_length;
+        }
+        let Some(context_length) = self.context_length else {
+            return Ok(());
+        };
+        let budget = context_length * 3 / 4;
+        if self.estimated_tokens() <= budget {
+            return Ok(());
+        }
+
+        if self.context_policy == ContextPolicy::Summarize {
+            self.summarize(ollama)?;
+        }
+        while self.estimated_tokens() > budget && self.drop_oldest() {}
+        Ok(())
+    }
+
+    // Removes the oldest message that isn't a system message or the latest one. Tool results
+    // left without the call that asked for them go too.
+    fn drop_oldest(&mut self) -> bool {
+        let last = self.messages.len().saturating_sub(1);
+        let Some(oldest) = self.messages[..last]
+            .iter()
+            .position(|m| m.role != Role::System)
+        else {
+            return false;
+        };
+        self.messages.remove(oldest);
+        while oldest < self.messages.len() - 1 && self.messages[oldest].role == Role::Tool {
+            self.messages.remove(oldest);
+        }
+        true
+    }
+
+    fn summarize(&mut self, ollama: &Ollama) -> Result<(), OllamaError> {
+        let older: Vec<usize> = (0..self.messages.len() - 1)
+            .filter(|&i| self.messages[i].role != Role::System)
+            .collect();
+        let older = &older[..older.len() / 2];
+        if older.is_empty() {
+            return Ok(());
+        }
+
+        let transcript: String = older
+            .iter()
+            .map(|&i| {
+                let message = &self.messages[i];
+                format!("{:?}: {}\n", message.role, message.content)
+            })
+            .collect();
+        let request = ChatRequest::new(
+            self.model.clone(),
+            vec![
+                ChatMessage::system(
+                    "Summarize this conversation in a few sentences. Keep names, facts and \
+                     decisions, leave out pleasantries.",
+                ),
+                ChatMessage::user(transcript),
+            ],
+        );
+        let summary = ollama.chat(&request)?.message.content;
+
+        let first = older[0];
+        for &i in older.iter().rev() {
+            self.messages.remove(i);
+        }
+        self.messages.insert(
+            first,
+            ChatMessage::system(format!(
+                "Summary of the earlier conversation: {}",
+                summary.trim()
+            )),
+        );
+        Ok(())
+    }
+
+    // Writes the session as JSON, through a temporary file so a crash never leaves half of it
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OllamaError> {
+        save_versioned(path.as_ref(), FORMAT, self, true)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<ChatSession, OllamaError> {
+        load_versioned(path.as_ref(), FORMAT, "chat session")
+    }
+}
+
+// Four characters per token is close for English text with most tokenizers, plus a few
+// tokens for the role markers around each message
+fn estimate_tokens(message: &ChatMessage) -> u64 {
+    message.content.chars().count().div_ceil(4) as u64 + 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockOllama, Role, ToolCall};
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn test_send_keeps_history() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({
+                    "model": "llama3",
+                    "message": { "role": "assistant", "content": "Hi Bob!" },
+                    "done": true
+                }),
+            )
+            .error("POST", "/api/chat", 500, "model crashed");
+        let ollama = mock.client();
+
+        let mut session = ChatSession::new("llama3").system("Be brief.");
+        let reply = session.send(&ollama, ChatMessage::user("I'm Bob")).unwrap();
+        assert_eq!(reply.content, "Hi Bob!");
+        assert_eq!(session.messages.len(), 3);
+
+        assert!(session.send(&ollama, ChatMessage::user("Again")).is_err());
+        assert_eq!(session.messages.len(), 3);
+    }
+
+    #[test]
+    fn test_tool_results() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                json!({
+                    "model": "qwen3",
+                    "message": { "role": "assistant", "content": "", "tool_calls": [
+                        { "id": "a", "function": { "name": "get_weather", "arguments": { "city": "Paris" } } },
+                        { "id": "b", "function": { "name": "get_weather", "arguments": { "city": "Rome" } } }
+                    ] },
+                    "done": true
+                }),
+            )
+            .respond("POST", "/api/chat", 200, chat_reply("Both sunny."));
+        let ollama = mock.client();
+        let tool = Tool::function(
+            "get_weather",
+            "Get the weather for a city",
+            json!({ "type": "object", "properties": { "city": { "type": "string" } } }),
+        );
+
+        let mut session = ChatSession::new("qwen3").tools(vec![tool]);
+        session
+            .send(&ollama, ChatMessage::user("Paris or Rome?"))
+            .unwrap();
+        let results: Vec<ChatMessage> = session
+            .pending_tool_calls()
+            .iter()
+            .map(|call| ChatMessage::tool_result(call, "sunny"))
+            .collect();
+        assert_eq!(results.len(), 2);
+
+        let reply = session.send_tool_results(&ollama, results).unwrap();
+        assert_eq!(reply.content, "Both sunny.");
+        assert!(session.pending_tool_calls().is_empty());
+
+        let sent = sent_messages(&mock, 2);
+        let roles: Vec<Role> = sent.iter().map(|m| m.role).collect();
+        assert_eq!(roles, [Role::User, Role::Assistant, Role::Tool, Role::Tool]);
+        assert_eq!(sent[3].tool_call_id.as_deref(), Some("b"));
+        assert_eq!(sent[3].tool_name.as_deref(), Some("get_weather"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("ollama-rs-session-{}.json", std::process::id()));
+
+        let call: ToolCall = serde_json::from_value(json!({
+            "function": { "name": "get_weather", "arguments": { "city": "Paris" } }
+        }))
+        .unwrap();
+        let mut asking = ChatMessage::assistant("");
+        asking.tool_calls = Some(vec![call]);
+
+        let mut session = ChatSession::new("llava").system("You describe pictures.");
+        session.messages.extend([
+            ChatMessage::user("What's this?").images(vec!["aGVsbG8=".to_string()]),
+            asking,
+            ChatMessage::tool("22 degrees and sunny"),
+        ]);
+        session.save(&path).unwrap();
+
+        let loaded = ChatSession::load(&path).unwrap();
+        assert_eq!(loaded, session);
+        assert_eq!(loaded.messages[3].role, Role::Tool);
+
+        let saved: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved["format"], 1);
+
+        fs::write(
+            &path,
+            json!({ "format": 2, "model": "x", "messages": [] }).to_string(),
+        )
+        .unwrap();
+        assert!(matches!(ChatSession::load(&path), Err(OllamaError::Io(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn chat_reply(content: &str) -> serde_json::Value {
+        json!({
+            "model": "llama3",
+            "message": { "role": "assistant", "content": content },
+            "done": true
+        })
+    }
+
+    fn sent_messages(mock: &MockOllama, index: usize) -> Vec<ChatMessage> {
+        serde_json::from_value(mock.request_json(index)["messages"].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_trim_policy() {
+        let mock = MockOllama::new().respond("POST", "/api/chat", 200, chat_reply("Sure."));
+        let long = "x".repeat(40);
+
+        let mut session = ChatSession::new("llama3")
+            .system("Be brief.")
+            .context_policy(ContextPolicy::Trim)
+            .context_length(40);
+        session
+            .messages
+            .extend([ChatMessage::user(&long), ChatMessage::assistant(&long)]);
+        session
+            .send(&mock.client(), ChatMessage::user(&long))
+            .unwrap();
+
+        // Only the system message and the newest one fit, and no /api/show was needed
+        let sent = sent_messages(&mock, 1);
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].role, Role::System);
+        assert_eq!(session.messages.len(), 3);
+        assert_eq!(session.messages[2].content, "Sure.");
+    }
+
+    #[test]
+    fn test_failed_send_restores_fitted_history() {
+        let long = "x".repeat(40);
+        let history = || {
+            let mut session = ChatSession::new("llama3")
+                .system("Be brief.")
+                .context_length(64);
+            for _ in 0..2 {
+                session
+                    .messages
+                    .extend([ChatMessage::user(&long), ChatMessage::assistant(&long)]);
+            }
+            session
+        };
+
+        let mock = MockOllama::new().error("POST", "/api/chat", 500, "model crashed");
+        let mut session = history().context_policy(ContextPolicy::Trim);
+        assert!(
+            session
+                .send(&mock.client(), ChatMessage::user(&long))
+                .is_err()
+        );
+        // The request went out trimmed, but the history is back as it was
+        assert_eq!(sent_messages(&mock, 1).len(), 3);
+        assert_eq!(session, history().context_policy(ContextPolicy::Trim));
+
+        let mock = MockOllama::new()
+            .respond("POST", "/api/chat", 200, chat_reply("Bob likes tea."))
+            .error("POST", "/api/chat", 500, "model crashed");
+        let mut session = history().context_policy(ContextPolicy::Summarize);
+        assert!(
+            session
+                .send(&mock.client(), ChatMessage::user(&long))
+                .is_err()
+        );
+        assert_eq!(mock.requests().len(), 1 + 2);
+        assert_eq!(session, history().context_policy(ContextPolicy::Summarize));
+    }
+
+    #[test]
+    fn test_summarize_policy() {
+        let mock = MockOllama::new()
+            .respond(
+                "POST",
+                "/api/show",
+                200,
+                json!({ "model_info": { "llama.context_length": 64 } }),
+            )
+            .respond("POST", "/api/chat", 200, chat_reply("Bob likes tea."))
+            .respond("POST", "/api/chat", 200, chat_reply("Green tea."));
+        let long = "y".repeat(40);
+
+        let mut session = ChatSession::new("llama3")
+            .system("Be brief.")
+            .context_policy(ContextPolicy::Summarize);
+        for _ in 0..2 {
+            session
+                .messages
+                .extend([ChatMessage::user(&long), ChatMessage::assistant(&long)]);
+        }
+        let reply = session.send(&mock.client(), ChatMessage::user("Which tea?"));
+        assert_eq!(reply.unwrap().content, "Green tea.");
+        assert_eq!(session.context_length, Some(64));
+
+        let paths: Vec<String> = mock.requests().iter().map(|r| r.path.clone()).collect();
+        assert_eq!(
+            paths,
+            ["/api/version", "/api/show", "/api/chat", "/api/chat"]
+        );
+
+        let summarized = &sent_messages(&mock, 2)[1];
+        assert_eq!(summarized.content.matches(&long).count(), 2);
+
+        let sent = sent_messages(&mock, 3);
+        assert_eq!(
+            sent[1].content,
+            "Summary of the earlier conversation: Bob likes tea."
+        );
+        assert_eq!(sent.last().unwrap().content, "Which tea?");
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+// Timings and token counts the server reports with the final (`done`) response of a generate
+// or chat call. Everything is zero on the chunks before it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct GenerationStats {
+    // Tokens in the prompt that had to be evaluated, cached ones don't count
+    #[serde(default)]
+    pub prompt_eval_count: u64,
+    // Tokens generated
+    #[serde(default)]
+    pub eval_count: u64,
+    #[serde(default, deserialize_with = "nanoseconds")]
+    pub total_duration: Duration,
+    // Spent loading the model, zero when it was already in memory
+    #[serde(default, deserialize_with = "nanoseconds")]
+    pub load_duration: Duration,
+    #[serde(default, deserialize_with = "nanoseconds")]
+    pub prompt_eval_duration: Duration,
+    #[serde(default, deserialize_with = "nanoseconds")]
+    pub eval_duration: Duration,
+}
+
+impl GenerationStats {
+    // Generation speed, None when the server reported no eval time
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        rate(self.eval_count, self.eval_duration)
+    }
+
+    pub fn prompt_tokens_per_second(&self) -> Option<f64> {
+        rate(self.prompt_eval_count, self.prompt_eval_duration)
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_eval_count + self.eval_count
+    }
+}
+
+fn rate(tokens: u64, duration: Duration) -> Option<f64> {
+    (!duration.is_zero()).then(|| tokens as f64 / duration.as_secs_f64())
+}
+
+// The server sends durations as integer nanoseconds
+fn nanoseconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    Ok(Duration::from_nanos(u64::deserialize(deserializer)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_per_second() {
+        let stats: GenerationStats = serde_json::from_str(
+            r#"{
+                "total_duration": 5043500667,
+                "load_duration": 5025959,
+                "prompt_eval_count": 26,
+                "prompt_eval_duration": 325953000,
+                "eval_count": 290,
+                "eval_duration": 4709213000
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(stats.total_duration, Duration::from_nanos(5043500667));
+        assert_eq!(stats.total_tokens(), 316);
+        assert!((stats.tokens_per_second().unwrap() - 61.58).abs() < 0.01);
+        assert!((stats.prompt_tokens_per_second().unwrap() - 79.77).abs() < 0.01);
+
+        assert_eq!(GenerationStats::default().tokens_per_second(), None);
+    }
+}
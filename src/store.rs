@@ -0,0 +1,166 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    OllamaError, cosine_similarity,
+    saved::{load_versioned, save_versioned},
+};
+
+// The `format` of saved stores, see save_versioned
+const FORMAT: u32 = 1;
+
+// A piece of text with its embedding, the unit a VectorStore holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    pub text: String,
+    pub embedding: Vec<f32>,
+    // e.g. the source file or a page number, for filtering searches
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Value>,
+}
+
+impl Document {
+    pub fn new(id: impl Into<String>, text: impl Into<String>, embedding: Vec<f32>) -> Document {
+        Document {
+            id: id.into(),
+            text: text.into(),
+            embedding,
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Document {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+// Documents kept in memory and searched by brute force, which is quick enough up to some
+// tens of thousands of entries. All embeddings should come from the same model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VectorStore {
+    documents: Vec<Document>,
+}
+
+impl VectorStore {
+    pub fn new() -> VectorStore {
+        VectorStore::default()
+    }
+
+    // Replaces the document with the same id, if any
+    pub fn insert(&mut self, document: Document) {
+        match self.documents.iter_mut().find(|d| d.id == document.id) {
+            Some(existing) => *existing = document,
+            None => self.documents.push(document),
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Document> {
+        let index = self.documents.iter().position(|d| d.id == id)?;
+        Some(self.documents.remove(index))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Document> {
+        self.documents.iter().find(|d| d.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    // The `k` documents most similar to `query`, best first with their cosine similarity
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(&Document, f32)> {
+        self.search_filtered(query, k, |_| true)
+    }
+
+    // Like search, among the documents `filter` accepts, e.g.
+    // `|d| d.metadata.get("lang") == Some(&"en".into())`
+    pub fn search_filtered(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: impl Fn(&Document) -> bool,
+    ) -> Vec<(&Document, f32)> {
+        let mut scored: Vec<(&Document, f32)> = self
+            .documents
+            .iter()
+            // Embeddings of another size came from another model and can't be compared
+            .filter(|d| d.embedding.len() == query.len() && filter(d))
+            .map(|d| (d, cosine_similarity(query, &d.embedding)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(k);
+        scored
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OllamaError> {
+        save_versioned(path.as_ref(), FORMAT, self, false)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<VectorStore, OllamaError> {
+        load_versioned(path.as_ref(), FORMAT, "vector store")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn store() -> VectorStore {
+        let mut store = VectorStore::new();
+        store.insert(
+            Document::new("cats", "Cats purr.", vec![1.0, 0.0, 0.0]).metadata("lang", "en"),
+        );
+        store.insert(
+            Document::new("chats", "Les chats ronronnent.", vec![0.9, 0.1, 0.0])
+                .metadata("lang", "fr"),
+        );
+        store.insert(
+            Document::new("rust", "Rust has no GC.", vec![0.0, 0.0, 1.0]).metadata("lang", "en"),
+        );
+        store
+    }
+
+    #[test]
+    fn test_search() {
+        let mut store = store();
+        let ids = |results: Vec<(&Document, f32)>| -> Vec<String> {
+            results.into_iter().map(|(d, _)| d.id.clone()).collect()
+        };
+
+        assert_eq!(ids(store.search(&[1.0, 0.0, 0.0], 2)), ["cats", "chats"]);
+        let english = store.search_filtered(&[1.0, 0.0, 0.0], 2, |d| {
+            d.metadata.get("lang") == Some(&Value::from("en"))
+        });
+        assert_eq!(ids(english), ["cats", "rust"]);
+        assert!(store.search(&[1.0, 0.0], 2).is_empty());
+
+        store.insert(Document::new("cats", "Cats sleep.", vec![0.0, 0.0, 1.0]));
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get("cats").unwrap().text, "Cats sleep.");
+        assert_eq!(store.remove("rust").unwrap().text, "Rust has no GC.");
+        assert_eq!(ids(store.search(&[0.0, 0.0, 1.0], 1)), ["cats"]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("ollama-rs-store-{}.json", std::process::id()));
+        let store = store();
+        store.save(&path).unwrap();
+        assert_eq!(VectorStore::load(&path).unwrap(), store);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,395 @@
+use std::{
+    any::Any,
+    collections::VecDeque,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        Arc, Condvar, Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use futures_core::Stream;
+
+use crate::{CancellationToken, Ollama, OllamaError};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// The threads the async client's calls run on. Threads are started as calls need them, up to
+// `max`, and stop after a while without work. Calls beyond `max` wait for a free thread.
+pub(crate) struct Workers {
+    max: AtomicUsize,
+    state: Mutex<WorkerState>,
+    ready: Condvar,
+}
+
+#[derive(Default)]
+struct WorkerState {
+    queue: VecDeque<Job>,
+    threads: usize,
+    idle: usize,
+}
+
+impl Workers {
+    const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+    pub fn new(max: usize) -> Workers {
+        Workers {
+            max: AtomicUsize::new(max.max(1)),
+            state: Mutex::default(),
+            ready: Condvar::new(),
+        }
+    }
+
+    // Shared by every AsyncOllama
+    pub fn global() -> &'static Workers {
+        static WORKERS: OnceLock<Workers> = OnceLock::new();
+        WORKERS.get_or_init(|| Workers::new(64))
+    }
+
+    pub fn set_max(&self, max: usize) {
+        self.max.store(max.max(1), Ordering::Relaxed);
+    }
+
+    pub fn run(&'static self, job: impl FnOnce() + Send + 'static) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.push_back(Box::new(job));
+        // An idle thread only leaves `idle` once it wakes, so count the jobs already handed to
+        // idle threads rather than whether one is idle
+        if state.queue.len() > state.idle && state.threads < self.max.load(Ordering::Relaxed) {
+            state.threads += 1;
+            std::thread::spawn(move || self.work());
+        } else {
+            self.ready.notify_one();
+        }
+    }
+
+    fn work(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+                // A panicking job must not take the thread, and its slot, with it
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                state = self.state.lock().unwrap();
+                if state.threads > self.max.load(Ordering::Relaxed) {
+                    state.threads -= 1;
+                    return;
+                }
+                continue;
+            }
+            state.idle += 1;
+            let (next, timeout) = self.ready.wait_timeout(state, Self::IDLE_TIMEOUT).unwrap();
+            state = next;
+            state.idle -= 1;
+            if timeout.timed_out() && state.queue.is_empty() {
+                state.threads -= 1;
+                return;
+            }
+        }
+    }
+}
+
+// Runs a blocking streamed call on a worker thread and hands the items to whichever executor
+// polls it, so no particular async runtime is needed
+pub(crate) struct ThreadStream<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    // Closes the connection right away when the stream is dropped
+    cancel: Option<CancellationToken>,
+}
+
+struct Shared<T> {
+    items: VecDeque<Result<T, OllamaError>>,
+    done: bool,
+    // Set when the stream is dropped so the thread stops reading and closes the connection
+    dropped: bool,
+    waker: Option<Waker>,
+}
+
+impl<T: Send + 'static> ThreadStream<T> {
+    // Dropping the stream aborts the call. When the client has its own CancellationToken the
+    // call only stops once the next chunk arrives, cancelling the token is immediate.
+    pub fn call<I>(
+        ollama: &Ollama,
+        call: impl FnOnce(Ollama) -> Result<I, OllamaError> + Send + 'static,
+    ) -> ThreadStream<T>
+    where
+        I: Iterator<Item = Result<T, OllamaError>>,
+    {
+        if ollama.cancel.is_some() {
+            let ollama = ollama.clone();
+            return Self::spawn(None, move || call(ollama));
+        }
+
+        let token = CancellationToken::new();
+        let ollama = ollama.with_cancellation(token.clone());
+        Self::spawn(Some(token), move || call(ollama))
+    }
+
+    fn spawn<I>(
+        cancel: Option<CancellationToken>,
+        call: impl FnOnce() -> Result<I, OllamaError> + Send + 'static,
+    ) -> ThreadStream<T>
+    where
+        I: Iterator<Item = Result<T, OllamaError>>,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            items: VecDeque::new(),
+            done: false,
+            dropped: false,
+            waker: None,
+        }));
+
+        let producer = shared.clone();
+        Workers::global().run(move || {
+            // Dropped while it waited for a thread
+            if producer.lock().unwrap().dropped {
+                return;
+            }
+            let push = |item: Option<Result<T, OllamaError>>| {
+                let mut shared = producer.lock().unwrap();
+                match item {
+                    Some(item) => shared.items.push_back(item),
+                    None => shared.done = true,
+                }
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake();
+                }
+                !shared.dropped
+            };
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                match call() {
+                    Ok(items) => {
+                        for item in items {
+                            if !push(Some(item)) {
+                                return false;
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        push(Some(Err(error)));
+                    }
+                }
+                true
+            }));
+            // A panic ends the stream with an error instead of leaving it pending forever
+            match result {
+                Ok(false) => {}
+                Ok(true) => {
+                    push(None);
+                }
+                Err(panic) => {
+                    push(Some(Err(OllamaError::Panicked(panic_message(panic)))));
+                    push(None);
+                }
+            }
+        });
+
+        ThreadStream { shared, cancel }
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "no message".to_string(),
+        },
+    }
+}
+
+impl<T> Stream for ThreadStream<T> {
+    type Item = Result<T, OllamaError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().unwrap();
+        if let Some(item) = shared.items.pop_front() {
+            Poll::Ready(Some(item))
+        } else if shared.done {
+            Poll::Ready(None)
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for ThreadStream<T> {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().dropped = true;
+        if let Some(cancel) = &self.cancel {
+            cancel.cancel();
+        }
+    }
+}
+
+// A blocking call run on a worker thread, as a stream with a single item
+pub(crate) struct ThreadFuture<T>(ThreadStream<T>);
+
+impl<T: Send + 'static> ThreadFuture<T> {
+    // Dropping the future aborts the call, like dropping a ThreadStream
+    pub fn call(
+        ollama: &Ollama,
+        call: impl FnOnce(Ollama) -> Result<T, OllamaError> + Send + 'static,
+    ) -> ThreadFuture<T> {
+        ThreadFuture(ThreadStream::call(ollama, move |ollama| {
+            call(ollama).map(|value| std::iter::once(Ok(value)))
+        }))
+    }
+}
+
+impl<T> Future for ThreadFuture<T> {
+    type Output = Result<T, OllamaError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.0).poll_next(cx) {
+            Poll::Ready(Some(result)) => Poll::Ready(result),
+            Poll::Ready(None) => panic!("ThreadFuture polled after completion"),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        sync::mpsc,
+        task::Wake,
+        thread::{self, Thread},
+    };
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    // Drains a stream on the current thread, parking while it is pending
+    pub(crate) fn collect<S: Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut items = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(item)) => items.push(item),
+                Poll::Ready(None) => return items,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    // Runs a future to completion on the current thread
+    pub(crate) fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match Pin::new(&mut future).poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_items_arrive_in_order() {
+        let (send, receive) = mpsc::channel::<u32>();
+        let stream = ThreadStream::spawn(None, move || Ok(receive.into_iter().map(Ok)));
+
+        thread::spawn(move || {
+            for i in 0..5 {
+                thread::sleep(std::time::Duration::from_millis(5));
+                send.send(i).unwrap();
+            }
+        });
+
+        let items: Vec<u32> = collect(stream).into_iter().map(|i| i.unwrap()).collect();
+        assert_eq!(items, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_workers_are_bounded() {
+        let workers: &'static Workers = Box::leak(Box::new(Workers::new(2)));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (done, finished) = mpsc::channel();
+        for _ in 0..6 {
+            let (running, peak, done) = (running.clone(), peak.clone(), done.clone());
+            workers.run(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+                done.send(()).unwrap();
+            });
+        }
+        for _ in 0..6 {
+            finished.recv().unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(workers.state.lock().unwrap().threads, 2);
+    }
+
+    #[test]
+    fn test_idle_worker_does_not_delay_second_job() {
+        // Whether the idle thread wakes between the two calls is down to timing, so try a few
+        for _ in 0..50 {
+            let workers: &'static Workers = Box::leak(Box::new(Workers::new(2)));
+            let (done, finished) = mpsc::channel();
+            workers.run(move || done.send(()).unwrap());
+            finished.recv().unwrap();
+            while workers.state.lock().unwrap().idle == 0 {
+                thread::yield_now();
+            }
+
+            // Both jobs are queued while one thread is idle, the second must not wait on the
+            // first
+            let (started, starts) = mpsc::channel();
+            let (release, released) = mpsc::channel::<()>();
+            let first = started.clone();
+            workers.run(move || {
+                first.send(()).unwrap();
+                let _ = released.recv();
+            });
+            workers.run(move || started.send(()).unwrap());
+            let overlapped = (0..2).all(|_| starts.recv_timeout(Duration::from_secs(1)).is_ok());
+            release.send(()).unwrap();
+            assert!(overlapped);
+        }
+    }
+
+    #[test]
+    fn test_panicking_call_ends_the_stream() {
+        let stream = ThreadStream::<u32>::spawn(None, || -> Result<std::iter::Empty<_>, _> {
+            panic!("interceptor failed")
+        });
+        let items = collect(stream);
+        assert_eq!(items.len(), 1);
+        assert!(matches!(&items[0], Err(OllamaError::Panicked(m)) if m == "interceptor failed"));
+
+        // The worker survives the panic and keeps its slot
+        let workers: &'static Workers = Box::leak(Box::new(Workers::new(1)));
+        workers.run(|| panic!("job failed"));
+        let (done, finished) = mpsc::channel();
+        workers.run(move || done.send(()).unwrap());
+        finished.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(workers.state.lock().unwrap().threads, 1);
+    }
+
+    #[test]
+    fn test_call_error_is_yielded() {
+        let stream = ThreadStream::<u32>::spawn(None, || {
+            Err::<std::iter::Empty<_>, _>(OllamaError::Timeout)
+        });
+        let items = collect(stream);
+        assert_eq!(items.len(), 1);
+        assert!(matches!(items[0], Err(OllamaError::Timeout)));
+    }
+}
@@ -0,0 +1,276 @@
+use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash, str::FromStr};
+
+use crate::{Ollama, OllamaError};
+
+// A prompt with `{name}` placeholders, checked when it is parsed and filled in by name.
+// `{{` and `}}` stand for literal braces, like in format!.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable(String),
+}
+
+impl PromptTemplate {
+    pub fn parse(template: &str) -> Result<PromptTemplate, OllamaError> {
+        let error = |message: String| OllamaError::Template(message);
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((at, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|(_, c)| *c == '{').is_some() => text.push('{'),
+                '}' if chars.next_if(|(_, c)| *c == '}').is_some() => text.push('}'),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) if c.is_alphanumeric() || c == '_' => name.push(c),
+                            Some((_, c)) => {
+                                return Err(error(format!(
+                                    "invalid character {:?} in placeholder at {}",
+                                    c, at
+                                )));
+                            }
+                            None => return Err(error(format!("unclosed placeholder at {}", at))),
+                        }
+                    }
+                    if name.is_empty() {
+                        return Err(error(format!("empty placeholder at {}", at)));
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Variable(name));
+                }
+                '}' => return Err(error(format!("unmatched '}}' at {}", at))),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(PromptTemplate { parts })
+    }
+
+    // Placeholder names in order of first appearance
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for part in &self.parts {
+            if let Part::Variable(name) = part
+                && !names.contains(&name.as_str())
+            {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    // The placeholders `vars` has no value for
+    pub fn missing<K, V>(&self, vars: &HashMap<K, V>) -> Vec<&str>
+    where
+        K: Borrow<str> + Hash + Eq,
+    {
+        self.variables()
+            .into_iter()
+            .filter(|name| !vars.contains_key(*name))
+            .collect()
+    }
+
+    // Fails listing every missing variable rather than sending a prompt with holes in it.
+    // Values are inserted as-is, braces in them are not placeholders.
+    pub fn render<K, V>(&self, vars: &HashMap<K, V>) -> Result<String, OllamaError>
+    where
+        K: Borrow<str> + Hash + Eq,
+        V: AsRef<str>,
+    {
+        let missing = self.missing(vars);
+        if !missing.is_empty() {
+            return Err(OllamaError::Template(format!(
+                "missing variables: {}",
+                missing.join(", ")
+            )));
+        }
+
+        Ok(self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.as_str(),
+                Part::Variable(name) => vars[name.as_str()].as_ref(),
+            })
+            .collect())
+    }
+
+    // Fills in the variables `vars` has values for and keeps the other placeholders
+    pub fn fill<K, V>(&self, vars: &HashMap<K, V>) -> PromptTemplate
+    where
+        K: Borrow<str> + Hash + Eq,
+        V: AsRef<str>,
+    {
+        let parts = self.parts.iter().map(|part| match part {
+            Part::Variable(name) if vars.contains_key(name.as_str()) => {
+                Part::Text(vars[name.as_str()].as_ref().to_string())
+            }
+            part => part.clone(),
+        });
+        PromptTemplate::from_parts(parts)
+    }
+
+    // Replaces the `name` placeholder with another template, whose placeholders join this one's
+    pub fn embed(&self, name: &str, template: &PromptTemplate) -> PromptTemplate {
+        let parts = self.parts.iter().flat_map(|part| match part {
+            Part::Variable(n) if n == name => template.parts.clone(),
+            part => vec![part.clone()],
+        });
+        PromptTemplate::from_parts(parts)
+    }
+
+    // This template followed by `next`
+    pub fn then(&self, next: &PromptTemplate) -> PromptTemplate {
+        PromptTemplate::from_parts(self.parts.iter().chain(&next.parts).cloned())
+    }
+
+    // Merges neighbouring text so equal templates compare equal however they were built
+    fn from_parts(parts: impl IntoIterator<Item = Part>) -> PromptTemplate {
+        let mut merged: Vec<Part> = Vec::new();
+        for part in parts {
+            match (merged.last_mut(), part) {
+                (_, Part::Text(text)) if text.is_empty() => {}
+                (Some(Part::Text(last)), Part::Text(text)) => last.push_str(&text),
+                (_, part) => merged.push(part),
+            }
+        }
+        PromptTemplate { parts: merged }
+    }
+}
+
+impl FromStr for PromptTemplate {
+    type Err = OllamaError;
+
+    fn from_str(template: &str) -> Result<PromptTemplate, OllamaError> {
+        PromptTemplate::parse(template)
+    }
+}
+
+// Writes the template back in the syntax `parse` reads
+impl fmt::Display for PromptTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => write!(f, "{}", text.replace('{', "{{").replace('}', "}}"))?,
+                Part::Variable(name) => write!(f, "{{{}}}", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Ollama {
+    // Renders the template and sends it like `prompt`, nothing is sent when a variable is missing
+    pub fn prompt_template<K, V>(
+        &self,
+        model: String,
+        template: &PromptTemplate,
+        vars: &HashMap<K, V>,
+    ) -> Result<String, OllamaError>
+    where
+        K: Borrow<str> + Hash + Eq,
+        V: AsRef<str>,
+    {
+        self.prompt_with(model, template.render(vars)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockOllama;
+
+    #[test]
+    fn test_render() {
+        let template =
+            PromptTemplate::parse("Translate {text} to {language}. Keep {{braces}}: {text}")
+                .unwrap();
+        assert_eq!(template.variables(), ["text", "language"]);
+
+        let vars = HashMap::from([("text", "hello {name}"), ("language", "French")]);
+        assert_eq!(
+            template.render(&vars).unwrap(),
+            "Translate hello {name} to French. Keep {braces}: hello {name}"
+        );
+
+        let error = template
+            .render(&HashMap::from([("text", "hello")]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "prompt template error: missing variables: language"
+        );
+        assert_eq!(
+            template.to_string().parse::<PromptTemplate>().unwrap(),
+            template
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in ["{unclosed", "{}", "{with space}", "stray }", "{a-b}"] {
+            assert!(
+                matches!(PromptTemplate::parse(bad), Err(OllamaError::Template(_))),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_composition() {
+        let system = PromptTemplate::parse("You are a {role}.\n{task}").unwrap();
+        let task = PromptTemplate::parse("Summarize: {document}").unwrap();
+        let footer = PromptTemplate::parse("\nAnswer in {language}.").unwrap();
+
+        let template = system.embed("task", &task).then(&footer);
+        assert_eq!(template.variables(), ["role", "document", "language"]);
+
+        let english = template.fill(&HashMap::from([("language", "English")]));
+        assert_eq!(english.variables(), ["role", "document"]);
+        assert_eq!(
+            english
+                .render(&HashMap::from([("role", "librarian"), ("document", "...")]))
+                .unwrap(),
+            "You are a librarian.\nSummarize: ...\nAnswer in English."
+        );
+    }
+
+    #[test]
+    fn test_prompt_template() {
+        let mock = MockOllama::new().respond(
+            "POST",
+            "/api/generate",
+            200,
+            serde_json::json!({ "model": "llama3", "response": "Bonjour", "done": true }),
+        );
+        let ollama = mock.client();
+        let template = PromptTemplate::parse("Say {word} in {language}").unwrap();
+
+        let vars = HashMap::from([("word".to_string(), "hello".to_string())]);
+        let error = ollama
+            .prompt_template("llama3".to_string(), &template, &vars)
+            .unwrap_err();
+        assert!(matches!(error, OllamaError::Template(_)));
+        assert_eq!(mock.requests().len(), 1);
+
+        let vars = HashMap::from([("word", "hello"), ("language", "French")]);
+        let reply = ollama
+            .prompt_template("llama3".to_string(), &template, &vars)
+            .unwrap();
+        assert_eq!(reply, "Bonjour");
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+// Socket timeouts, None blocks indefinitely
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    #[serde(deserialize_with = "crate::config::optional_duration")]
+    pub connect: Option<Duration>,
+    #[serde(deserialize_with = "crate::config::optional_duration")]
+    pub read: Option<Duration>,
+    #[serde(deserialize_with = "crate::config::optional_duration")]
+    pub write: Option<Duration>,
+}
+
+impl Timeouts {
+    pub fn connect(mut self, timeout: Duration) -> Timeouts {
+        self.connect = Some(timeout);
+        self
+    }
+
+    // Applies to each read, so long streamed generations only time out when the server stalls
+    pub fn read(mut self, timeout: Duration) -> Timeouts {
+        self.read = Some(timeout);
+        self
+    }
+
+    pub fn write(mut self, timeout: Duration) -> Timeouts {
+        self.write = Some(timeout);
+        self
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::OllamaError;
+
+// A function the model may call, described with a JSON schema for its parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: ToolFunction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolFunction {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+}
+
+impl Tool {
+    pub fn function(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+    ) -> Tool {
+        Tool {
+            kind: "function".to_string(),
+            function: ToolFunction {
+                name: name.into(),
+                description: description.into(),
+                parameters,
+            },
+        }
+    }
+
+    // A function whose parameters are the fields of T, described by its JSON schema. Field doc
+    // comments or #[schemars(description = "...")] become their descriptions. Read the arguments back with
+    // ToolCall::arguments::<T>.
+    #[cfg(feature = "schemars")]
+    pub fn from_type<T: schemars::JsonSchema>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Tool {
+        let mut parameters = serde_json::to_value(schemars::schema_for!(T))
+            .expect("a JSON schema always serializes");
+        // Meta fields the model has no use for
+        if let Some(fields) = parameters.as_object_mut() {
+            fields.remove("$schema");
+            fields.remove("title");
+        }
+        Tool::function(name, description, parameters)
+    }
+}
+
+// A call the model wants made, found in the assistant message's `tool_calls`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub function: ToolCallFunction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallFunction {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+impl ToolCall {
+    pub fn name(&self) -> &str {
+        &self.function.name
+    }
+
+    pub fn arguments<T: DeserializeOwned>(&self) -> Result<T, OllamaError> {
+        Ok(serde_json::from_value(self.function.arguments.clone())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_serialization() {
+        let tool = Tool::function(
+            "get_weather",
+            "Get the current weather for a city",
+            serde_json::json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"]
+            }),
+        );
+        let value = serde_json::to_value(&tool).unwrap();
+        assert_eq!(value["type"], "function");
+        assert_eq!(value["function"]["name"], "get_weather");
+        assert_eq!(value["function"]["parameters"]["required"][0], "city");
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_tool_from_type() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Weather {
+            #[schemars(description = "The city to look up")]
+            city: String,
+            days: Option<u8>,
+        }
+
+        let tool = Tool::from_type::<Weather>("get_weather", "Get the forecast for a city");
+        let parameters = &tool.function.parameters;
+        assert_eq!(parameters["type"], "object");
+        assert_eq!(
+            parameters["properties"]["city"]["description"],
+            "The city to look up"
+        );
+        assert_eq!(parameters["required"], serde_json::json!(["city"]));
+        assert!(parameters.get("$schema").is_none());
+        assert!(parameters.get("title").is_none());
+    }
+
+    #[test]
+    fn test_typed_arguments() {
+        #[derive(Deserialize)]
+        struct Weather {
+            city: String,
+        }
+
+        let call: ToolCall = serde_json::from_value(serde_json::json!({
+            "function": { "name": "get_weather", "arguments": { "city": "Tokyo" } }
+        }))
+        .unwrap();
+        assert_eq!(call.name(), "get_weather");
+        assert_eq!(call.arguments::<Weather>().unwrap().city, "Tokyo");
+        assert!(matches!(
+            call.arguments::<Vec<u8>>(),
+            Err(OllamaError::Json(_))
+        ));
+    }
+}
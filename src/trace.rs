@@ -0,0 +1,236 @@
+// Instrumentation around every API call: the client's Metrics, and with the `tracing`
+// feature an "ollama" span with the method, path and model, where the status and token counts
+// are filled in as they become known. Bodies are logged at debug level.
+
+use serde_json::Value;
+
+use crate::{Body, Instant, Metrics, OllamaError, Request, Response};
+
+#[derive(Debug, Clone)]
+pub(crate) struct CallSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    start: Instant,
+    metrics: Option<Metrics>,
+}
+
+impl CallSpan {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(
+        method: &str,
+        path: &str,
+        body: Option<&Body>,
+        metrics: Option<Metrics>,
+    ) -> CallSpan {
+        CallSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "ollama",
+                method,
+                path,
+                model = model(body),
+                status = tracing::field::Empty,
+                prompt_tokens = tracing::field::Empty,
+                completion_tokens = tracing::field::Empty,
+            ),
+            start: Instant::now(),
+            metrics,
+        }
+    }
+
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn request(&self, request: &Request) {
+        #[cfg(feature = "tracing")]
+        if let Some(Body::Json(json)) = &request.body {
+            tracing::debug!(parent: &self.span, body = %String::from_utf8_lossy(json), "request");
+        }
+    }
+
+    pub(crate) fn response(&self, result: &Result<Response, OllamaError>) {
+        let elapsed = self.start.elapsed();
+        if let Some(metrics) = &self.metrics {
+            metrics.request(elapsed, result.is_err());
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            let elapsed_ms = elapsed.as_millis() as u64;
+            match result {
+                Ok(response) => {
+                    self.span.record("status", response.status);
+                    tracing::debug!(parent: &self.span, status = response.status, elapsed_ms, "response");
+                }
+                Err(error) => {
+                    if let Some(status) = error.status() {
+                        self.span.record("status", status);
+                    }
+                    tracing::warn!(parent: &self.span, %error, elapsed_ms, "request failed");
+                }
+            }
+        }
+    }
+
+    // Called with every JSON object the server sends back, the last one of a generation has
+    // the token counts
+    pub(crate) fn reply(&self, value: &Value) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, body = %value, "reply");
+
+        // Native replies count prompt_eval_count/eval_count, the /v1 endpoints report usage
+        let prompt_tokens = value["prompt_eval_count"]
+            .as_u64()
+            .or(value["usage"]["prompt_tokens"].as_u64());
+        let completion_tokens = value["eval_count"]
+            .as_u64()
+            .or(value["usage"]["completion_tokens"].as_u64());
+        if prompt_tokens.is_none() && completion_tokens.is_none() {
+            return;
+        }
+
+        if let Some(metrics) = &self.metrics {
+            let model = value["model"].as_str().unwrap_or_default();
+            metrics.tokens(
+                model,
+                prompt_tokens.unwrap_or(0),
+                completion_tokens.unwrap_or(0),
+            );
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            if let Some(tokens) = prompt_tokens {
+                self.span.record("prompt_tokens", tokens);
+            }
+            if let Some(tokens) = completion_tokens {
+                self.span.record("completion_tokens", tokens);
+            }
+            tracing::info!(
+                parent: &self.span,
+                prompt_tokens,
+                completion_tokens,
+                elapsed_ms = self.start.elapsed().as_millis() as u64,
+                "finished"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn model(body: Option<&Body>) -> Option<String> {
+    match body {
+        Some(Body::Json(json)) => serde_json::from_slice::<Value>(json)
+            .ok()
+            .and_then(|v| v["model"].as_str().map(str::to_string)),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    };
+
+    use serde_json::json;
+    use tracing::{
+        Event, Metadata, Subscriber,
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+    };
+
+    use crate::{GenerateRequest, MockOllama};
+
+    // Writes every span field and event as "name=value" lines
+    #[derive(Default)]
+    struct Recorder {
+        next_id: AtomicU64,
+        lines: Mutex<Vec<String>>,
+    }
+
+    struct Fields<'a>(&'a mut Vec<String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for &'static Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut Fields(&mut self.lines.lock().unwrap()));
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut Fields(&mut self.lines.lock().unwrap()));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut Fields(&mut self.lines.lock().unwrap()));
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_generate_is_traced() {
+        let mock = MockOllama::new().respond(
+            "POST",
+            "/api/generate",
+            200,
+            json!({
+                "model": "llama3",
+                "response": "Hi",
+                "done": true,
+                "prompt_eval_count": 5,
+                "eval_count": 2
+            }),
+        );
+        let ollama = mock.client();
+
+        let recorder: &'static Recorder = Box::leak(Box::default());
+        tracing::subscriber::with_default(recorder, || {
+            ollama
+                .generate(&GenerateRequest::new("llama3", "Hello"))
+                .unwrap();
+        });
+
+        let lines = recorder.lines.lock().unwrap();
+        for expected in [
+            "path=\"/api/generate\"",
+            "model=\"llama3\"",
+            "status=200",
+            "prompt_tokens=5",
+            "completion_tokens=2",
+            "message=finished",
+        ] {
+            assert!(
+                lines.iter().any(|l| l == expected),
+                "{} in {:?}",
+                expected,
+                lines
+            );
+        }
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("body=") && l.contains("Hello"))
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Error, ErrorKind, Read},
+    path::PathBuf,
+};
+
+use crate::{
+    CancellationToken, ConnectionPool, Host, OllamaError, Proxy, Timeouts, connection::Connection,
+    http,
+};
+
+// An API call as handed to a transport, before any HTTP framing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    // e.g. "/api/generate"
+    pub path: String,
+    // Authorization and custom headers, the transport adds its own framing headers
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Body>,
+    // Transports should abort the call when it fires, see CancellationToken
+    pub cancel: Option<CancellationToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Body {
+    Json(Vec<u8>),
+    // Streamed from disk as application/octet-stream, for blob uploads
+    File(PathBuf),
+}
+
+// The status and (possibly streamed) body of an API call
+pub struct Response {
+    pub status: u16,
+    body: Box<dyn Read + Send>,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Read + Send + 'static) -> Response {
+        Response {
+            status,
+            body: Box::new(body),
+        }
+    }
+
+    pub fn into_body(self) -> Box<dyn Read + Send> {
+        self.body
+    }
+
+    pub(crate) fn text(mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        self.body.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    // NDJSON streams: yields each non-empty line as soon as it arrives
+    pub(crate) fn lines(self) -> impl Iterator<Item = Result<String, Error>> {
+        BufReader::new(self.body)
+            .lines()
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .finish_non_exhaustive()
+    }
+}
+
+// Carries requests to the server. Implement it to record, replay or instrument calls, or to
+// reach Ollama over something other than HTTP.
+pub trait Transport: Send + Sync {
+    fn send(&self, request: &Request) -> Result<Response, OllamaError>;
+}
+
+impl fmt::Debug for dyn Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dyn Transport")
+    }
+}
+
+// The default transport: HTTP/1.1 over TCP, TLS or a unix socket, with kept-alive connections
+// reused from the pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpTransport {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    // See Host::base_path
+    pub base_path: String,
+    pub timeouts: Timeouts,
+    pub proxy: Option<Proxy>,
+    pub pool: ConnectionPool,
+}
+
+impl HttpTransport {
+    pub fn new(host: Host) -> HttpTransport {
+        HttpTransport {
+            proxy: Proxy::from_env(&host),
+            scheme: host.scheme,
+            host: host.host,
+            port: host.port,
+            base_path: host.base_path,
+            timeouts: Timeouts::default(),
+            pool: ConnectionPool::default(),
+        }
+    }
+
+    fn proxy(&self) -> Option<&Proxy> {
+        self.proxy.as_ref().filter(|_| self.scheme != "unix")
+    }
+
+    // Value for the Host header, the port is left out when it is the scheme's default
+    pub(crate) fn host_header(&self) -> String {
+        if self.scheme == "unix" {
+            return "localhost".to_string();
+        }
+
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+
+        match (self.scheme.as_str(), self.port) {
+            ("http", 80) | ("https", 443) => host,
+            _ => format!("{}:{}", host, self.port),
+        }
+    }
+}
+
+impl HttpTransport {
+    // Connections through different proxies can't stand in for each other
+    fn pool_key(&self) -> String {
+        let key = format!("{}://{}:{}", self.scheme, self.host, self.port);
+        match self.proxy() {
+            Some(proxy) => format!("{} via {}:{}", key, proxy.host, proxy.port),
+            None => key,
+        }
+    }
+
+    fn exchange(
+        &self,
+        mut stream: Connection,
+        key: String,
+        request: &Request,
+    ) -> Result<Response, Error> {
+        // The cancel hook stays registered until the body is done with the connection
+        let guard = match &request.cancel {
+            Some(token) => Some(token.register(stream.closer()?)),
+            None => None,
+        };
+
+        // Plain http proxies expect the full URL as the request target, and the proxy
+        // credentials with every request. Over a CONNECT tunnel they went with the CONNECT.
+        let host = self.host_header();
+        let mut headers = request.headers.clone();
+        let path = format!("{}{}", self.base_path, request.path);
+        let target = match self.proxy() {
+            Some(proxy) if self.scheme == "http" => {
+                if let Some(auth) = &proxy.auth {
+                    headers.push(("Proxy-Authorization".to_string(), auth.header_value()));
+                }
+                format!("http://{}{}", host, path)
+            }
+            _ => path,
+        };
+
+        http::write_request(
+            &mut stream,
+            &request.method,
+            &host,
+            &target,
+            &headers,
+            request.body.as_ref(),
+            self.pool.is_enabled(),
+        )?;
+
+        let pool = self.pool.clone();
+        let release = Box::new(move |stream| {
+            drop(guard);
+            pool.put(&key, stream);
+        });
+        http::read_response(stream, &request.method, Some(release)).map_err(delivered)
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send(&self, request: &Request) -> Result<Response, OllamaError> {
+        let key = self.pool_key();
+        if let Some(stream) = self.pool.take(&key) {
+            stream.set_timeouts(&self.timeouts)?;
+            match self.exchange(stream, key.clone(), request) {
+                // The server closed the idle connection in the meantime, go again on a new one
+                Err(e)
+                    if is_stale(&e)
+                        && !request.cancel.as_ref().is_some_and(|c| c.is_cancelled()) => {}
+                result => return Ok(result?),
+            }
+        }
+
+        let stream = Connection::open(
+            &self.scheme,
+            &self.host,
+            self.port,
+            &self.timeouts,
+            self.proxy(),
+        )?;
+        Ok(self.exchange(stream, key, request)?)
+    }
+}
+
+// A failure reading the reply, after the server got the request and may have run it. HostPool
+// won't send such a request to another server.
+#[derive(Debug)]
+struct Delivered(Error);
+
+impl fmt::Display for Delivered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for Delivered {}
+
+fn delivered(error: Error) -> Error {
+    Error::new(error.kind(), Delivered(error))
+}
+
+pub(crate) fn was_delivered(error: &Error) -> bool {
+    error.get_ref().is_some_and(|e| e.is::<Delivered>())
+}
+
+fn is_stale(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::UnexpectedEof
+            | ErrorKind::BrokenPipe
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+    )
+}
@@ -0,0 +1,135 @@
+use serde::de::DeserializeOwned;
+
+use crate::{ChatMessage, ChatRequest, Ollama, OllamaError};
+
+impl Ollama {
+    // prompt_structured that doesn't give up on the first bad reply: when the output doesn't
+    // deserialize into T, the model is shown its reply and the error and asked again, up to
+    // `repairs` more times. Fails with ValidationFailed once it runs out of tries.
+    pub fn prompt_validated<T: DeserializeOwned + schemars::JsonSchema>(
+        &self,
+        model: impl Into<String>,
+        prompt: impl Into<String>,
+        repairs: usize,
+    ) -> Result<T, OllamaError> {
+        self.prompt_validated_with(model, prompt, repairs, |_: &T| Ok(()))
+    }
+
+    // prompt_validated with checks the schema can't express, e.g. value ranges. `validate`
+    // explains what's wrong in words the model can act on.
+    pub fn prompt_validated_with<T: DeserializeOwned + schemars::JsonSchema>(
+        &self,
+        model: impl Into<String>,
+        prompt: impl Into<String>,
+        repairs: usize,
+        validate: impl Fn(&T) -> Result<(), String>,
+    ) -> Result<T, OllamaError> {
+        let model = model.into();
+        let schema = serde_json::to_value(schemars::schema_for!(T))?;
+        let mut messages = vec![ChatMessage::user(prompt)];
+
+        let mut attempt = 0;
+        loop {
+            let request = ChatRequest::new(model.clone(), messages.clone()).format(schema.clone());
+            let output = self.chat(&request)?.message.content;
+            let message = match serde_json::from_str::<T>(&output) {
+                Ok(value) => match validate(&value) {
+                    Ok(()) => return Ok(value),
+                    Err(message) => message,
+                },
+                Err(error) => error.to_string(),
+            };
+            if attempt == repairs {
+                return Err(OllamaError::ValidationFailed {
+                    output,
+                    attempts: repairs + 1,
+                    message,
+                });
+            }
+            attempt += 1;
+            messages.push(ChatMessage::assistant(output));
+            messages.push(ChatMessage::user(format!(
+                "That reply is invalid: {}. Answer again with only the corrected JSON.",
+                message
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockOllama;
+    use serde::Deserialize;
+    use serde_json::{Value, json};
+
+    #[derive(Debug, PartialEq, Deserialize, schemars::JsonSchema)]
+    struct City {
+        name: String,
+        population: u64,
+    }
+
+    fn reply(content: &str) -> Value {
+        json!({ "model": "llama3", "message": { "role": "assistant", "content": content }, "done": true })
+    }
+
+    #[test]
+    fn test_prompt_validated() {
+        let mock = MockOllama::new()
+            .respond("POST", "/api/chat", 200, reply(r#"{"name":"Oslo"}"#))
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                reply(r#"{"name":"Oslo","population":0}"#),
+            )
+            .respond(
+                "POST",
+                "/api/chat",
+                200,
+                reply(r#"{"name":"Oslo","population":709000}"#),
+            );
+        let ollama = mock.client();
+        let positive = |city: &City| match city.population {
+            0 => Err("population must be above 0".to_string()),
+            _ => Ok(()),
+        };
+
+        let city: City = ollama
+            .prompt_validated_with("llama3", "Biggest city in Norway?", 2, positive)
+            .unwrap();
+        assert_eq!(city.population, 709000);
+
+        // The last request carries both failed replies and what was wrong with them
+        let body = mock.request_json(3);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 5);
+        assert!(
+            messages[2]["content"]
+                .as_str()
+                .unwrap()
+                .contains("missing field `population`")
+        );
+        assert!(
+            messages[4]["content"]
+                .as_str()
+                .unwrap()
+                .contains("population must be above 0")
+        );
+        assert_eq!(
+            body["format"]["properties"]["population"]["type"],
+            "integer"
+        );
+
+        let mock = MockOllama::new().respond("POST", "/api/chat", 200, reply("Oslo"));
+        let error = mock
+            .client()
+            .prompt_validated::<City>("llama3", "Biggest city in Norway?", 1)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            OllamaError::ValidationFailed { attempts: 2, ref output, .. } if output == "Oslo"
+        ));
+        assert_eq!(mock.requests().len(), 1 + 2);
+    }
+}
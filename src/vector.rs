@@ -0,0 +1,85 @@
+// Plain slice math for comparing embeddings, enough for brute-force semantic search.
+// The functions panic when two vectors differ in length, that means they came from different
+// models and comparing them is meaningless.
+
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "vectors differ in length");
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+pub fn norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+// Scales `v` to length 1 in place, a zero vector stays as it is. The dot product of
+// normalized vectors is their cosine similarity, which saves work when comparing many.
+pub fn normalize(v: &mut [f32]) {
+    let norm = norm(v);
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+// 1 for the same direction, 0 for unrelated, -1 for opposite. 0 when either is a zero vector.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        return 0.0;
+    }
+    dot(a, b) / norms
+}
+
+// Indices and cosine similarities of the `k` vectors closest to `query`, best first
+pub fn top_k<V: AsRef<[f32]>>(query: &[f32], vectors: &[V], k: usize) -> Vec<(usize, f32)> {
+    let mut scored: Vec<(usize, f32)> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i, cosine_similarity(query, v.as_ref())))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), 32.0);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+        assert!((cosine_similarity(&[1.0, 1.0], &[-1.0, -1.0]) + 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+
+        let mut v = [3.0, 4.0];
+        normalize(&mut v);
+        assert!((v[0] - 0.6).abs() < 1e-6 && (v[1] - 0.8).abs() < 1e-6);
+        assert!((norm(&v) - 1.0).abs() < 1e-6);
+        let mut zero = [0.0, 0.0];
+        normalize(&mut zero);
+        assert_eq!(zero, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_top_k() {
+        let vectors = vec![
+            vec![0.0, 1.0],
+            vec![1.0, 0.1],
+            vec![-1.0, 0.0],
+            vec![1.0, 1.0],
+        ];
+        let nearest = top_k(&[1.0, 0.0], &vectors, 2);
+        let indices: Vec<usize> = nearest.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, [1, 3]);
+        assert!(nearest[0].1 > nearest[1].1);
+        assert_eq!(top_k(&[1.0, 0.0], &vectors, 10).len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "vectors differ in length")]
+    fn test_length_mismatch() {
+        dot(&[1.0], &[1.0, 2.0]);
+    }
+}
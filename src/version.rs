@@ -0,0 +1,186 @@
+use std::{cmp::Ordering, fmt};
+
+// A server version as reported by /api/version, e.g. "0.5.1" or "0.1.32-rc1"
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    // e.g. "rc1", sorts before the release it precedes
+    pub pre: Option<String>,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+
+    pub fn parse(version: &str) -> Option<Version> {
+        let version = version.trim().trim_start_matches('v');
+        // Build metadata doesn't take part in comparisons
+        let version = version.split('+').next().unwrap_or_default();
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (version, None),
+        };
+
+        let mut parts = core.split('.').map(|p| p.parse::<u64>());
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Version {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    // Builds from source report 0.0.0, assume they are recent enough for everything
+    pub fn is_dev(&self) -> bool {
+        self.major == 0 && self.minor == 0 && self.patch == 0 && self.pre.is_none()
+    }
+
+    pub fn at_least(&self, major: u64, minor: u64, patch: u64) -> bool {
+        self.is_dev() || *self >= Version::new(major, minor, patch)
+    }
+
+    // /api/chat
+    pub fn supports_chat(&self) -> bool {
+        self.at_least(0, 1, 14)
+    }
+
+    // /api/ps
+    pub fn supports_running_models(&self) -> bool {
+        self.at_least(0, 1, 38)
+    }
+
+    // `tools` on chat requests
+    pub fn supports_tools(&self) -> bool {
+        self.at_least(0, 3, 0)
+    }
+
+    // A JSON schema as `format`, see prompt_structured
+    pub fn supports_structured_outputs(&self) -> bool {
+        self.at_least(0, 5, 0)
+    }
+
+    // `think` on generate and chat requests
+    pub fn supports_thinking(&self) -> bool {
+        self.at_least(0, 9, 0)
+    }
+
+    // `logprobs` and `top_logprobs` on generate and chat requests
+    pub fn supports_logprobs(&self) -> bool {
+        self.at_least(0, 12, 11)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Version) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => compare_pre(a, b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Dot separated identifiers as in semver: numeric ones compare as numbers and sort before
+// alphanumeric ones
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_order() {
+        let rc = Version::parse("0.1.32-rc1").unwrap();
+        assert_eq!(rc.pre.as_deref(), Some("rc1"));
+        assert_eq!(rc.to_string(), "0.1.32-rc1");
+        assert_eq!(Version::parse("v0.5.1"), Some(Version::new(0, 5, 1)));
+        assert_eq!(Version::parse("0.5"), Some(Version::new(0, 5, 0)));
+        assert_eq!(Version::parse("0.5.x"), None);
+        assert_eq!(Version::parse(""), None);
+
+        assert!(rc < Version::new(0, 1, 32));
+        assert!(Version::new(0, 1, 32) < Version::new(0, 1, 100));
+        assert!(Version::parse("0.2.0-rc.2").unwrap() < Version::parse("0.2.0-rc.10").unwrap());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let old = Version::new(0, 1, 10);
+        assert!(!old.supports_chat());
+        assert!(!old.supports_tools());
+
+        let tools = Version::new(0, 3, 0);
+        assert!(tools.supports_chat());
+        assert!(tools.supports_tools());
+        assert!(!tools.supports_structured_outputs());
+        assert!(!tools.supports_thinking());
+        assert!(!Version::new(0, 12, 10).supports_logprobs());
+        assert!(Version::new(0, 12, 11).supports_logprobs());
+        assert!(
+            !Version::parse("0.5.0-rc1")
+                .unwrap()
+                .supports_structured_outputs()
+        );
+
+        assert!(
+            Version::parse("0.0.0")
+                .unwrap()
+                .supports_structured_outputs()
+        );
+    }
+}